@@ -3,6 +3,7 @@ use tokio::time::sleep;
 use std::time::Duration;
 use feeder::websocket::WebSocket;
 use feeder::websocket::BinanceWebSocketClient;
+use feeder::reconnect::{FaultPoint, InjectedFault, ReconnectPolicy};
 
 
 /* 
@@ -11,7 +12,7 @@ use feeder::websocket::BinanceWebSocketClient;
 */
 
 #[tokio::main]
-async fn main() -> Result<(), Box<dyn Error + Send>> {
+async fn main() -> Result<(), Box<dyn Error>> {
     // 测试连接单一流，示例订阅 bnbusdt@aggTrade
     let streams = vec!["bnbusdt@aggTrade"];
     
@@ -26,8 +27,22 @@ async fn main() -> Result<(), Box<dyn Error + Send>> {
     // client.subscribe(vec!["adausdt@aggTrade"]).await?;
     client.subscribe(vec!["adausdt@depth5@100ms"]).await?;
     
+    // 指数退避重连，最多连续尝试 5 次
+    let mut policy = ReconnectPolicy::default();
+    policy.max_attempts = Some(5);
+    policy.set_give_up_callback(|attempts, reason| {
+        eprintln!("放弃重连（{} 次）：{}", attempts, reason);
+    });
+    client.set_reconnect_policy(policy);
+
+    // 故障注入：收到第 20 条消息时模拟服务端断开，第 1 次重连人为失败，用于验证退避与重订阅
+    client.set_fault_injector(|point| match point {
+        FaultPoint::BeforeRead { messages_received: 20 } => Some(InjectedFault::Close),
+        FaultPoint::BeforeReconnect { attempt: 1 } => Some(InjectedFault::FailReconnect),
+        _ => None,
+    });
+
     // 开始监听（注意：此处会持续打印接收到的消息）
-    // 如果需要测试断线重连，可关闭连接或等待超过24小时后自动断连
     client.listen_loop().await?;
     
     Ok(())
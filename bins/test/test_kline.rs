@@ -0,0 +1,45 @@
+// test_kline.rs
+// K 线订阅示例：订阅多个周期，只处理已完结的 K 线
+use std::error::Error;
+
+use app::runtime::Runtime;
+use common::exchange::Exchange;
+use event_engine::event::{EventPayload, EventType, KlineInterval};
+use event_engine::event_dispatcher::EventData;
+
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn Error>> {
+    let mut app = Runtime::new(Exchange::Binance, 500).await?;
+
+    let streams: Vec<String> = [KlineInterval::Minute1, KlineInterval::Minute15]
+        .iter()
+        .map(|interval| interval.stream_name("btcusdt"))
+        .collect();
+    app.subscribe(streams.iter().map(|s| s.as_str()).collect()).await?;
+
+    app.register_event_callback(EventType::Kline, Box::new(|event: &EventData| {
+        let EventPayload::Kline(kline) = &event.data else {
+            return;
+        };
+        let k = &kline.kline;
+        if !k.is_closed {
+            // 未完结的 K 线每秒推送多次，这里只关心收盘后的结果
+            return;
+        }
+        println!(
+            "[{}] {} {} O:{} H:{} L:{} C:{} V:{} 笔数:{}",
+            kline.stream,
+            k.symbol,
+            k.interval.as_str(),
+            k.open,
+            k.high,
+            k.low,
+            k.close,
+            k.volume,
+            k.trade_count,
+        );
+    }));
+
+    app.start_service().await?;
+    Ok(())
+}
@@ -9,7 +9,7 @@ use tokio::runtime::Runtime;
 use event_engine::event::{EventType, EventPayload, DepthEvent};
 use event_engine::event_dispatcher::{AsyncQueueEventDispatcher, EventDispatcher, EventData};
 
-use market_agent::market_agent::MarketAgent;
+use market_agent::market_agent::{EventEmission, MarketAgent};
 use market_agent::binance_market_agent::BinanceMarketAgent;
 
 use feeder::websocket::WebSocket;
@@ -45,10 +45,10 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
 
     // 创建一个共享的订单簿引擎实例，针对 "BTCUSDT"
     let orderbook_engine = Arc::new(Mutex::new(OrderBookEngine::new("BTCUSDT")));
-    // 注册 Depth 事件的回调：dispatcher 收到深度事件后调用订单簿的 push_update
+    // 注册 BookDelta 事件的回调：dispatcher 收到归一化的深度增量后调用订单簿的 push_update
     {
         let engine_clone = Arc::clone(&orderbook_engine);
-        async_dispatcher.register(EventType::Depth, Box::new(move |event: &EventData| {
+        async_dispatcher.register(EventType::BookDelta, Box::new(move |event: &EventData| {
             // 锁定订单簿引擎并调用 push_update，将 event 数据传入
             let mut engine = engine_clone.lock().unwrap();
             // println!("收到深度事件: {:?}", event);
@@ -88,6 +88,8 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
 
     // 创建 market agent，将 ws_client 和 dispatcher 的 producer 传入
     let mut market_agent = BinanceMarketAgent::new(ws_client, producer);
+    // 订单簿只处理归一化事件
+    market_agent.set_event_emission(EventEmission::Normalized);
 
     // 开启一个新线程运行 market_agent（异步调用）
     thread::spawn(move || {
@@ -0,0 +1,53 @@
+// test_rest_client.rs
+// REST 行情客户端示例：按时间区间分页获取归集成交、K 线、资金费率与持仓量历史，
+// 每次请求后打印本分钟已用权重
+use std::error::Error;
+
+use event_engine::event::KlineInterval;
+use rest_client::{BinanceRestClient, RestMarket};
+
+fn now_ms() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .expect("Time went backwards")
+        .as_millis() as u64
+}
+
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn Error>> {
+    let rest = BinanceRestClient::shared(RestMarket::UsdFutures);
+    let now = now_ms();
+
+    let depth = rest.depth("BTCUSDT", 100).await?;
+    println!("深度快照 lastUpdateId={} 买一={:?} 卖一={:?} 已用权重={}",
+        depth.last_update_id, depth.bids.first(), depth.asks.first(), rest.used_weight());
+
+    // 最近 2 分钟的归集成交，跨多页
+    let trades = rest.agg_trades_by_time("BTCUSDT", now - 2 * 60 * 1000, now).await?;
+    if let (Some(first), Some(last)) = (trades.first(), trades.last()) {
+        println!("归集成交 {} 笔，id {}-{} 已用权重={}", trades.len(), first.agg_trade_id, last.agg_trade_id, rest.used_weight());
+    }
+
+    // 最近 2 天的 1 分钟 K 线，超过单页上限，自动翻页
+    let klines = rest.klines_range("BTCUSDT", KlineInterval::Minute1, now - 2 * 24 * 60 * 60 * 1000, now).await?;
+    if let Some(last) = klines.last() {
+        println!("K 线 {} 根，最新收盘价 {} 已用权重={}", klines.len(), last.close, rest.used_weight());
+    }
+
+    let rates = rest.funding_rates_range("BTCUSDT", now - 7 * 24 * 60 * 60 * 1000, now).await?;
+    for rate in &rates {
+        println!("资金费率 {} {} 标记价格 {}", rate.funding_time, rate.funding_rate, rate.mark_price);
+    }
+
+    let oi = rest.open_interest("BTCUSDT").await?;
+    println!("当前持仓量 {} @ {}", oi.open_interest, oi.time);
+    let hist = rest.open_interest_hist_range("BTCUSDT", KlineInterval::Hour1, now - 24 * 60 * 60 * 1000, now).await?;
+    println!("持仓量历史 {} 条，已用权重={}", hist.len(), rest.used_weight());
+
+    // 现货没有资金费率接口
+    let spot = BinanceRestClient::shared(RestMarket::Spot);
+    if let Err(e) = spot.funding_rates("BTCUSDT", None, None, 10).await {
+        println!("预期的错误: {}", e);
+    }
+    Ok(())
+}
@@ -7,19 +7,28 @@ use app::runtime::Runtime;
 use event_engine::event::{EventType};
 use event_engine::event_dispatcher::EventData;
 use common::exchange::Exchange;
+use market_agent::market_agent::EventEmission;
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn Error>> {
     // 创建 Runtime（核心系统）
-    let mut app = Runtime::new(Exchange::Binance, 200).await?;
+    // 改为 Exchange::BinanceSpot 即切换到现货行情与现货深度同步规则
+    let exchange = Exchange::Binance;
+    let mut app = Runtime::new(exchange, 200).await?;
+    // 订单簿只处理归一化的 BookDelta / BookSnapshot 事件
+    app.set_event_emission(EventEmission::Normalized)?;
     app.subscribe(vec!["btcusdt@depth@100ms"]).await?;
 
     // 创建订单簿模块（作为独立应用层模块），这里用 Arc<Mutex<>> 包装以便跨线程共享
-    let orderbook_engine = Arc::new(Mutex::new(OrderBookEngine::new("BTCUSDT")));
+    let orderbook_engine = Arc::new(Mutex::new(OrderBookEngine::for_exchange(exchange, "BTCUSDT")));
 
     // 注册订单簿模块的更新回调，打印订单簿状态
     {
         let mut engine = orderbook_engine.lock().unwrap();
+        // 按品种的最小价格变动对齐价格档位
+        if let Some(instrument) = app.instrument("BTCUSDT") {
+            engine.set_instrument(instrument);
+        }
 
         engine.register_callback(|order_book| {
             let spread = order_book.best_bid()
@@ -37,10 +46,10 @@ async fn main() -> Result<(), Box<dyn Error>> {
     }
 
 
-    // 注册订单簿的更新函数作为回调到 Runtime 中，订阅 Depth 事件
-    {
+    // 注册订单簿的更新函数作为回调到 Runtime 中，订阅归一化的 BookDelta / BookSnapshot 事件
+    for event_type in [EventType::BookDelta, EventType::BookSnapshot] {
         let orderbook_clone = Arc::clone(&orderbook_engine);
-        app.register_event_callback(EventType::Depth, Box::new(move |event: &EventData| {
+        app.register_event_callback(event_type, Box::new(move |event: &EventData| {
             let mut engine = orderbook_clone.lock().unwrap();
             if let Err(e) = engine.push_update(event.clone()) {
                 eprintln!("订单簿更新失败: {}", e);
@@ -48,6 +57,15 @@ async fn main() -> Result<(), Box<dyn Error>> {
         }));
     }
 
+    // 连接断开、静默或重连后使订单簿失效，由引擎的重新同步任务重新获取快照
+    for event_type in [EventType::Disconnected, EventType::Stale, EventType::Resubscribed] {
+        let orderbook_clone = Arc::clone(&orderbook_engine);
+        app.register_event_callback(event_type, Box::new(move |event: &EventData| {
+            orderbook_clone.lock().unwrap().on_connection_event(event);
+        }));
+    }
+    OrderBookEngine::spawn_resync_task(Arc::clone(&orderbook_engine));
+
     // 初始化订单簿
     {
         let mut engine = orderbook_engine.lock().unwrap();
@@ -0,0 +1,126 @@
+// test_user_data.rs
+// 用户数据流示例：在本地启动 listenKey REST 与 WebSocket 的简易 mock，
+// UserDataAgent 连接 mock 后应依次收到订单、账户、追加保证金事件，
+// 随后 mock 推送 listenKeyExpired，代理重新创建 listenKey 并重连
+use std::error::Error;
+use std::time::Duration;
+
+use event_engine::event::{EventPayload, EventType};
+use event_engine::event_dispatcher::AsyncQueueEventDispatcher;
+use futures_util::{SinkExt, StreamExt};
+use market_agent::user_data_agent::{UserDataAgent, UserDataConfig};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpListener;
+use tokio_tungstenite::tungstenite::Message;
+
+const LISTEN_KEY: &str = "pqia91ma19a5s61cv6a81va65sdf19v8a65a1a5s61cv6a81va65sdf19v8a65a1";
+
+const ORDER_TRADE_UPDATE: &str = r#"{"e":"ORDER_TRADE_UPDATE","E":1568879465651,"T":1568879465650,"o":{"s":"BTCUSDT","c":"TEST","S":"SELL","o":"LIMIT","f":"GTC","q":"0.001","p":"65000","ap":"65000","sp":"0","x":"TRADE","X":"FILLED","i":8886774,"l":"0.001","z":"0.001","L":"65000","N":"USDT","n":"0.026","T":1568879465650,"t":12345,"b":"0","a":"0","m":true,"R":false,"wt":"CONTRACT_PRICE","ot":"LIMIT","ps":"BOTH","cp":false,"rp":"0"}}"#;
+const ACCOUNT_UPDATE: &str = r#"{"e":"ACCOUNT_UPDATE","E":1564745798939,"T":1564745798938,"a":{"m":"ORDER","B":[{"a":"USDT","wb":"122624.12345678","cw":"100.12345678","bc":"50.12345678"}],"P":[{"s":"BTCUSDT","pa":"-0.001","ep":"65000","bep":"65026","cr":"200","up":"0","mt":"cross","iw":"0","ps":"BOTH"}]}}"#;
+const MARGIN_CALL: &str = r#"{"e":"MARGIN_CALL","E":1587727187525,"cw":"3.16812045","p":[{"s":"ETHUSDT","ps":"LONG","pa":"1.327","mt":"CROSSED","iw":"0","mp":"187.17127","up":"-1.166074","mm":"1.614445"}]}"#;
+
+/// listenKey 接口 mock：POST 返回固定的 listenKey，PUT / DELETE 返回空对象
+async fn run_rest_mock(listener: TcpListener) {
+    loop {
+        let Ok((mut socket, _)) = listener.accept().await else {
+            return;
+        };
+        tokio::spawn(async move {
+            let mut buf = [0u8; 4096];
+            let n = socket.read(&mut buf).await.unwrap_or(0);
+            let request = String::from_utf8_lossy(&buf[..n]).to_string();
+            let method = request.split_whitespace().next().unwrap_or("").to_string();
+            println!("[REST mock] {}", request.lines().next().unwrap_or(""));
+            let body = if method == "POST" {
+                format!(r#"{{"listenKey":"{}"}}"#, LISTEN_KEY)
+            } else {
+                "{}".to_string()
+            };
+            let response = format!(
+                "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                body.len(),
+                body
+            );
+            let _ = socket.write_all(response.as_bytes()).await;
+        });
+    }
+}
+
+/// 用户数据流 mock：第一次连接推送三类事件后推送 listenKeyExpired，之后的连接保持空闲
+async fn run_ws_mock(listener: TcpListener) {
+    let mut connections = 0;
+    loop {
+        let Ok((socket, _)) = listener.accept().await else {
+            return;
+        };
+        connections += 1;
+        let first = connections == 1;
+        tokio::spawn(async move {
+            let Ok(mut ws) = tokio_tungstenite::accept_async(socket).await else {
+                return;
+            };
+            if first {
+                for msg in [ORDER_TRADE_UPDATE, ACCOUNT_UPDATE, MARGIN_CALL] {
+                    let _ = ws.send(Message::Text(msg.to_string())).await;
+                    tokio::time::sleep(Duration::from_millis(200)).await;
+                }
+                let expired = format!(r#"{{"e":"listenKeyExpired","E":1576653824250,"listenKey":"{}"}}"#, LISTEN_KEY);
+                let _ = ws.send(Message::Text(expired)).await;
+            }
+            while let Some(Ok(_)) = ws.next().await {}
+        });
+    }
+}
+
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn Error>> {
+    let rest_listener = TcpListener::bind("127.0.0.1:0").await?;
+    let ws_listener = TcpListener::bind("127.0.0.1:0").await?;
+    let rest_addr = rest_listener.local_addr()?.to_string();
+    let ws_addr = ws_listener.local_addr()?.to_string();
+    tokio::spawn(run_rest_mock(rest_listener));
+    tokio::spawn(run_ws_mock(ws_listener));
+
+    let dispatcher = AsyncQueueEventDispatcher::new(100);
+    let (producer, mut consumer) = dispatcher.split();
+    consumer.register(EventType::OrderUpdate, Box::new(|event| {
+        if let EventPayload::OrderUpdate(update) = &event.data {
+            let o = &update.order;
+            println!("[订单] {} {} {} {}@{} 状态:{} 成交:{}", o.symbol, o.side, o.execution_type, o.last_filled_qty, o.last_filled_price, o.status, o.is_fill());
+        }
+    }));
+    consumer.register(EventType::AccountUpdate, Box::new(|event| {
+        if let EventPayload::AccountUpdate(update) = &event.data {
+            for b in &update.account.balances {
+                println!("[余额] {} 钱包余额:{} 变化:{}", b.asset, b.wallet_balance, b.balance_change);
+            }
+            for p in &update.account.positions {
+                println!("[持仓] {} 数量:{} 开仓价:{}", p.symbol, p.position_amount, p.entry_price);
+            }
+        }
+    }));
+    consumer.register(EventType::MarginCall, Box::new(|event| {
+        if let EventPayload::MarginCall(call) = &event.data {
+            for p in &call.positions {
+                println!("[追加保证金] {} {} 维持保证金:{}", p.symbol, p.position_side, p.maintenance_margin);
+            }
+        }
+    }));
+    std::thread::spawn(move || loop {
+        consumer.process();
+    });
+
+    let mut agent = UserDataAgent::new(UserDataConfig::local("test-api-key", &rest_addr, &ws_addr), producer);
+    let stop = agent.stop_handle();
+    let handle = tokio::spawn(async move {
+        if let Err(e) = agent.start().await {
+            eprintln!("用户数据流代理异常退出: {}", e);
+        }
+    });
+
+    tokio::time::sleep(Duration::from_secs(3)).await;
+    stop.stop();
+    handle.await?;
+    println!("测试结束");
+    Ok(())
+}
@@ -7,6 +7,8 @@ use std::sync::{Arc, RwLock};
 use crate::types::WatchedQtySet;
 use chrono::Duration;
 use crate::types::TradeHistory;
+use feeder::endpoint::WsEndpoint;
+use common::instrument::InstrumentRegistry;
 
 /// 从 config.toml 中加载配置
 pub static CONFIG: Lazy<Config> = Lazy::new(|| {
@@ -29,6 +31,48 @@ pub struct Config {
     pub backup_path: String,
 
     pub telegram: TelegramConfig,
+
+    /// WebSocket 端点（可选），未配置时使用 U 本位合约正式环境
+    /// 例如：
+    /// [endpoint]
+    /// base_url = "127.0.0.1:9001"
+    /// path_style = "binance"
+    /// tls = false
+    #[serde(default)]
+    pub endpoint: Option<WsEndpoint>,
+
+    /// 强平聚集统计（可选）
+    #[serde(default)]
+    pub liquidation: LiquidationConfig,
+
+    /// 品种元数据缓存文件，获取 exchangeInfo 失败时从这里加载
+    #[serde(default = "default_instrument_cache_path")]
+    pub instrument_cache_path: String,
+}
+
+fn default_instrument_cache_path() -> String {
+    "instruments.json".to_string()
+}
+
+/// 强平聚集：同一方向相邻强平间隔不超过 cluster_gap_secs 视为同一簇，
+/// 名义价值合计达到 cluster_min_notional 才会报告
+#[derive(Debug, Deserialize)]
+#[serde(default)]
+pub struct LiquidationConfig {
+    pub cluster_gap_secs: i64,
+    pub cluster_min_notional: f64,
+    /// 每个 symbol 保留多少条强平记录
+    pub history_max_len: usize,
+}
+
+impl Default for LiquidationConfig {
+    fn default() -> Self {
+        Self {
+            cluster_gap_secs: 60,
+            cluster_min_notional: 100_000.0,
+            history_max_len: 5000,
+        }
+    }
 }
 
 #[derive(Debug, Deserialize)]
@@ -76,11 +120,20 @@ pub fn get_push_interval_enum() -> PushInterval {
     PushInterval::from_str(&CONFIG.push_interval).expect("无效的 push_interval 配置")
 }
 
+/// 获取 WebSocket 端点配置
+pub fn get_ws_endpoint() -> WsEndpoint {
+    CONFIG.endpoint.clone().unwrap_or_default()
+}
+
 /// 获取 qty 集合（通常用于过滤）
-pub fn get_watched_qty_set() -> WatchedQtySet {
+/// 数量按品种精度规范化（"7" -> "7.000"），与交易所推送的数量字符串一致
+pub fn get_watched_qty_set(instruments: &InstrumentRegistry) -> WatchedQtySet {
     let mut result = HashMap::new();
     for (symbol, list) in &CONFIG.watched_quantities {
-        let set: HashSet<String> = list.iter().cloned().collect();  // ✅ 不再 format
+        let set: HashSet<String> = list
+            .iter()
+            .map(|qty| instruments.normalize_quantity(symbol, qty))
+            .collect();
         result.insert(symbol.to_lowercase(), set);
     }
     Arc::new(RwLock::new(result))
@@ -1,5 +1,5 @@
-use crate::types::{WatchedQtySet, TradeHistory};
-use crate::trade_store::insert_trade;
+use crate::types::{WatchedQtySet, TradeHistory, FeedGaps, LiquidationHistory};
+use crate::trade_store::{insert_trade, insert_liquidation, open_gap, close_gap, record_gap};
 use event_engine::event::{EventPayload, EventType};
 use event_engine::event_dispatcher::EventDispatcher;
 use event_engine::event_dispatcher::AsyncQueueEventDispatcher;
@@ -9,8 +9,10 @@ pub fn register_handlers(
     dispatcher: &mut AsyncQueueEventDispatcher,
     watched_qty: WatchedQtySet,
     trade_history: TradeHistory,
+    feed_gaps: FeedGaps,
+    liquidations: LiquidationHistory,
 ) {
-    let watched_qty = watched_qty.clone();
+    let watched_symbols = watched_qty.clone();
     println!("注册聚合成交事件处理器");
     dispatcher.register(EventType::AggTrade, Box::new(move |event| {
         // println!("[聚合成交] 处理事件: {:?}\n", event);
@@ -38,11 +40,62 @@ pub fn register_handlers(
         } else {
             return;
         }
-        println!("[监控命中] {} 触发观察币种 {} 的观察交易数量 {}, 方向 {} ", formatted ,symbol, qty, 
-            if trade.is_buyer_maker { "卖" } else { "买" });   
+        println!("[监控命中{}] {} 触发观察币种 {} 的观察交易数量 {}, 方向 {} ",
+            if trade.backfilled { "·补录" } else { "" }, formatted ,symbol, qty,
+            if trade.is_buyer_maker { "卖" } else { "买" });
         insert_trade(&trade_history, &symbol, qty, trade.clone());
 
 
     }));
 
+    // 只记录关注币种的强平订单，用于统计强平聚集
+    println!("注册强平事件处理器");
+    dispatcher.register(EventType::Liquidation, Box::new(move |event| {
+        let EventPayload::Liquidation(liq) = &event.data else {
+            return;
+        };
+        let symbol = liq.order.symbol.to_lowercase();
+        if !watched_symbols.read().unwrap().contains_key(&symbol) {
+            return;
+        }
+        insert_liquidation(&liquidations, &symbol, liq.clone());
+    }));
+
+    // 归集成交 id 缺口由 agent 通过 REST 补录，补录不完整时才会真正漏掉成交，记为缺口
+    println!("注册成交缺口事件处理器");
+    let gaps = feed_gaps.clone();
+    dispatcher.register(EventType::AggTradeGap, Box::new(move |event| {
+        let EventPayload::AggTradeGap(gap) = &event.data else {
+            return;
+        };
+        if gap.complete {
+            return;
+        }
+        let reason = format!(
+            "{} 归集成交 {}-{} 补录 {}/{} 笔: {}",
+            gap.symbol, gap.from_id, gap.to_id, gap.recovered, gap.to_id - gap.from_id + 1, gap.reason
+        );
+        println!("[行情缺口] {}", reason);
+        record_gap(&gaps, gap.from_time as u128, gap.to_time as u128, &reason);
+    }));
+
+    // 连接断开到恢复订阅之间的成交不会被记录，标记为缺口
+    println!("注册连接状态事件处理器");
+    let gaps = feed_gaps.clone();
+    dispatcher.register(EventType::Disconnected, Box::new(move |event| {
+        let EventPayload::Connection(conn) = &event.data else {
+            return;
+        };
+        println!("[行情缺口] 连接 {} 断开: {}", conn.connection_id, conn.reason);
+        open_gap(&gaps, conn.timestamp, &conn.reason);
+    }));
+    let gaps = feed_gaps;
+    dispatcher.register(EventType::Resubscribed, Box::new(move |event| {
+        let EventPayload::Connection(conn) = &event.data else {
+            return;
+        };
+        println!("[行情缺口] 连接 {} 已恢复订阅", conn.connection_id);
+        close_gap(&gaps, conn.timestamp);
+    }));
+
 }
@@ -1,6 +1,6 @@
 use chrono::{DateTime, Duration, Utc, TimeZone};
 use std::collections::{HashMap, VecDeque};
-use event_engine::event::AggTradeEvent;
+use event_engine::event::{AggTradeEvent, LiquidationEvent};
 use std::collections::HashSet;
 
 /// symbol -> [(bar_start_time, 平均强度)]
@@ -115,3 +115,74 @@ pub fn summarize_imbalance_series(
 
     (last_15, sum_1h, sum_4h, sum_1d, sum_3d)
 }
+
+
+/// 强平聚集：同一方向、时间上相邻的一组强平订单
+#[derive(Debug, Clone)]
+pub struct LiquidationCluster {
+    /// "SELL" 为多头被强平，"BUY" 为空头被强平
+    pub side: String,
+    pub start: DateTime<Utc>,
+    pub end: DateTime<Utc>,
+    pub count: usize,
+    pub quantity: f64,
+    pub notional: f64,
+}
+
+impl LiquidationCluster {
+    pub fn side_label(&self) -> &'static str {
+        if self.side == "SELL" { "多头爆仓" } else { "空头爆仓" }
+    }
+}
+
+/// 找出 since 之后的强平聚集：同方向相邻强平间隔不超过 gap 归为一簇，
+/// 名义价值合计不低于 min_notional 的簇才会返回，按开始时间排序
+pub fn detect_liquidation_clusters(
+    liquidations: &VecDeque<LiquidationEvent>,
+    since: DateTime<Utc>,
+    gap: Duration,
+    min_notional: f64,
+) -> Vec<LiquidationCluster> {
+    let mut events: Vec<&LiquidationEvent> = liquidations.iter().collect();
+    events.sort_by_key(|e| e.order.trade_time);
+
+    let mut open: HashMap<String, LiquidationCluster> = HashMap::new();
+    let mut clusters = Vec::new();
+
+    for event in events {
+        let Some(ts) = Utc.timestamp_millis_opt(event.order.trade_time as i64).single() else {
+            continue;
+        };
+        if ts < since {
+            continue;
+        }
+        let qty: f64 = event.order.filled_qty.parse().unwrap_or(0.0);
+        let notional = event.order.notional();
+
+        match open.get_mut(&event.order.side) {
+            Some(cluster) if ts.signed_duration_since(cluster.end) <= gap => {
+                cluster.end = ts;
+                cluster.count += 1;
+                cluster.quantity += qty;
+                cluster.notional += notional;
+            }
+            _ => {
+                let cluster = LiquidationCluster {
+                    side: event.order.side.clone(),
+                    start: ts,
+                    end: ts,
+                    count: 1,
+                    quantity: qty,
+                    notional,
+                };
+                if let Some(done) = open.insert(event.order.side.clone(), cluster) {
+                    clusters.push(done);
+                }
+            }
+        }
+    }
+    clusters.extend(open.into_values());
+    clusters.retain(|c| c.notional >= min_notional);
+    clusters.sort_by_key(|c| c.start);
+    clusters
+}
@@ -7,8 +7,9 @@ use std::sync::RwLock;
 use once_cell::sync::Lazy;
 use crate::trade_store::get_by_symbol_qty;
 use crate::trade_store::get_all;
-use crate::types::{TradeHistory, WatchedQtySet};
-use crate::indicators::{compute_symbol_imbalance_series,summarize_imbalance_series};
+use crate::trade_store::gaps_since;
+use crate::types::{FeedGap, FeedGaps, LiquidationHistory, TradeHistory, WatchedQtySet};
+use crate::indicators::{compute_symbol_imbalance_series,summarize_imbalance_series, detect_liquidation_clusters};
 use teloxide::types::{BotCommand};
 use chrono::{DateTime, Duration, TimeZone};
 
@@ -17,6 +18,12 @@ use teloxide::prelude::*; // 确保引入所有必要类型（尤其是 `Message
 use teloxide::utils::command::BotCommands;
 use std::collections::{HashMap, HashSet, VecDeque};
 use event_engine::event::AggTradeEvent;
+use market_agent::market_agent::CommandHandle;
+use market_agent::feed_stats::FeedStats;
+use event_engine::event::FeedStatsEvent;
+use common::instrument::InstrumentRegistry;
+use rest_client::{BinanceRestClient, RestMarket};
+use std::sync::Arc;
 
 use teloxide::Bot;
 use teloxide::types::ParseMode;
@@ -73,7 +80,7 @@ pub async fn send_message_to(chat_id: &str, text: &str) {
 
 
 /// 启动 bot 接收消息（需单独线程运行）
-pub async fn start_bot(trade_history: TradeHistory, watched_qty: WatchedQtySet) {
+pub async fn start_bot(trade_history: TradeHistory, watched_qty: WatchedQtySet, feed_gaps: FeedGaps, liquidations: LiquidationHistory, agent_commands: CommandHandle, instruments: Arc<InstrumentRegistry>, feed_stats: FeedStats) {
     let bot = Bot::new(&CONFIG.telegram.token);
     // 注册命令显示到输入框左侧按钮中
     let commands = vec![
@@ -95,6 +102,11 @@ pub async fn start_bot(trade_history: TradeHistory, watched_qty: WatchedQtySet)
         let bot = bot.clone(); // 显式 clone 保持 `Fn`
         let trade_history = trade_history.clone();
         let watched_qty = watched_qty.clone();
+        let feed_gaps = feed_gaps.clone();
+        let liquidations = liquidations.clone();
+        let agent_commands = agent_commands.clone();
+        let instruments = instruments.clone();
+        let feed_stats = feed_stats.clone();
 
         async move {
             let text = message.text().unwrap_or("").trim();
@@ -115,6 +127,7 @@ pub async fn start_bot(trade_history: TradeHistory, watched_qty: WatchedQtySet)
 /imbalance 所有监控品种资金偏移统计
 /status 查看缓存中的成交数据条数
 /detail SYMBOL Q 查询某币种某数量的成交明细，如 `/detail btcusdt 10.023`
+/oi SYMBOL 查询某币种当前持仓量与最近资金费率，如 `/oi btcusdt`
 ";
                     bot.send_message(sender_id, help_msg).send().await?;
                 }
@@ -123,7 +136,9 @@ pub async fn start_bot(trade_history: TradeHistory, watched_qty: WatchedQtySet)
                         let lock = trade_history.lock().unwrap();
                         lock.clone()
                     };
-                    let summary = format_summary_snapshot(&cloned);
+                    let mut summary = format_summary_snapshot(&cloned);
+                    summary.push_str(&format_feed_gaps(&gaps_since(&feed_gaps, 0)));
+                    summary.push_str(&format_feed_stats(&feed_stats.snapshot()));
                     bot.send_message(sender_id, summary)
                         // .parse_mode(ParseMode::MarkdownV2)
                         .send()
@@ -221,8 +236,9 @@ pub async fn start_bot(trade_history: TradeHistory, watched_qty: WatchedQtySet)
                             - 1小时累计：{:+.3}\n\
                             - 4小时累计：{:+.3}\n\
                             - 1日累计：{:+.3}\n\
-                            - 3日累计：{:+.3}",
-                            symbol_fmt, aligned_now,v15, h1, h4, d1, d3
+                            - 3日累计：{:+.3}{}",
+                            symbol_fmt, aligned_now,v15, h1, h4, d1, d3,
+                            format_liquidation_clusters(&liquidations, &symbol)
                         );
 
                         bot.send_message(sender_id, msg)
@@ -266,9 +282,10 @@ pub async fn start_bot(trade_history: TradeHistory, watched_qty: WatchedQtySet)
                         let (v15, h1, h4, d1, d3) = summarize_imbalance_series(&series, aligned_now,chrono::Duration::minutes(15));
 
                         let line = format!(
-                            "*{}*\n- 15min: {:+.3} | 1h: {:+.3} | 4h: {:+.3} | 1d: {:+.3} | 3d: {:+.3}",
+                            "*{}*\n- 15min: {:+.3} | 1h: {:+.3} | 4h: {:+.3} | 1d: {:+.3} | 3d: {:+.3}{}",
                             symbol.to_uppercase().replace('_', "\\_"),
-                            v15, h1, h4, d1, d3
+                            v15, h1, h4, d1, d3,
+                            format_liquidation_clusters(&liquidations, &symbol)
                         );
                         lines.push(line);
                     }
@@ -298,7 +315,15 @@ pub async fn start_bot(trade_history: TradeHistory, watched_qty: WatchedQtySet)
                     }
 
                     let symbol = args[0].to_lowercase();
-                    let qty_str = args[1].trim(); // ✅ 保留原始字符串
+                    // 按品种精度规范化，"10.02" 与 "10.020" 视为同一数量
+                    let qty_string = match parse_watched_qty(&instruments, &symbol, args[1].trim(), false) {
+                        Ok(qty) => qty,
+                        Err(e) => {
+                            bot.send_message(sender_id, format!("❌ {e}")).send().await?;
+                            return Ok(());
+                        }
+                    };
+                    let qty_str = qty_string.as_str();
 
                     let snapshot = {
                         let lock = trade_history.lock().unwrap();
@@ -315,26 +340,55 @@ pub async fn start_bot(trade_history: TradeHistory, watched_qty: WatchedQtySet)
                 }
 
 
+                cmd if cmd.starts_with("/oi ") => {
+                    let symbol = cmd["/oi ".len()..].trim().to_uppercase();
+                    let reply = format_open_interest(&symbol).await;
+                    bot.send_message(sender_id, reply)
+                        .send()
+                        .await?;
+                }
+
+
                 cmd if cmd.starts_with("/add ") => {
                     let parts: Vec<&str> = cmd["/add ".len()..].trim().split_whitespace().collect();
                     if parts.len() != 2 {
                         bot.send_message(sender_id, "❌ 格式错误，应为 `/add <symbol> <quantity>`").send().await?;
                     } else {
                         let symbol = parts[0].to_lowercase();
-                        let qty_str = parts[1].trim();
-
-                        if !qty_str.chars().all(|c| c.is_ascii_digit() || c == '.') {
-                            bot.send_message(sender_id, "❌ 数量格式非法，应为纯数字或小数").send().await?;
-                            return Ok(());
-                        }
+                        // 有品种元数据时校验交易对与数量步长，并规范化为交易所推送的格式
+                        let qty_string = match parse_watched_qty(&instruments, &symbol, parts[1].trim(), true) {
+                            Ok(qty) => qty,
+                            Err(e) => {
+                                bot.send_message(sender_id, format!("❌ {e}")).send().await?;
+                                return Ok(());
+                            }
+                        };
+                        let qty_str = qty_string.as_str();
 
-                        {
+                        let is_new_symbol = {
                             let mut qty_map = watched_qty.write().unwrap();
+                            let is_new_symbol = !qty_map.contains_key(&symbol);
                             let entry = qty_map.entry(symbol.clone()).or_default();
                             entry.insert(qty_str.to_string());
-                        }
-
-                        bot.send_message(sender_id, format!("✅ 已添加 {symbol} 的关注数量 {qty_str}"))
+                            is_new_symbol
+                        };
+
+                        // 新币种需要订阅成交和强平流才会有数据，错误先转为字符串（Box<dyn Error> 不是 Send）
+                        let subscribe_result = if is_new_symbol {
+                            let streams = vec![format!("{}@aggTrade", symbol), format!("{}@forceOrder", symbol)];
+                            agent_commands
+                                .subscribe(streams.iter().map(|s| s.as_str()).collect())
+                                .await
+                                .map_err(|e| e.to_string())
+                        } else {
+                            Ok(())
+                        };
+
+                        let reply = match subscribe_result {
+                            Ok(()) => format!("✅ 已添加 {symbol} 的关注数量 {qty_str}"),
+                            Err(e) => format!("⚠️ 已添加 {symbol} 的关注数量 {qty_str}，但订阅行情失败: {e}"),
+                        };
+                        bot.send_message(sender_id, reply)
                             .send()
                             .await?;
                     }
@@ -346,12 +400,14 @@ pub async fn start_bot(trade_history: TradeHistory, watched_qty: WatchedQtySet)
                         bot.send_message(sender_id, "❌ 格式错误，应为 `/remove <symbol> <quantity>`").send().await?;
                     } else {
                         let symbol = parts[0].to_lowercase();
-                        let qty_str = parts[1].trim();
-
-                        if !qty_str.chars().all(|c| c.is_ascii_digit() || c == '.') {
-                            bot.send_message(sender_id, "❌ 数量格式非法，应为纯数字或小数").send().await?;
-                            return Ok(());
-                        }
+                        let qty_string = match parse_watched_qty(&instruments, &symbol, parts[1].trim(), false) {
+                            Ok(qty) => qty,
+                            Err(e) => {
+                                bot.send_message(sender_id, format!("❌ {e}")).send().await?;
+                                return Ok(());
+                            }
+                        };
+                        let qty_str = qty_string.as_str();
 
                         {
                             let mut qty_map = watched_qty.write().unwrap();
@@ -387,6 +443,55 @@ pub async fn start_bot(trade_history: TradeHistory, watched_qty: WatchedQtySet)
     .await;
 }
 
+/// 解析用户输入的关注数量并按品种精度规范化（"7" -> "7.000"）。
+/// 关注的是成交数量而不是下单量，只校验能否解析；check_step 为 true 时（/add）还校验交易对与数量步长
+fn parse_watched_qty(instruments: &InstrumentRegistry, symbol: &str, qty_str: &str, check_step: bool) -> Result<String, String> {
+    if !qty_str.chars().all(|c| c.is_ascii_digit() || c == '.') {
+        return Err("数量格式非法，应为纯数字或小数".to_string());
+    }
+    let qty: f64 = qty_str.parse().map_err(|_| "数量格式非法，应为纯数字或小数".to_string())?;
+    match instruments.get(symbol) {
+        Some(instrument) => {
+            if check_step {
+                instrument.validate_step(qty).map_err(|e| e.to_string())?;
+            }
+            Ok(instrument.format_quantity(qty))
+        }
+        None if check_step && !instruments.is_empty() => Err(format!("未知交易对 {}", symbol.to_uppercase())),
+        None => Ok(qty_str.to_string()),
+    }
+}
+
+/// 当前持仓量与最近 3 次资金费率，数据来自 U 本位合约 REST 接口
+async fn format_open_interest(symbol: &str) -> String {
+    let rest = BinanceRestClient::shared(RestMarket::UsdFutures);
+    // 错误先转为字符串，Box<dyn Error> 不能跨 await 持有
+    let open_interest = rest.open_interest(symbol).await.map_err(|e| e.to_string());
+    let funding_rates = rest.funding_rates(symbol, None, None, 3).await.map_err(|e| e.to_string());
+
+    let mut lines = vec![format!("📈 {} 持仓与资金费率", symbol)];
+    match open_interest {
+        Ok(oi) => lines.push(format!("- 当前持仓量：{}", oi.open_interest)),
+        Err(e) => lines.push(format!("- 持仓量查询失败：{}", e)),
+    }
+    match funding_rates {
+        Ok(rates) => {
+            lines.push("- 最近资金费率：".to_string());
+            for rate in rates.iter().rev() {
+                let time = Utc
+                    .timestamp_millis_opt(rate.funding_time as i64)
+                    .single()
+                    .map(|dt| dt.format("%Y-%m-%d %H:%M").to_string())
+                    .unwrap_or_else(|| rate.funding_time.to_string());
+                let pct = rate.funding_rate.parse::<f64>().unwrap_or(0.0) * 100.0;
+                lines.push(format!("  {}  {:+.4}%", time, pct));
+            }
+        }
+        Err(e) => lines.push(format!("- 资金费率查询失败：{}", e)),
+    }
+    lines.join("\n")
+}
+
 /// 简要统计 summary
 fn format_summary(history: &TradeHistory) -> String {
     let map = get_all(history);
@@ -467,6 +572,78 @@ fn format_summary_snapshot(
     lines.join("\n")
 }
 
+/// 近 24 小时的强平聚集（最近 5 簇），无聚集时返回空字符串
+pub fn format_liquidation_clusters(liquidations: &LiquidationHistory, symbol: &str) -> String {
+    let list = {
+        let lock = liquidations.lock().unwrap();
+        match lock.get(symbol) {
+            Some(list) => list.clone(),
+            None => return String::new(),
+        }
+    };
+    let clusters = detect_liquidation_clusters(
+        &list,
+        Utc::now() - Duration::days(1),
+        Duration::seconds(CONFIG.liquidation.cluster_gap_secs),
+        CONFIG.liquidation.cluster_min_notional,
+    );
+    if clusters.is_empty() {
+        return String::new();
+    }
+    let mut lines = vec![format!("\n💥 近24h 强平聚集 {} 次：", clusters.len())];
+    for c in clusters.iter().rev().take(5) {
+        lines.push(format!(
+            "  - {}~{} {} {} 笔，数量 {:.3}，名义 {:.0}",
+            c.start.format("%m-%d %H:%M:%S"),
+            c.end.format("%H:%M:%S"),
+            c.side_label(),
+            c.count,
+            c.quantity,
+            c.notional
+        ));
+    }
+    lines.join("\n")
+}
+
+/// 行情缺口列表（最近 5 次）
+fn format_feed_gaps(gaps: &[FeedGap]) -> String {
+    if gaps.is_empty() {
+        return String::new();
+    }
+    let fmt = |ms: u128| {
+        Utc.timestamp_millis_opt(ms as i64)
+            .single()
+            .map(|dt| dt.format("%m-%d %H:%M:%S").to_string())
+            .unwrap_or_else(|| ms.to_string())
+    };
+    let mut lines = vec![format!("\n\n⚠️ 行情中断 {} 次（期间成交未记录），最近：", gaps.len())];
+    for gap in gaps.iter().rev().take(5) {
+        let end = gap.end_ms.map(fmt).unwrap_or_else(|| "未恢复".to_string());
+        lines.push(format!("  - {} ~ {}（{}）", fmt(gap.start_ms), end, gap.reason));
+    }
+    lines.join("\n")
+}
+
+/// 各行情流的消息速率、延迟与异常计数
+fn format_feed_stats(stats: &FeedStatsEvent) -> String {
+    if stats.streams.is_empty() {
+        return String::new();
+    }
+    let mut lines = vec![format!("\n\n📡 行情流（断线 {} 次）：", stats.disconnects)];
+    for s in &stats.streams {
+        let age = s.last_message_age_ms.map(|ms| format!("{:.1}s", ms as f64 / 1000.0)).unwrap_or_else(|| "-".to_string());
+        let latency = match (s.latency_p50_ms, s.latency_p99_ms) {
+            (Some(p50), Some(p99)) => format!("{:.0}/{:.0}ms", p50, p99),
+            _ => "-".to_string(),
+        };
+        lines.push(format!(
+            "  - {}：{:.1} 条/s，延迟 p50/p99 {}，最后消息 {} 前，解析失败 {}，未识别 {}，重连 {}",
+            s.stream, s.message_rate, latency, age, s.parse_failures, s.unknown_events, s.reconnects
+        ));
+    }
+    lines.join("\n")
+}
+
 fn format_detail_snapshot(
     history: &HashMap<String, HashMap<String, VecDeque<AggTradeEvent>>>,
     symbol: &str,
@@ -5,13 +5,13 @@ mod trade_store;
 mod types;
 mod telegram;
 mod indicators;
-use crate::config::{get_watched_qty_set, CONFIG};
+use crate::config::{get_watched_qty_set, get_ws_endpoint, CONFIG};
 use crate::event_handlers::register_handlers;
 use crate::timer::start_timer_loop;
 use crate::trade_store::load_from_file;
-use crate::types::TradeHistory;
-use crate::telegram::{SUBSCRIBERS, send_message_to};
-use crate::trade_store::get_all;
+use crate::types::{FeedGaps, LiquidationHistory, TradeHistory};
+use crate::telegram::{SUBSCRIBERS, send_message_to, format_liquidation_clusters};
+use crate::trade_store::{get_all, gaps_since};
 use crate::indicators::{compute_symbol_imbalance_series,summarize_imbalance_series};
 
 use std::collections::VecDeque;
@@ -25,11 +25,11 @@ use event_engine::event::EventType;
 use event_engine::event_dispatcher::AsyncQueueEventDispatcher;
 use market_agent::market_agent::MarketAgent;
 use market_agent::binance_market_agent::BinanceMarketAgent;
+use common::exchange::Exchange;
+use common::instrument::InstrumentRegistry;
 
 use std::collections::HashMap;
 use std::sync::{Arc, Mutex};
-use std::thread;
-use tokio::runtime::Runtime;
 use chrono::{DateTime, Duration};
 
 
@@ -75,20 +75,31 @@ fn format_trade_summary(history: &TradeHistory) -> String {
 
 
 async fn run_system() {
+    println!("[启动] 加载品种元数据...");
+    let instruments = InstrumentRegistry::load_or_fetch(Exchange::Binance, &CONFIG.instrument_cache_path)
+        .await
+        .unwrap_or_else(|e| {
+            eprintln!("[启动] 品种元数据不可用: {}，关注数量将按原始字符串匹配", e);
+            InstrumentRegistry::new(Exchange::Binance)
+        });
+    let instruments = Arc::new(instruments);
+
     println!("[启动] 加载配置...");
-    let watched = get_watched_qty_set();
+    let watched = get_watched_qty_set(&instruments);
     let trade_history: TradeHistory = Arc::new(Mutex::new(HashMap::new()));
+    let feed_gaps: FeedGaps = Arc::new(Mutex::new(VecDeque::new()));
+    let liquidations: LiquidationHistory = Arc::new(Mutex::new(HashMap::new()));
 
     // 尝试从本地恢复缓存
     load_from_file(&trade_history, &CONFIG.backup_path);
 
     let mut dispatcher = AsyncQueueEventDispatcher::new(500);
-    register_handlers(&mut dispatcher, watched.clone(), trade_history.clone());
+    register_handlers(&mut dispatcher, watched.clone(), trade_history.clone(), feed_gaps.clone(), liquidations.clone());
 
     let (producer, mut consumer) = dispatcher.split();
 
     println!("[启动] 初始化 Binance WebSocket...");
-    let mut ws_client = BinanceWebSocketClient::new();
+    let mut ws_client = BinanceWebSocketClient::with_endpoint(get_ws_endpoint());
     ws_client
         .connect(vec!["btcusdt@aggTrade"])
         .await
@@ -97,20 +108,36 @@ async fn run_system() {
         .subscribe(vec!["btcusdt@aggTrade"])
         .await
         .unwrap();
+    // 关注币种的强平订单，用于在偏移统计旁报告强平聚集
+    let liquidation_streams: Vec<String> = watched
+        .read()
+        .unwrap()
+        .keys()
+        .map(|symbol| format!("{}@forceOrder", symbol))
+        .collect();
+    if !liquidation_streams.is_empty() {
+        ws_client
+            .subscribe(liquidation_streams.iter().map(|s| s.as_str()).collect())
+            .await
+            .unwrap();
+    }
 
     let mut market_agent = BinanceMarketAgent::new(ws_client, producer);
+    market_agent.apply_instruments(&instruments);
+    // 启动后代理被移入任务，Telegram 的 /add 通过命令句柄实时订阅新币种
+    let commands = market_agent.command_handle();
+    let feed_stats = market_agent.feed_stats();
 
     println!("[启动] 启动 MarketAgent...");
-    thread::spawn(move || {
-        let rt = Runtime::new().unwrap();
-        rt.block_on(async {
-            market_agent.start().await;
-        });
+    tokio::spawn(async move {
+        if let Err(e) = market_agent.start().await {
+            eprintln!("[MarketAgent] 异常退出: {}", e);
+        }
     });
 
 
     // ✅ 启动 Telegram Bot 监听指令
-    tokio::spawn(telegram::start_bot(trade_history.clone(), watched.clone()));
+    tokio::spawn(telegram::start_bot(trade_history.clone(), watched.clone(), feed_gaps.clone(), liquidations.clone(), commands, instruments.clone(), feed_stats));
     println!("[启动] 启动 Telegram Bot监听指令...");
 
     println!("[启动] 启动定时推送器...");
@@ -118,6 +145,8 @@ async fn run_system() {
     start_timer_loop(cloned_history.clone(), move || {
         let trade_history = cloned_history.clone();
         let watched_map = watched.read().unwrap().clone(); // ✅ 提前 clone HashMap，释放锁
+        let feed_gaps = feed_gaps.clone();
+        let liquidations = liquidations.clone();
 
         
         async move {
@@ -139,6 +168,15 @@ async fn run_system() {
                         .single()
                         .unwrap_or_else(Utc::now);
 
+            // 统计区间（3日）内存在行情缺口时提示，累计值可能偏小
+            let window_start = (Utc::now() - chrono::Duration::days(3)).timestamp_millis() as u128;
+            let gap_count = gaps_since(&feed_gaps, window_start).len();
+            let gap_note = if gap_count > 0 {
+                format!("\n⚠️ 统计区间内有 {} 次行情中断，期间成交未记录", gap_count)
+            } else {
+                String::new()
+            };
+
             for (symbol, series) in imbalance {
                 let (v15, h1, h4, d1, d3) = summarize_imbalance_series(&series, aligned_now,chrono::Duration::minutes(15));
 
@@ -149,8 +187,9 @@ async fn run_system() {
                     - 1小时累计：{:+.3}\n\
                     - 4小时累计：{:+.3}\n\
                     - 1日累计：{:+.3}\n\
-                    - 3日累计：{:+.3}",
-                    symbol.to_uppercase(),aligned_now, v15, h1, h4, d1, d3
+                    - 3日累计：{:+.3}{}{}",
+                    symbol.to_uppercase(),aligned_now, v15, h1, h4, d1, d3,
+                    format_liquidation_clusters(&liquidations, &symbol), gap_note
                 );
 
                 for id in &ids {
@@ -4,8 +4,8 @@ use std::io::Write;
 use std::sync::Mutex;
 
 use crate::config::CONFIG;
-use crate::types::TradeHistory;
-use event_engine::event::AggTradeEvent;
+use crate::types::{FeedGap, FeedGaps, LiquidationHistory, TradeHistory};
+use event_engine::event::{AggTradeEvent, LiquidationEvent};
 
 use serde::{Deserialize, Serialize};
 
@@ -52,6 +52,61 @@ pub fn get_by_symbol_qty(history: &TradeHistory, symbol: &str, qty: f64) -> Opti
 }
 
 
+/// 记录一条强平订单（自动维护滑动窗口）
+pub fn insert_liquidation(history: &LiquidationHistory, symbol: &str, event: LiquidationEvent) {
+    let mut guard = history.lock().unwrap();
+    let entry = guard.entry(symbol.to_string()).or_default();
+    entry.push_back(event);
+    if entry.len() > CONFIG.liquidation.history_max_len {
+        entry.pop_front();
+    }
+}
+
+/// 最多保留的缺口记录数
+const MAX_FEED_GAPS: usize = 100;
+
+/// 记录缺口开始（已有未结束的缺口时忽略）
+pub fn open_gap(gaps: &FeedGaps, start_ms: u128, reason: &str) {
+    let mut guard = gaps.lock().unwrap();
+    if guard.back().map(|g| g.end_ms.is_none()).unwrap_or(false) {
+        return;
+    }
+    guard.push_back(FeedGap { start_ms, end_ms: None, reason: reason.to_string() });
+    if guard.len() > MAX_FEED_GAPS {
+        guard.pop_front();
+    }
+}
+
+/// 记录缺口结束
+pub fn close_gap(gaps: &FeedGaps, end_ms: u128) {
+    let mut guard = gaps.lock().unwrap();
+    if let Some(gap) = guard.back_mut() {
+        if gap.end_ms.is_none() {
+            gap.end_ms = Some(end_ms);
+        }
+    }
+}
+
+/// 记录一段已结束的缺口（例如补录失败的归集成交区间）
+pub fn record_gap(gaps: &FeedGaps, start_ms: u128, end_ms: u128, reason: &str) {
+    let mut guard = gaps.lock().unwrap();
+    guard.push_back(FeedGap { start_ms, end_ms: Some(end_ms), reason: reason.to_string() });
+    if guard.len() > MAX_FEED_GAPS {
+        guard.pop_front();
+    }
+}
+
+/// 与 [since_ms, 现在] 有重叠的缺口
+pub fn gaps_since(gaps: &FeedGaps, since_ms: u128) -> Vec<FeedGap> {
+    let guard = gaps.lock().unwrap();
+    guard
+        .iter()
+        .filter(|g| g.end_ms.map(|end| end >= since_ms).unwrap_or(true))
+        .cloned()
+        .collect()
+}
+
+
 /// ✅ JSON 序列化用结构
 #[derive(Serialize, Deserialize)]
 pub struct SerializableHistory(pub HashMap<String, HashMap<String, Vec<AggTradeEvent>>>);
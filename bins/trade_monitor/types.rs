@@ -1,11 +1,25 @@
 use std::collections::{HashMap, HashSet, VecDeque};
 use std::sync::{Arc, Mutex, RwLock};
-use event_engine::event::AggTradeEvent;
+use event_engine::event::{AggTradeEvent, LiquidationEvent};
 
 
 pub type WatchedQtySet = Arc<RwLock<HashMap<String, HashSet<String>>>>;
 pub type TradeHistory = Arc<Mutex<HashMap<String, HashMap<String, VecDeque<AggTradeEvent>>>>>;
 
+/// 行情缺口：连接断开到恢复订阅之间，期间的成交没有被记录
+#[derive(Debug, Clone)]
+pub struct FeedGap {
+    pub start_ms: u128,
+    /// 尚未恢复时为 None
+    pub end_ms: Option<u128>,
+    pub reason: String,
+}
+
+pub type FeedGaps = Arc<Mutex<VecDeque<FeedGap>>>;
+
+/// symbol -> 最近的强平订单
+pub type LiquidationHistory = Arc<Mutex<HashMap<String, VecDeque<LiquidationEvent>>>>;
+
 // pub fn default_watched_quantities() -> WatchedQtySet {
 //     Arc::new([5.023, 10.002, 1.234].into_iter().collect())
 // }
@@ -1,33 +1,119 @@
 use event_engine::event_dispatcher::QueueEventDispatcherProducer;
 use market_agent::market_agent::MarketAgent;
-use market_agent::binance_market_agent::BinanceMarketAgent;
+use market_agent::binance_market_agent::{BinanceMarket, BinanceMarketAgent};
 use feeder::websocket::WebSocket;
 use feeder::websocket::BinanceWebSocketClient;
+use feeder::okx_websocket::OkxWebSocketClient;
+use feeder::bybit_websocket::BybitWebSocketClient;
+use market_agent::okx_market_agent::OkxMarketAgent;
+use market_agent::bybit_market_agent::BybitMarketAgent;
+use feeder::endpoint::WsEndpoint;
 use common::exchange::Exchange;
+use common::instrument::InstrumentRegistry;
 
 // 定义一个结构体存放两个模块的实例
 pub struct ExchangeComponents {
     // pub ws_client: Box<dyn WebSocket>,
     pub market_agent: Box<dyn MarketAgent + Send>,
+    /// 品种元数据，暂不支持或获取失败时为空
+    pub instruments: InstrumentRegistry,
 }
 
+/// 获取品种元数据；失败时不影响行情启动，返回空集合
+async fn load_instruments(exchange: Exchange) -> InstrumentRegistry {
+    if InstrumentRegistry::exchange_info_urls(exchange).is_empty() {
+        return InstrumentRegistry::new(exchange);
+    }
+    let fetched = InstrumentRegistry::fetch(exchange).await.map_err(|e| e.to_string());
+    match fetched {
+        Ok(registry) => {
+            println!("[品种信息] 已从交易所加载 {} 个品种", registry.len());
+            registry
+        }
+        Err(e) => {
+            eprintln!("[品种信息] 获取 {} 的品种信息失败: {}", exchange.as_str(), e);
+            InstrumentRegistry::new(exchange)
+        }
+    }
+}
+
+/// endpoint 为 None 时使用该交易所的正式环境端点；
+/// 传入测试网或本地 mock 端点即可在不改代码的情况下切换环境
 pub async fn create_exchange_components(
     exchange: Exchange,
+    endpoint: Option<WsEndpoint>,
     producer: QueueEventDispatcherProducer, 
 ) -> Result<ExchangeComponents, Box<dyn std::error::Error>> {
     match exchange {
         Exchange::Binance => {
             // 生成 Binance 的 websocket 客户端和 market agent
-            let mut ws_client = BinanceWebSocketClient::new();
+            let endpoint = endpoint.unwrap_or_else(|| BinanceMarket::UsdFutures.default_endpoint());
+            let mut ws_client = BinanceWebSocketClient::with_endpoint(endpoint);
             // ws_client.connect(Vec::<&str>::new()).await?;
             ws_client.connect(vec!["btcusdt@depth@100ms"]).await?;
             // ws_client.subscribe(vec!["btcusdt@depth@100ms"]).await?;
-            let market_agent = BinanceMarketAgent::new(ws_client, producer);
+            let mut market_agent = BinanceMarketAgent::new(ws_client, producer);
+            let instruments = load_instruments(exchange).await;
+            market_agent.apply_instruments(&instruments);
             Ok(ExchangeComponents {
                 // ws_client: Box::new(ws_client),
                 market_agent: Box::new(market_agent),
+                instruments,
+            })
+        }
+        Exchange::BinanceSpot => {
+            let endpoint = endpoint.unwrap_or_else(|| BinanceMarket::Spot.default_endpoint());
+            let mut ws_client = BinanceWebSocketClient::with_endpoint(endpoint);
+            ws_client.connect(vec!["btcusdt@depth@100ms"]).await?;
+            let mut market_agent = BinanceMarketAgent::spot(ws_client, producer);
+            let instruments = load_instruments(exchange).await;
+            market_agent.apply_instruments(&instruments);
+            Ok(ExchangeComponents {
+                market_agent: Box::new(market_agent),
+                instruments,
+            })
+        }
+        Exchange::BinanceCoinFutures => {
+            let endpoint = endpoint.unwrap_or_else(|| BinanceMarket::CoinFutures.default_endpoint());
+            let mut ws_client = BinanceWebSocketClient::with_endpoint(endpoint);
+            ws_client.connect(vec!["btcusd_perp@depth@100ms"]).await?;
+            let mut market_agent = BinanceMarketAgent::coin_futures(ws_client, producer);
+            // 数量单位为张，面值只能来自 exchangeInfo，获取失败时不启动
+            let instruments = InstrumentRegistry::fetch(exchange)
+                .await
+                .map_err(|e| format!("获取币本位合约面值失败: {}", e))?;
+            market_agent.apply_instruments(&instruments);
+            Ok(ExchangeComponents {
+                market_agent: Box::new(market_agent),
+                instruments,
+            })
+        }
+        Exchange::Okx => {
+            let endpoint = endpoint.unwrap_or_else(WsEndpoint::okx_public);
+            let mut ws_client = OkxWebSocketClient::with_endpoint(endpoint);
+            ws_client.connect(vec!["BTC-USDT-SWAP@books"]).await?;
+            let mut market_agent = OkxMarketAgent::new(ws_client, producer);
+            // 合约数量单位为张，需要 ctVal 才能折算为币
+            let instruments = load_instruments(exchange).await;
+            market_agent.apply_instruments(&instruments);
+            Ok(ExchangeComponents {
+                market_agent: Box::new(market_agent),
+                instruments,
+            })
+        }
+        Exchange::Bybit => {
+            let endpoint = endpoint.unwrap_or_else(WsEndpoint::bybit_linear);
+            let mut ws_client = BybitWebSocketClient::with_endpoint(endpoint);
+            ws_client.connect(vec!["orderbook.50.BTCUSDT"]).await?;
+            let mut market_agent = BybitMarketAgent::new(ws_client, producer);
+            // 反向合约数量单位为张，需要品种元数据才能折算为币
+            let instruments = load_instruments(exchange).await;
+            market_agent.apply_instruments(&instruments);
+            Ok(ExchangeComponents {
+                market_agent: Box::new(market_agent),
+                instruments,
             })
         }
-        
+
     }
 }
\ No newline at end of file
@@ -1,4 +1,5 @@
 // use std::sync::{Arc, Mutex};
+use std::sync::Arc;
 use tokio::task;
 use std::error::Error;
 
@@ -8,15 +9,18 @@ use event_engine::event_dispatcher::QueueEventDispatcherProducer;
 use event_engine::event_dispatcher::QueueEventDispatcherConsumer;
 use event_engine::event::{EventType};
 use event_engine::event_dispatcher::EventData;
-use market_agent::market_agent::MarketAgent;
+use market_agent::market_agent::{CommandHandle, MarketAgent, StopHandle};
+use market_agent::cache::MarkPriceCache;
+use market_agent::feed_stats::FeedStats;
 use market_agent::binance_market_agent::BinanceMarketAgent;
 use feeder::websocket::WebSocket;
 use feeder::websocket::BinanceWebSocketClient;
-use tokio::runtime::Runtime;
+use feeder::endpoint::WsEndpoint;
 use std::thread;
 
 use crate::components::create_exchange_components;
 use common::exchange::Exchange;
+use common::instrument::InstrumentRegistry;
 
 pub struct Context {
     // pub dispatcher: &'a AsyncQueueEventDispatcher,
@@ -24,18 +28,39 @@ pub struct Context {
     // pub ws_client: Box<dyn WebSocket>,
     // pub producer: &'a QueueEventDispatcherProducer,
     pub consumer: Option<QueueEventDispatcherConsumer>,
+    /// 标记价格最新值缓存（代理启动后仍可查询）
+    pub mark_prices: MarkPriceCache,
+    /// 市场代理的命令句柄，代理启动后仍可订阅、取消订阅
+    pub commands: CommandHandle,
+    /// 市场代理的行情统计（代理启动后仍可查询）
+    pub feed_stats: FeedStats,
+    /// 品种元数据，构造时已应用到市场代理（例如币本位合约面值），订单簿等模块可按交易对查询
+    pub instruments: Arc<InstrumentRegistry>,
+    /// 运行中的市场代理任务，结束时交还代理本身以便重启
+    agent_task: Option<task::JoinHandle<Box<dyn MarketAgent + Send>>>,
+    /// 运行中的市场代理的停止句柄
+    agent_stop: Option<StopHandle>,
 }
 
 impl Context {
     /// 初始化 AppContext，只构造事件调度器和市场代理，不包含订单簿
     pub async fn new(exchange:Exchange, dispatcher_capacity: usize) -> Result<Self, Box<dyn Error>> {
+        Self::with_endpoint(exchange, None, dispatcher_capacity).await
+    }
+
+    /// 指定 WebSocket 端点初始化（测试网、本地 mock 等），None 表示使用正式环境
+    pub async fn with_endpoint(exchange:Exchange, endpoint: Option<WsEndpoint>, dispatcher_capacity: usize) -> Result<Self, Box<dyn Error>> {
         // 创建 dispatcher
         let dispatcher = AsyncQueueEventDispatcher::new(dispatcher_capacity);
         let (producer, mut consumer) = dispatcher.split();
 
-        let exchange_components = create_exchange_components(exchange, producer).await?;
+        let exchange_components = create_exchange_components(exchange, endpoint, producer).await?;
         // let ws_client = exchange_components.ws_client;
         let market_agent = exchange_components.market_agent;
+        let instruments = Arc::new(exchange_components.instruments);
+        let mark_prices = market_agent.mark_price_cache();
+        let commands = market_agent.command_handle();
+        let feed_stats = market_agent.feed_stats();
 
         // ws_client.subscribe(vec!["btcusdt@depth@100ms"]).await?;
 
@@ -47,20 +72,38 @@ impl Context {
             // ws_client,
             // producer: &producer,
             consumer: Some(consumer),
+            mark_prices,
+            commands,
+            feed_stats,
+            instruments,
+            agent_task: None,
+            agent_stop: None,
         })
     }
 
-    /// 启动市场代理（异步运行）
+    /// 启动市场代理（在当前 tokio 运行时中作为独立任务运行，需在运行时内调用）
     pub fn start_market_agent(&mut self) {
         let mut market_agent = self.market_agent.take().expect("market_agent is already taken");
+        self.agent_stop = Some(market_agent.stop_handle());
 
-        thread::spawn(move || {
-            let rt = Runtime::new().unwrap();
-            rt.block_on(async {
-                market_agent.start().await;
-            });
-        });
-        // Ok(())
+        self.agent_task = Some(task::spawn(async move {
+            if let Err(e) = market_agent.start().await {
+                eprintln!("市场代理异常退出: {}", e);
+            }
+            market_agent
+        }));
+    }
+
+    /// 停止市场代理并收回，之后可再次调用 start_market_agent 重启
+    pub async fn stop_market_agent(&mut self) -> Result<(), Box<dyn Error>> {
+        if let Some(stop) = self.agent_stop.take() {
+            stop.stop();
+        }
+        if let Some(handle) = self.agent_task.take() {
+            let market_agent = handle.await?;
+            self.market_agent = Some(market_agent);
+        }
+        Ok(())
     }
 
     /// 在独立线程中启动事件消费循环
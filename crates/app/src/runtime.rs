@@ -1,9 +1,12 @@
 // runtime.rs
 use std::error::Error;
 use crate::context::Context;
-use event_engine::event::EventType;
+use event_engine::event::{EventType, FeedStatsEvent, MarkPriceEvent};
 use common::exchange::Exchange;
+use common::instrument::Instrument;
+use feeder::endpoint::WsEndpoint;
 use event_engine::event_dispatcher::EventData;
+use market_agent::market_agent::{CommandHandle, EventEmission};
 use tokio;
 
 pub struct Runtime {
@@ -18,6 +21,12 @@ impl Runtime {
         Ok(Self { context })
     }
 
+    /// 创建 Runtime 实例，并使用指定的 WebSocket 端点（测试网、本地 mock 等）
+    pub async fn with_endpoint(exchange:Exchange, endpoint: WsEndpoint, dispatcher_capacity: usize) -> Result<Self, Box<dyn Error>> {
+        let context = Context::with_endpoint(exchange, Some(endpoint), dispatcher_capacity).await?;
+        Ok(Self { context })
+    }
+
     /// 启动服务：启动市场代理、事件循环等
     pub async fn start_service(&mut self) -> Result<(), Box<dyn Error>> {
         self.context.start_market_agent();
@@ -27,6 +36,14 @@ impl Runtime {
         // 主线程等待 Ctrl+C 信号，从而保持运行状态
         tokio::signal::ctrl_c().await?;
         println!("收到退出信号，程序结束。");
+        self.context.stop_market_agent().await?;
+        Ok(())
+    }
+
+    /// 重启市场代理：停止当前运行的代理后重新启动，已建立的连接与订阅保留
+    pub async fn restart_market_agent(&mut self) -> Result<(), Box<dyn Error>> {
+        self.context.stop_market_agent().await?;
+        self.context.start_market_agent();
         Ok(())
     }
 
@@ -38,12 +55,62 @@ impl Runtime {
         self.context.register_callback(event_type, callback);
     }
 
+    /// 查询某个交易对最新的标记价格与资金费率
+    pub fn latest_mark_price(&self, symbol: &str) -> Option<MarkPriceEvent> {
+        self.context.mark_prices.get(symbol)
+    }
+
+    /// 交易对的品种元数据，用于订单簿价格取整等
+    pub fn instrument(&self, symbol: &str) -> Option<Instrument> {
+        self.context.instruments.get(symbol).cloned()
+    }
+
+    /// 当前行情统计快照：各流的消息速率、解析失败、重连次数与延迟分位数
+    pub fn feed_stats(&self) -> FeedStatsEvent {
+        self.context.feed_stats.snapshot()
+    }
+
+    /// 订阅指定的流。start_service 之前直接调用代理，之后通过命令通道交给运行中的代理执行
     pub async fn subscribe(&mut self, streams: Vec<&str>) -> Result<(), Box<dyn Error>> {
         // 内部处理 Option，不用让使用者处理 unwrap
         if let Some(agent) = self.context.market_agent.as_mut() {
             agent.subscribe(streams).await
         } else {
-            Err("market_agent is None".into())
+            self.context.commands.subscribe(streams).await
+        }
+    }
+
+    /// 取消订阅指定的流
+    pub async fn unsubscribe(&mut self, streams: Vec<&str>) -> Result<(), Box<dyn Error>> {
+        if let Some(agent) = self.context.market_agent.as_mut() {
+            agent.unsubscribe(streams).await
+        } else {
+            self.context.commands.unsubscribe(streams).await
+        }
+    }
+
+    /// 查询交易所侧已确认的订阅列表
+    pub async fn list_subscriptions(&mut self) -> Result<Vec<String>, Box<dyn Error>> {
+        if let Some(agent) = self.context.market_agent.as_mut() {
+            agent.list_subscriptions().await
+        } else {
+            self.context.commands.list_subscriptions().await
         }
     }
+
+    /// 设置行情事件的分发形式（原始、归一化或两者），只能在 start_service 之前或停止服务之后调用
+    pub fn set_event_emission(&mut self, emission: EventEmission) -> Result<(), Box<dyn Error>> {
+        match self.context.market_agent.as_mut() {
+            Some(agent) => {
+                agent.set_event_emission(emission);
+                Ok(())
+            }
+            None => Err("市场代理运行中，请在 start_service 之前设置事件分发形式".into()),
+        }
+    }
+
+    /// 命令句柄，可交给其他任务在服务运行期间调整订阅
+    pub fn command_handle(&self) -> CommandHandle {
+        self.context.commands.clone()
+    }
 }
@@ -5,7 +5,16 @@
 /// 定义支持的交易所枚举
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Exchange {
+    /// 币安 U 本位合约
     Binance,
+    /// 币安现货
+    BinanceSpot,
+    /// 币安币本位合约（交割与永续）
+    BinanceCoinFutures,
+    /// OKX（公共频道）
+    Okx,
+    /// Bybit（v5 公共频道，USDT 合约）
+    Bybit,
 }
 
 impl Exchange {
@@ -13,6 +22,10 @@ impl Exchange {
     pub fn as_str(&self) -> &'static str {
         match self {
             Exchange::Binance => "binance",
+            Exchange::BinanceSpot => "binance_spot",
+            Exchange::BinanceCoinFutures => "binance_coin_futures",
+            Exchange::Okx => "okx",
+            Exchange::Bybit => "bybit",
         }
     }
 }
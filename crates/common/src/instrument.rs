@@ -0,0 +1,569 @@
+// common/instrument.rs
+
+// 交易品种元数据：最小价格变动、数量步长、最小名义价值、合约类型与精度。
+// 从交易所 exchangeInfo 加载，也可以缓存到本地 JSON 文件离线使用
+
+use std::collections::HashMap;
+use std::error::Error;
+use std::fs;
+use std::time::Duration;
+
+use reqwest::Client;
+use event_engine::event::ContractSize;
+use serde::{Deserialize, Deserializer, Serialize};
+use serde_json::Value;
+
+use crate::exchange::Exchange;
+
+/// exchangeInfo 响应较大，超时时间放宽一些
+const FETCH_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// 建立连接的超时时间
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// 单个交易品种的元数据
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Instrument {
+    pub symbol: String,             // 交易对，例如 "BTCUSDT"、"BTCUSD_PERP"
+    pub base_asset: String,
+    pub quote_asset: String,
+    pub contract_type: String,      // "PERPETUAL"、"CURRENT_QUARTER" 等，现货为 "SPOT"
+    pub status: String,             // "TRADING" 表示可交易
+    pub tick_size: f64,             // 最小价格变动
+    pub step_size: f64,             // 数量步长
+    pub min_qty: f64,
+    pub max_qty: f64,
+    pub min_notional: f64,          // 最小名义价值（交易所未限制时为 0）
+    pub price_precision: u32,       // 价格小数位数
+    pub quantity_precision: u32,    // 数量小数位数
+    #[serde(default, deserialize_with = "de_contract_size")]
+    pub contract_size: Option<ContractSize>, // 合约每张的大小，数量单位为币的品种为 None
+}
+
+/// 旧版缓存文件中的 contract_size 为数值（币本位合约面值），按反向合约读取
+fn de_contract_size<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<ContractSize>, D::Error> {
+    match Option::<Value>::deserialize(deserializer)? {
+        None | Some(Value::Null) => Ok(None),
+        Some(Value::Number(n)) => Ok(n.as_f64().map(ContractSize::Inverse)),
+        Some(other) => serde_json::from_value(other).map(Some).map_err(serde::de::Error::custom),
+    }
+}
+
+impl Instrument {
+    pub fn is_trading(&self) -> bool {
+        self.status == "TRADING"
+    }
+
+    /// 价格按最小变动取整，并去掉浮点误差
+    pub fn round_price(&self, price: f64) -> f64 {
+        round_to(round_to_step(price, self.tick_size), self.price_precision)
+    }
+
+    /// 数量按步长取整，并去掉浮点误差
+    pub fn round_quantity(&self, quantity: f64) -> f64 {
+        round_to(round_to_step(quantity, self.step_size), self.quantity_precision)
+    }
+
+    /// 按价格精度格式化，例如 "65000.10"
+    pub fn format_price(&self, price: f64) -> String {
+        format!("{:.*}", self.price_precision as usize, self.round_price(price))
+    }
+
+    /// 按数量精度格式化，与交易所推送的数量字符串一致，例如 "7.000"
+    pub fn format_quantity(&self, quantity: f64) -> String {
+        format!("{:.*}", self.quantity_precision as usize, self.round_quantity(quantity))
+    }
+
+    /// 将用户输入或行情中的数量字符串规范化（"7"、"7.0" -> "7.000"），无法解析时返回 None
+    pub fn normalize_quantity(&self, quantity: &str) -> Option<String> {
+        let value: f64 = quantity.trim().parse().ok()?;
+        Some(self.format_quantity(value))
+    }
+
+    /// 检查数量是否满足步长与上下限
+    pub fn validate_quantity(&self, quantity: f64) -> Result<(), Box<dyn Error>> {
+        if quantity < self.min_qty {
+            return Err(format!("{} 数量 {} 小于最小下单量 {}", self.symbol, quantity, self.min_qty).into());
+        }
+        if self.max_qty > 0.0 && quantity > self.max_qty {
+            return Err(format!("{} 数量 {} 大于最大下单量 {}", self.symbol, quantity, self.max_qty).into());
+        }
+        self.validate_step(quantity)
+    }
+
+    /// 只检查数量是否为步长的整数倍（成交数量不受下单量上下限约束）
+    pub fn validate_step(&self, quantity: f64) -> Result<(), Box<dyn Error>> {
+        if !is_multiple_of(quantity, self.step_size) {
+            return Err(format!("{} 数量 {} 不是步长 {} 的整数倍", self.symbol, quantity, self.step_size).into());
+        }
+        Ok(())
+    }
+
+    /// 检查价格与数量是否满足交易所的下单规则
+    pub fn validate_order(&self, price: f64, quantity: f64) -> Result<(), Box<dyn Error>> {
+        if !is_multiple_of(price, self.tick_size) {
+            return Err(format!("{} 价格 {} 不是最小变动 {} 的整数倍", self.symbol, price, self.tick_size).into());
+        }
+        self.validate_quantity(quantity)?;
+        if self.min_notional > 0.0 && price * quantity < self.min_notional {
+            return Err(format!("{} 名义价值 {} 小于最小值 {}", self.symbol, price * quantity, self.min_notional).into());
+        }
+        Ok(())
+    }
+}
+
+/// 交易所的品种元数据集合，按交易对查询（不区分大小写）
+#[derive(Debug, Clone)]
+pub struct InstrumentRegistry {
+    pub exchange: Exchange,
+    instruments: HashMap<String, Instrument>,
+}
+
+impl InstrumentRegistry {
+    pub fn new(exchange: Exchange) -> Self {
+        Self { exchange, instruments: HashMap::new() }
+    }
+
+    pub fn insert(&mut self, instrument: Instrument) {
+        self.instruments.insert(instrument.symbol.to_uppercase(), instrument);
+    }
+
+    pub fn get(&self, symbol: &str) -> Option<&Instrument> {
+        self.instruments.get(&symbol.to_uppercase())
+    }
+
+    pub fn len(&self) -> usize {
+        self.instruments.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.instruments.is_empty()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Instrument> {
+        self.instruments.values()
+    }
+
+    /// 规范化某个交易对的数量字符串，未知交易对或无法解析时原样返回
+    pub fn normalize_quantity(&self, symbol: &str, quantity: &str) -> String {
+        self.get(symbol)
+            .and_then(|instrument| instrument.normalize_quantity(quantity))
+            .unwrap_or_else(|| quantity.to_string())
+    }
+
+    /// 品种信息的 REST 地址：币安为 exchangeInfo，OKX 为永续合约的 public/instruments，
+    /// Bybit 为正向与反向合约的 instruments-info（两者合并）
+    pub fn exchange_info_urls(exchange: Exchange) -> &'static [&'static str] {
+        match exchange {
+            Exchange::Binance => &["https://fapi.binance.com/fapi/v1/exchangeInfo"],
+            Exchange::BinanceCoinFutures => &["https://dapi.binance.com/dapi/v1/exchangeInfo"],
+            Exchange::BinanceSpot => &["https://api.binance.com/api/v3/exchangeInfo"],
+            Exchange::Okx => &["https://www.okx.com/api/v5/public/instruments?instType=SWAP"],
+            Exchange::Bybit => &[
+                "https://api.bybit.com/v5/market/instruments-info?category=linear&limit=1000",
+                "https://api.bybit.com/v5/market/instruments-info?category=inverse&limit=1000",
+            ],
+        }
+    }
+
+    /// 解析 exchangeInfo 响应（OKX 为 public/instruments 响应，Bybit 为 instruments-info 响应）
+    pub fn from_exchange_info(exchange: Exchange, json: &str) -> Result<Self, Box<dyn Error>> {
+        let info: Value = serde_json::from_str(json)?;
+        if exchange == Exchange::Okx {
+            return Self::from_okx_instruments(&info);
+        }
+        if exchange == Exchange::Bybit {
+            return Self::from_bybit_instruments(&info);
+        }
+        let symbols = info["symbols"].as_array().ok_or("exchangeInfo 缺少 symbols 字段")?;
+        let mut registry = Self::new(exchange);
+        for symbol in symbols {
+            if let Some(instrument) = parse_symbol(exchange, symbol) {
+                registry.insert(instrument);
+            }
+        }
+        Ok(registry)
+    }
+
+    fn from_okx_instruments(info: &Value) -> Result<Self, Box<dyn Error>> {
+        if info["code"].as_str().unwrap_or("0") != "0" {
+            return Err(format!("OKX 品种信息请求失败: {}", info["msg"]).into());
+        }
+        let data = info["data"].as_array().ok_or("OKX 品种信息缺少 data 字段")?;
+        let mut registry = Self::new(Exchange::Okx);
+        for item in data {
+            if let Some(instrument) = parse_okx_instrument(item) {
+                registry.insert(instrument);
+            }
+        }
+        Ok(registry)
+    }
+
+    fn from_bybit_instruments(info: &Value) -> Result<Self, Box<dyn Error>> {
+        if info["retCode"].as_i64().unwrap_or(0) != 0 {
+            return Err(format!("Bybit 品种信息请求失败: {}", info["retMsg"]).into());
+        }
+        let list = info["result"]["list"].as_array().ok_or("Bybit 品种信息缺少 result.list 字段")?;
+        let mut registry = Self::new(Exchange::Bybit);
+        for item in list {
+            if let Some(instrument) = parse_bybit_instrument(item) {
+                registry.insert(instrument);
+            }
+        }
+        Ok(registry)
+    }
+
+    /// 通过 REST 获取 exchangeInfo，有多个地址时合并
+    pub async fn fetch(exchange: Exchange) -> Result<Self, Box<dyn Error>> {
+        let urls = Self::exchange_info_urls(exchange);
+        if urls.is_empty() {
+            return Err(format!("暂不支持获取 {} 的品种信息", exchange.as_str()).into());
+        }
+        let client = Client::builder()
+            .timeout(FETCH_TIMEOUT)
+            .connect_timeout(CONNECT_TIMEOUT)
+            .build()?;
+        let mut registry = Self::new(exchange);
+        for url in urls {
+            let body = client.get(*url).send().await?.text().await?;
+            for instrument in Self::from_exchange_info(exchange, &body)?.instruments.into_values() {
+                registry.insert(instrument);
+            }
+        }
+        Ok(registry)
+    }
+
+    /// 从本地缓存文件加载
+    pub fn load_from_file(exchange: Exchange, path: &str) -> Result<Self, Box<dyn Error>> {
+        let content = fs::read_to_string(path)?;
+        let instruments: Vec<Instrument> = serde_json::from_str(&content)?;
+        let mut registry = Self::new(exchange);
+        for instrument in instruments {
+            registry.insert(instrument);
+        }
+        Ok(registry)
+    }
+
+    /// 保存到本地缓存文件，供离线时使用
+    pub fn save_to_file(&self, path: &str) -> Result<(), Box<dyn Error>> {
+        let mut instruments: Vec<&Instrument> = self.instruments.values().collect();
+        instruments.sort_by(|a, b| a.symbol.cmp(&b.symbol));
+        fs::write(path, serde_json::to_string_pretty(&instruments)?)?;
+        Ok(())
+    }
+
+    /// 优先从交易所获取并刷新缓存文件，获取失败时退回到缓存文件
+    pub async fn load_or_fetch(exchange: Exchange, cache_path: &str) -> Result<Self, Box<dyn Error>> {
+        let fetched = Self::fetch(exchange).await.map_err(|e| e.to_string());
+        match fetched {
+            Ok(registry) => {
+                if let Err(e) = registry.save_to_file(cache_path) {
+                    eprintln!("[品种信息] 写入缓存 {} 失败: {}", cache_path, e);
+                }
+                println!("[品种信息] 已从交易所加载 {} 个品种", registry.len());
+                Ok(registry)
+            }
+            Err(e) => {
+                eprintln!("[品种信息] 获取 exchangeInfo 失败: {}，使用缓存 {}", e, cache_path);
+                Self::load_from_file(exchange, cache_path)
+            }
+        }
+    }
+}
+
+/// 解析 exchangeInfo 中的单个品种，缺少价格或数量过滤器的品种跳过
+fn parse_symbol(exchange: Exchange, symbol: &Value) -> Option<Instrument> {
+    let filters = symbol["filters"].as_array()?;
+    let filter = |filter_type: &str| filters.iter().find(|f| f["filterType"] == filter_type);
+    let number = |value: &Value| -> f64 {
+        value.as_str().and_then(|s| s.parse().ok()).or_else(|| value.as_f64()).unwrap_or(0.0)
+    };
+
+    let price_filter = filter("PRICE_FILTER")?;
+    let lot_size = filter("LOT_SIZE")?;
+    let tick_size = price_filter["tickSize"].as_str()?;
+    let step_size = lot_size["stepSize"].as_str()?;
+    // U 本位为 MIN_NOTIONAL.notional，现货为 NOTIONAL / MIN_NOTIONAL 的 minNotional，币本位没有该限制
+    let min_notional = filter("MIN_NOTIONAL")
+        .or_else(|| filter("NOTIONAL"))
+        .map(|f| if f["notional"].is_null() { number(&f["minNotional"]) } else { number(&f["notional"]) })
+        .unwrap_or(0.0);
+
+    // 现货没有 pricePrecision / quantityPrecision，由最小变动推算
+    let price_precision = symbol["pricePrecision"].as_u64().map(|p| p as u32).unwrap_or_else(|| decimals(tick_size));
+    let quantity_precision = symbol["quantityPrecision"].as_u64().map(|p| p as u32).unwrap_or_else(|| decimals(step_size));
+    let contract_type = match exchange {
+        Exchange::BinanceSpot => "SPOT".to_string(),
+        _ => symbol["contractType"].as_str().unwrap_or("").to_string(),
+    };
+    // 币本位合约的状态字段为 contractStatus
+    let status = symbol["status"]
+        .as_str()
+        .or_else(|| symbol["contractStatus"].as_str())
+        .unwrap_or("")
+        .to_string();
+
+    Some(Instrument {
+        symbol: symbol["symbol"].as_str()?.to_string(),
+        base_asset: symbol["baseAsset"].as_str().unwrap_or("").to_string(),
+        quote_asset: symbol["quoteAsset"].as_str().unwrap_or("").to_string(),
+        contract_type,
+        status,
+        tick_size: tick_size.parse().ok()?,
+        step_size: step_size.parse().ok()?,
+        min_qty: number(&lot_size["minQty"]),
+        max_qty: number(&lot_size["maxQty"]),
+        min_notional,
+        price_precision,
+        quantity_precision,
+        // 只有币本位合约带 contractSize（每张面值，美元）
+        contract_size: symbol["contractSize"].as_f64().map(ContractSize::Inverse),
+    })
+}
+
+/// 解析 OKX public/instruments 中的单个品种。合约数量单位为张：
+/// 正向合约（ctType=linear）每张 ctVal 个币，反向合约（ctType=inverse）每张面值 ctVal 美元
+fn parse_okx_instrument(item: &Value) -> Option<Instrument> {
+    let text = |key: &str| item[key].as_str().unwrap_or("").to_string();
+    let number = |key: &str| item[key].as_str().and_then(|s| s.parse::<f64>().ok());
+    let tick_size = item["tickSz"].as_str()?;
+    let step_size = item["lotSz"].as_str()?;
+    // 合约的 baseCcy / quoteCcy 为空，从标的指数（例如 "BTC-USDT"）拆出
+    let uly = text("uly");
+    let mut pair = uly.split('-');
+    let contract_size = match (item["ctType"].as_str(), number("ctVal")) {
+        (Some("linear"), Some(ct_val)) => Some(ContractSize::Linear(ct_val)),
+        (Some("inverse"), Some(ct_val)) => Some(ContractSize::Inverse(ct_val)),
+        _ => None,
+    };
+    let status = match item["state"].as_str() {
+        Some("live") => "TRADING".to_string(),
+        other => other.unwrap_or("").to_string(),
+    };
+
+    Some(Instrument {
+        symbol: item["instId"].as_str()?.to_string(),
+        base_asset: pair.next().unwrap_or("").to_string(),
+        quote_asset: pair.next().unwrap_or("").to_string(),
+        contract_type: text("instType"),
+        status,
+        tick_size: tick_size.parse().ok()?,
+        step_size: step_size.parse().ok()?,
+        min_qty: number("minSz").unwrap_or(0.0),
+        max_qty: number("maxLmtSz").unwrap_or(0.0),
+        min_notional: 0.0,
+        price_precision: decimals(tick_size),
+        quantity_precision: decimals(step_size),
+        contract_size,
+    })
+}
+
+/// 解析 Bybit instruments-info 中的单个合约。正向合约数量单位为币；
+/// 反向合约（contractType 为 InversePerpetual / InverseFutures）数量单位为张，每张 1 美元
+fn parse_bybit_instrument(item: &Value) -> Option<Instrument> {
+    let text = |value: &Value| value.as_str().unwrap_or("").to_string();
+    let number = |value: &Value| value.as_str().and_then(|s| s.parse::<f64>().ok()).unwrap_or(0.0);
+    let tick_size = item["priceFilter"]["tickSize"].as_str()?;
+    let lot_size = &item["lotSizeFilter"];
+    let step_size = lot_size["qtyStep"].as_str()?;
+    let contract_type = text(&item["contractType"]);
+    let contract_size = contract_type.starts_with("Inverse").then_some(ContractSize::Inverse(1.0));
+    let status = match item["status"].as_str() {
+        Some("Trading") => "TRADING".to_string(),
+        other => other.unwrap_or("").to_string(),
+    };
+
+    Some(Instrument {
+        symbol: item["symbol"].as_str()?.to_string(),
+        base_asset: text(&item["baseCoin"]),
+        quote_asset: text(&item["quoteCoin"]),
+        contract_type,
+        status,
+        tick_size: tick_size.parse().ok()?,
+        step_size: step_size.parse().ok()?,
+        min_qty: number(&lot_size["minOrderQty"]),
+        max_qty: number(&lot_size["maxOrderQty"]),
+        // 只有正向合约有 minNotionalValue
+        min_notional: number(&lot_size["minNotionalValue"]),
+        price_precision: decimals(tick_size),
+        quantity_precision: decimals(step_size),
+        contract_size,
+    })
+}
+
+/// "0.00100000" -> 3
+fn decimals(step: &str) -> u32 {
+    step.split('.')
+        .nth(1)
+        .map(|frac| frac.trim_end_matches('0').len() as u32)
+        .unwrap_or(0)
+}
+
+fn round_to_step(value: f64, step: f64) -> f64 {
+    if step <= 0.0 {
+        return value;
+    }
+    (value / step).round() * step
+}
+
+fn round_to(value: f64, precision: u32) -> f64 {
+    let factor = 10f64.powi(precision as i32);
+    (value * factor).round() / factor
+}
+
+fn is_multiple_of(value: f64, step: f64) -> bool {
+    if step <= 0.0 {
+        return true;
+    }
+    let ratio = value / step;
+    (ratio - ratio.round()).abs() < 1e-6
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn btcusdt() -> Instrument {
+        Instrument {
+            symbol: "BTCUSDT".to_string(),
+            base_asset: "BTC".to_string(),
+            quote_asset: "USDT".to_string(),
+            contract_type: "PERPETUAL".to_string(),
+            status: "TRADING".to_string(),
+            tick_size: 0.1,
+            step_size: 0.001,
+            min_qty: 0.001,
+            max_qty: 1000.0,
+            min_notional: 100.0,
+            price_precision: 1,
+            quantity_precision: 3,
+            contract_size: None,
+        }
+    }
+
+    #[test]
+    fn rounds_and_formats_to_step() {
+        let instrument = btcusdt();
+        assert_eq!(instrument.round_price(65000.149), 65000.1);
+        assert_eq!(instrument.round_price(65000.15000001), 65000.2);
+        // 0.1 + 0.2 的浮点误差被去掉
+        assert_eq!(instrument.round_quantity(0.1 + 0.2), 0.3);
+        assert_eq!(instrument.format_price(65000.0), "65000.0");
+        assert_eq!(instrument.format_quantity(7.0), "7.000");
+        assert_eq!(instrument.normalize_quantity(" 7 ").as_deref(), Some("7.000"));
+        assert_eq!(instrument.normalize_quantity("abc"), None);
+    }
+
+    #[test]
+    fn validates_step_with_float_tolerance() {
+        let instrument = btcusdt();
+        assert!(instrument.validate_step(0.3).is_ok());
+        assert!(instrument.validate_step(0.1 + 0.2).is_ok());
+        assert!(instrument.validate_step(0.0015).is_err());
+        assert!(is_multiple_of(0.7, 0.1));
+        assert!(!is_multiple_of(0.75, 0.1));
+        // 步长为 0 时不限制
+        assert!(is_multiple_of(0.123, 0.0));
+        assert!(instrument.validate_quantity(0.0005).is_err());
+        assert!(instrument.validate_order(65000.1, 0.002).is_ok());
+        assert!(instrument.validate_order(65000.15, 0.002).is_err());
+        // 名义价值 65 小于 100
+        assert!(instrument.validate_order(65000.0, 0.001).is_err());
+    }
+
+    #[test]
+    fn parses_futures_and_coin_futures_exchange_info() {
+        let json = r#"{"symbols": [
+            {"symbol": "BTCUSDT", "status": "TRADING", "contractType": "PERPETUAL", "baseAsset": "BTC", "quoteAsset": "USDT",
+             "pricePrecision": 2, "quantityPrecision": 3, "filters": [
+                {"filterType": "PRICE_FILTER", "tickSize": "0.10"},
+                {"filterType": "LOT_SIZE", "stepSize": "0.001", "minQty": "0.001", "maxQty": "1000"},
+                {"filterType": "MIN_NOTIONAL", "notional": "100"}]},
+            {"symbol": "NOFILTERS", "filters": []}
+        ]}"#;
+        let registry = InstrumentRegistry::from_exchange_info(Exchange::Binance, json).unwrap();
+        assert_eq!(registry.len(), 1);
+        let instrument = registry.get("btcusdt").unwrap();
+        assert_eq!((instrument.tick_size, instrument.step_size), (0.1, 0.001));
+        assert_eq!((instrument.min_qty, instrument.max_qty, instrument.min_notional), (0.001, 1000.0, 100.0));
+        assert_eq!((instrument.price_precision, instrument.quantity_precision), (2, 3));
+        assert!(instrument.is_trading());
+        assert_eq!(instrument.contract_size, None);
+
+        let json = r#"{"symbols": [
+            {"symbol": "BTCUSD_PERP", "contractStatus": "TRADING", "contractType": "PERPETUAL", "contractSize": 100,
+             "filters": [{"filterType": "PRICE_FILTER", "tickSize": "0.1"}, {"filterType": "LOT_SIZE", "stepSize": "1"}]}
+        ]}"#;
+        let registry = InstrumentRegistry::from_exchange_info(Exchange::BinanceCoinFutures, json).unwrap();
+        let instrument = registry.get("BTCUSD_PERP").unwrap();
+        assert!(instrument.is_trading());
+        assert_eq!(instrument.contract_size, Some(ContractSize::Inverse(100.0)));
+
+        assert!(InstrumentRegistry::from_exchange_info(Exchange::Binance, "{}").is_err());
+    }
+
+    #[test]
+    fn parses_okx_instruments() {
+        let json = r#"{"code": "0", "msg": "", "data": [
+            {"instId": "BTC-USDT-SWAP", "instType": "SWAP", "uly": "BTC-USDT", "ctType": "linear", "ctVal": "0.01",
+             "tickSz": "0.1", "lotSz": "0.01", "minSz": "0.01", "maxLmtSz": "100000", "state": "live"},
+            {"instId": "BTC-USD-SWAP", "instType": "SWAP", "uly": "BTC-USD", "ctType": "inverse", "ctVal": "100",
+             "tickSz": "0.1", "lotSz": "1", "minSz": "1", "state": "suspend"}
+        ]}"#;
+        let registry = InstrumentRegistry::from_exchange_info(Exchange::Okx, json).unwrap();
+        let linear = registry.get("BTC-USDT-SWAP").unwrap();
+        assert_eq!(linear.contract_size, Some(ContractSize::Linear(0.01)));
+        assert_eq!((linear.base_asset.as_str(), linear.quote_asset.as_str()), ("BTC", "USDT"));
+        assert_eq!(linear.quantity_precision, 2);
+        assert!(linear.is_trading());
+        let inverse = registry.get("BTC-USD-SWAP").unwrap();
+        assert_eq!(inverse.contract_size, Some(ContractSize::Inverse(100.0)));
+        assert!(!inverse.is_trading());
+
+        let error = r#"{"code": "50011", "msg": "Too Many Requests", "data": []}"#;
+        assert!(InstrumentRegistry::from_exchange_info(Exchange::Okx, error).is_err());
+    }
+
+    #[test]
+    fn parses_bybit_instruments() {
+        let json = r#"{"retCode": 0, "retMsg": "OK", "result": {"category": "inverse", "list": [
+            {"symbol": "BTCUSD", "contractType": "InversePerpetual", "status": "Trading", "baseCoin": "BTC", "quoteCoin": "USD",
+             "priceFilter": {"tickSize": "0.50"}, "lotSizeFilter": {"qtyStep": "1", "minOrderQty": "1", "maxOrderQty": "1000000"}},
+            {"symbol": "BTCUSDT", "contractType": "LinearPerpetual", "status": "Trading", "baseCoin": "BTC", "quoteCoin": "USDT",
+             "priceFilter": {"tickSize": "0.10"}, "lotSizeFilter": {"qtyStep": "0.001", "minOrderQty": "0.001", "minNotionalValue": "5"}}
+        ]}}"#;
+        let registry = InstrumentRegistry::from_exchange_info(Exchange::Bybit, json).unwrap();
+        let inverse = registry.get("BTCUSD").unwrap();
+        assert_eq!(inverse.contract_size, Some(ContractSize::Inverse(1.0)));
+        assert_eq!(inverse.price_precision, 1);
+        assert!(inverse.is_trading());
+        let linear = registry.get("BTCUSDT").unwrap();
+        assert_eq!(linear.contract_size, None);
+        assert_eq!(linear.min_notional, 5.0);
+
+        let error = r#"{"retCode": 10001, "retMsg": "params error", "result": {}}"#;
+        assert!(InstrumentRegistry::from_exchange_info(Exchange::Bybit, error).is_err());
+    }
+
+    #[test]
+    fn reads_legacy_numeric_contract_size() {
+        let mut value = serde_json::to_value(btcusdt()).unwrap();
+        value["contract_size"] = serde_json::json!(100.0);
+        let legacy: Instrument = serde_json::from_value(value.clone()).unwrap();
+        assert_eq!(legacy.contract_size, Some(ContractSize::Inverse(100.0)));
+
+        value["contract_size"] = serde_json::json!({"Linear": 0.01});
+        let current: Instrument = serde_json::from_value(value.clone()).unwrap();
+        assert_eq!(current.contract_size, Some(ContractSize::Linear(0.01)));
+
+        value.as_object_mut().unwrap().remove("contract_size");
+        let missing: Instrument = serde_json::from_value(value).unwrap();
+        assert_eq!(missing.contract_size, None);
+
+        // 保存后再读取不丢失面值
+        let mut instrument = btcusdt();
+        instrument.contract_size = Some(ContractSize::Inverse(10.0));
+        let round_trip: Instrument = serde_json::from_str(&serde_json::to_string(&instrument).unwrap()).unwrap();
+        assert_eq!(round_trip, instrument);
+    }
+}
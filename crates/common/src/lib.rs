@@ -1,2 +1,3 @@
 pub mod exchange;
+pub mod instrument;
 pub use exchange::Exchange;
\ No newline at end of file
@@ -1,6 +1,8 @@
 use serde::{Serialize, Deserialize}; // 允许序列化和反序列化，以便于在网络中传输
 use serde_json::Value; // 这里引入 `Value`
 use std::collections::HashMap; // 这里引入 `HashMap`
+use crate::market_data::{Bbo, BookDelta, BookSnapshot, Trade};
+use crate::user_data::{AccountUpdateEvent, MarginCallEvent, OrderUpdateEvent};
 
 
 #[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize, Eq, Hash)]
@@ -8,12 +10,48 @@ pub enum EventType {
     AggTrade,
     Depth,
     Kline,
-    Trade
+    Trade,
+    BookTicker,
+    MarkPrice,
+    Liquidation,
+    // 归集成交 id 缺口及补录结果，载荷为 EventPayload::AggTradeGap
+    AggTradeGap,
+    // 归一化行情事件（见 market_data），与原始事件同时分发，处理器无需区分交易所
+    MarketTrade,
+    BookDelta,
+    BookSnapshot,
+    Bbo,
+    // 用户数据流事件（见 user_data），由 UserDataAgent 分发
+    OrderUpdate,
+    AccountUpdate,
+    MarginCall,
+    // 连接生命周期事件，载荷均为 EventPayload::Connection
+    Connected,
+    Disconnected,
+    Resubscribed,
+    Stale,
+    // 行情统计快照，由市场代理定期分发，载荷为 EventPayload::FeedStats
+    FeedStats,
 }
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub enum EventPayload {
     AggTrade(AggTradeEvent),
     Depth(DepthEvent),
+    Kline(KlineEvent),
+    Trade(TradeEvent),
+    BookTicker(BookTickerEvent),
+    MarkPrice(MarkPriceEvent),
+    Liquidation(LiquidationEvent),
+    AggTradeGap(AggTradeGapEvent),
+    MarketTrade(Trade),
+    BookDelta(BookDelta),
+    BookSnapshot(BookSnapshot),
+    Bbo(Bbo),
+    OrderUpdate(OrderUpdateEvent),
+    AccountUpdate(AccountUpdateEvent),
+    MarginCall(MarginCallEvent),
+    Connection(ConnectionEvent),
+    FeedStats(FeedStatsEvent),
 }
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -24,8 +62,17 @@ pub enum BinanceEvent {
     // 如果将来有其他事件类型，可以在这里添加，例如：
     #[serde(rename = "depthUpdate")]
     Depth(DepthEvent),
-    // #[serde(rename = "kline")]
-    // Kline(KlineEvent),
+    #[serde(rename = "kline")]
+    Kline(KlineEvent),
+    #[serde(rename = "trade")]
+    Trade(TradeEvent),
+    // 现货的 bookTicker 不带 "e" 字段，由 market_agent 按流名称或字段特征识别
+    #[serde(rename = "bookTicker")]
+    BookTicker(BookTickerEvent),
+    #[serde(rename = "markPriceUpdate")]
+    MarkPrice(MarkPriceEvent),
+    #[serde(rename = "forceOrder")]
+    Liquidation(LiquidationEvent),
 }
 
 impl BinanceEvent {
@@ -33,12 +80,16 @@ impl BinanceEvent {
         match self {
             BinanceEvent::AggTrade(_) => EventType::AggTrade,
             BinanceEvent::Depth(_) => EventType::Depth,
-            // BinanceEvent::Kline(_) => EventType::Kline,
+            BinanceEvent::Kline(_) => EventType::Kline,
+            BinanceEvent::Trade(_) => EventType::Trade,
+            BinanceEvent::BookTicker(_) => EventType::BookTicker,
+            BinanceEvent::MarkPrice(_) => EventType::MarkPrice,
+            BinanceEvent::Liquidation(_) => EventType::Liquidation,
         }
     }
 }
 
-#[derive(Serialize, Deserialize, Debug, Clone)]
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
 pub struct AggTradeEvent {
     #[serde(alias = "e", alias = "event", default)]
     pub event: String,
@@ -46,6 +97,10 @@ pub struct AggTradeEvent {
     pub event_time: u64,
     #[serde(alias = "a", alias = "aggTradeId", default)]
     pub agg_trade_id: u64,
+    #[serde(alias = "f", alias = "firstTradeId", default)]
+    pub first_trade_id: u64,         // 归集的第一笔逐笔成交 id
+    #[serde(alias = "l", alias = "lastTradeId", default)]
+    pub last_trade_id: u64,          // 归集的最后一笔逐笔成交 id
     #[serde(alias = "s", alias = "symbol", default)]
     pub symbol: String,
     #[serde(alias = "p", alias = "price", default)]
@@ -60,12 +115,516 @@ pub struct AggTradeEvent {
     #[serde(skip)]  // ✅ 这个字段不会被 `serde_json` 解析
     pub received_timestamp: u128, // ✅ 记录 WebSocket 接收到的时间戳
 
+    #[serde(skip)]
+    pub stream: String, // 来源流名称，例如 "btcusdt@aggTrade"
+
+    #[serde(skip)]
+    pub contract_size: Option<ContractSize>, // 数量单位为张时每张的大小，数量为币时为 None
+
+    #[serde(default)]
+    pub backfilled: bool,           // 是否为 id 缺口后通过 REST 补录的成交
+
     // 捕获额外的未知字段
     #[serde(flatten)]
     pub extra: HashMap<String, Value>,
 }
 
+impl AggTradeEvent {
+    /// 以币计的成交数量
+    pub fn base_quantity(&self) -> f64 {
+        base_quantity(&self.price, &self.quantity, self.contract_size)
+    }
+
+    /// 名义价值（计价货币）
+    pub fn notional(&self) -> f64 {
+        notional(&self.price, &self.quantity, self.contract_size)
+    }
+}
+
+/// 合约的数量单位为“张”时每张的大小
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum ContractSize {
+    /// 反向（币本位）合约：每张面值为若干计价货币，例如币安 BTCUSD_PERP 每张 100 美元
+    Inverse(f64),
+    /// 正向合约：每张对应若干基础货币，例如 OKX BTC-USDT-SWAP 每张 0.01 BTC
+    Linear(f64),
+}
+
+impl ContractSize {
+    /// 张数折算为币：反向合约为 张数 × 面值 / 价格，正向合约为 张数 × 每张币数
+    pub fn base_quantity(&self, price: f64, contracts: f64) -> f64 {
+        match *self {
+            ContractSize::Inverse(size) => if price > 0.0 { contracts * size / price } else { 0.0 },
+            ContractSize::Linear(size) => contracts * size,
+        }
+    }
+
+    /// 张数对应的名义价值（计价货币）
+    pub fn notional(&self, price: f64, contracts: f64) -> f64 {
+        match *self {
+            ContractSize::Inverse(size) => contracts * size,
+            ContractSize::Linear(size) => contracts * size * price,
+        }
+    }
+}
+
+/// 以币计的数量；contract_size 为 None 时数量本身就是币
+pub fn base_quantity(price: &str, quantity: &str, contract_size: Option<ContractSize>) -> f64 {
+    let qty: f64 = quantity.parse().unwrap_or(0.0);
+    match contract_size {
+        Some(size) => size.base_quantity(price.parse().unwrap_or(0.0), qty),
+        None => qty,
+    }
+}
+
+/// 名义价值：合约按每张大小折算，其余市场为 数量 × 价格
+pub fn notional(price: &str, quantity: &str, contract_size: Option<ContractSize>) -> f64 {
+    let qty: f64 = quantity.parse().unwrap_or(0.0);
+    let price: f64 = price.parse().unwrap_or(0.0);
+    match contract_size {
+        Some(size) => size.notional(price, qty),
+        None => qty * price,
+    }
+}
+
+/// K 线周期
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum KlineInterval {
+    #[serde(rename = "1s")]
+    Second1, // 仅现货支持
+    #[serde(rename = "1m")]
+    Minute1,
+    #[serde(rename = "3m")]
+    Minute3,
+    #[serde(rename = "5m")]
+    Minute5,
+    #[serde(rename = "15m")]
+    Minute15,
+    #[serde(rename = "30m")]
+    Minute30,
+    #[serde(rename = "1h")]
+    Hour1,
+    #[serde(rename = "2h")]
+    Hour2,
+    #[serde(rename = "4h")]
+    Hour4,
+    #[serde(rename = "6h")]
+    Hour6,
+    #[serde(rename = "8h")]
+    Hour8,
+    #[serde(rename = "12h")]
+    Hour12,
+    #[serde(rename = "1d")]
+    Day1,
+    #[serde(rename = "3d")]
+    Day3,
+    #[serde(rename = "1w")]
+    Week1,
+    #[serde(rename = "1M")]
+    Month1,
+}
+
+impl KlineInterval {
+    pub const ALL: [KlineInterval; 16] = [
+        KlineInterval::Second1, KlineInterval::Minute1, KlineInterval::Minute3, KlineInterval::Minute5,
+        KlineInterval::Minute15, KlineInterval::Minute30, KlineInterval::Hour1, KlineInterval::Hour2,
+        KlineInterval::Hour4, KlineInterval::Hour6, KlineInterval::Hour8, KlineInterval::Hour12,
+        KlineInterval::Day1, KlineInterval::Day3, KlineInterval::Week1, KlineInterval::Month1,
+    ];
+
+    /// 交易所使用的周期字符串，例如 "15m"
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            KlineInterval::Second1 => "1s",
+            KlineInterval::Minute1 => "1m",
+            KlineInterval::Minute3 => "3m",
+            KlineInterval::Minute5 => "5m",
+            KlineInterval::Minute15 => "15m",
+            KlineInterval::Minute30 => "30m",
+            KlineInterval::Hour1 => "1h",
+            KlineInterval::Hour2 => "2h",
+            KlineInterval::Hour4 => "4h",
+            KlineInterval::Hour6 => "6h",
+            KlineInterval::Hour8 => "8h",
+            KlineInterval::Hour12 => "12h",
+            KlineInterval::Day1 => "1d",
+            KlineInterval::Day3 => "3d",
+            KlineInterval::Week1 => "1w",
+            KlineInterval::Month1 => "1M",
+        }
+    }
+
+    /// 由周期字符串解析（区分大小写，"1m" 为 1 分钟，"1M" 为 1 个月）
+    pub fn parse(s: &str) -> Option<Self> {
+        Self::ALL.iter().copied().find(|i| i.as_str() == s)
+    }
+
+    /// 订阅流名称，例如 btcusdt@kline_1m
+    pub fn stream_name(&self, symbol: &str) -> String {
+        format!("{}@kline_{}", symbol.to_lowercase(), self.as_str())
+    }
+}
+
+/// K 线事件，对应 <symbol>@kline_<interval>
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct KlineEvent {
+    #[serde(alias = "e", alias = "event", default)]
+    pub event: String,               // 事件类型 "kline"
+
+    #[serde(alias = "E", alias = "eventTime", default)]
+    pub event_time: u64,             // 事件时间
+
+    #[serde(alias = "s", alias = "symbol", default)]
+    pub symbol: String,              // 交易对
+
+    #[serde(alias = "k")]
+    pub kline: KlineData,            // K 线数据
+
+    #[serde(skip)]
+    pub received_timestamp: u128,    // 记录 WebSocket 接收到的时间戳
+
+    #[serde(skip)]
+    pub stream: String,              // 来源流名称，例如 "btcusdt@kline_1m"
+
+    #[serde(flatten)]
+    pub extra: HashMap<String, Value>, // 捕获额外的未知字段
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct KlineData {
+    #[serde(alias = "t", default)]
+    pub start_time: u64,             // 这根 K 线的起始时间
+
+    #[serde(alias = "T", default)]
+    pub close_time: u64,             // 这根 K 线的结束时间
+
+    #[serde(alias = "s", default)]
+    pub symbol: String,              // 交易对
+
+    #[serde(alias = "i")]
+    pub interval: KlineInterval,     // K 线周期
+
+    #[serde(alias = "f", default)]
+    pub first_trade_id: i64,         // 第一笔成交 id（无成交时为 -1）
+
+    #[serde(alias = "L", default)]
+    pub last_trade_id: i64,          // 末一笔成交 id（无成交时为 -1）
+
+    #[serde(alias = "o", default)]
+    pub open: String,                // 开盘价
+
+    #[serde(alias = "c", default)]
+    pub close: String,               // 收盘价（未完结时为最新价）
+
+    #[serde(alias = "h", default)]
+    pub high: String,                // 最高价
+
+    #[serde(alias = "l", default)]
+    pub low: String,                 // 最低价
+
+    #[serde(alias = "v", default)]
+    pub volume: String,              // 成交量
+
+    #[serde(alias = "n", default)]
+    pub trade_count: u64,            // 成交笔数
+
+    #[serde(alias = "x", default)]
+    pub is_closed: bool,             // 这根 K 线是否已完结
+
+    #[serde(alias = "q", default)]
+    pub quote_volume: String,        // 成交额
+
+    #[serde(alias = "V", default)]
+    pub taker_buy_volume: String,    // 主动买入成交量
+
+    #[serde(alias = "Q", default)]
+    pub taker_buy_quote_volume: String, // 主动买入成交额
+}
+
+/// 归集成交 id 缺口：from_id..=to_id 未从行情流收到，补录完成（或放弃）后分发。
+/// complete 为 false 时缺口内的成交（全部或部分）已丢失
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct AggTradeGapEvent {
+    pub symbol: String,              // 交易对（大写）
+    pub from_id: u64,                // 缺失的第一个归集成交 id
+    pub to_id: u64,                  // 缺失的最后一个归集成交 id
+    pub from_time: u64,              // 缺口前最后一笔成交的时间（毫秒）
+    pub to_time: u64,                // 缺口后第一笔成交的时间（毫秒）
+    pub recovered: u64,              // 补录到的成交数
+    pub complete: bool,              // 是否完整补录
+    pub reason: String,              // 未完整补录的原因
+}
+
+/// 连接生命周期事件：行情连接建立、断开、重连后恢复订阅、静默
+/// 断开到恢复订阅之间的行情可能缺失，依赖连续性的处理器（如订单簿）应据此重新同步
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ConnectionEvent {
+    pub exchange: String,            // 交易所，例如 "binance"
+    pub connection_id: u64,          // 连接编号（同一 agent 内唯一）
+    pub streams: Vec<String>,        // 受影响的订阅流
+    pub reason: String,              // 断开或静默原因，其他事件为空
+    pub silent_ms: u64,              // 静默时长（毫秒），仅 Stale 事件有效
+    pub timestamp: u128,             // 本地时间戳（毫秒）
+}
+
+/// 单个订阅流的行情统计。计数为代理创建以来的累计值，
+/// 消息速率与延迟分位数只统计当前窗口（两次定期分发之间）
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct StreamStats {
+    pub stream: String,              // 流名称，例如 "btcusdt@aggTrade"
+    pub messages: u64,               // 累计消息数
+    pub message_rate: f64,           // 窗口内每秒消息数
+    pub parse_failures: u64,         // 累计解析失败次数
+    pub unknown_events: u64,         // 累计未识别的事件类型或频道次数
+    pub reconnects: u64,             // 断线重连后恢复订阅的次数
+    pub last_message_age_ms: Option<u64>, // 距最后一条消息的时间（毫秒），未收到过为 None
+    pub latency_samples: u64,        // 窗口内带交易所事件时间的消息数
+    pub latency_p50_ms: Option<f64>, // 交易所事件时间到本地接收的延迟分位数（毫秒），
+    pub latency_p90_ms: Option<f64>, // 包含两端时钟偏差，可能为负
+    pub latency_p99_ms: Option<f64>,
+    pub latency_max_ms: Option<f64>,
+}
+
+/// 市场代理的行情统计快照
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct FeedStatsEvent {
+    pub exchange: String,            // 交易所，例如 "binance"
+    pub timestamp: u128,             // 本地时间戳（毫秒）
+    pub window_ms: u64,              // 当前窗口已持续的时间（毫秒）
+    pub disconnects: u64,            // 所有连接累计断线次数
+    pub streams: Vec<StreamStats>,   // 按流名称排序
+}
+
+impl FeedStatsEvent {
+    /// 查询某个流的统计
+    pub fn stream(&self, stream: &str) -> Option<&StreamStats> {
+        self.streams.iter().find(|s| s.stream == stream)
+    }
+
+    /// 所有流的累计消息数
+    pub fn total_messages(&self) -> u64 {
+        self.streams.iter().map(|s| s.messages).sum()
+    }
+
+    /// 所有流的累计解析失败与未识别事件数
+    pub fn total_errors(&self) -> u64 {
+        self.streams.iter().map(|s| s.parse_failures + s.unknown_events).sum()
+    }
+}
+
+/// 逐笔成交事件，对应 <symbol>@trade（未经归集的单笔成交）
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct TradeEvent {
+    #[serde(alias = "e", alias = "event", default)]
+    pub event: String,               // 事件类型 "trade"
+
+    #[serde(alias = "E", alias = "eventTime", default)]
+    pub event_time: u64,             // 事件时间
+
+    #[serde(alias = "T", alias = "tradeTime", default)]
+    pub trade_time: u64,             // 成交时间
+
+    #[serde(alias = "s", alias = "symbol", default)]
+    pub symbol: String,              // 交易对
+
+    #[serde(alias = "t", alias = "tradeId", default)]
+    pub trade_id: u64,               // 成交 id
+
+    #[serde(skip)]
+    pub raw_trade_id: String,        // 非数值的成交 id（Bybit 合约为 UUID），此时 trade_id 为 0；币安为空
+
+    #[serde(alias = "p", alias = "price", default)]
+    pub price: String,               // 成交价
+
+    #[serde(alias = "q", alias = "quantity", default)]
+    pub quantity: String,            // 成交量
+
+    #[serde(alias = "m", alias = "isBuyerMaker", default)]
+    pub is_buyer_maker: bool,        // 买方是否为挂单方
+
+    #[serde(alias = "X", alias = "orderType", default)]
+    pub order_type: String,          // 订单类型（合约："MARKET"、"LIQUIDATION" 等；现货无此字段）
+
+    #[serde(skip)]
+    pub received_timestamp: u128,    // 记录 WebSocket 接收到的时间戳
+
+    #[serde(skip)]
+    pub stream: String,              // 来源流名称，例如 "btcusdt@trade"
+
+    #[serde(skip)]
+    pub contract_size: Option<ContractSize>, // 数量单位为张时每张的大小，数量为币时为 None
+
+    #[serde(flatten)]
+    pub extra: HashMap<String, Value>, // 捕获额外的未知字段
+}
+
+impl TradeEvent {
+    /// 以币计的成交数量
+    pub fn base_quantity(&self) -> f64 {
+        base_quantity(&self.price, &self.quantity, self.contract_size)
+    }
+
+    /// 名义价值（计价货币）
+    pub fn notional(&self) -> f64 {
+        notional(&self.price, &self.quantity, self.contract_size)
+    }
+}
+
+/// 最优挂单事件，对应 <symbol>@bookTicker，实时推送买一卖一的价格与数量
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct BookTickerEvent {
+    #[serde(alias = "e", alias = "event", default)]
+    pub event: String,               // 事件类型 "bookTicker"（现货为空）
+
+    #[serde(alias = "u", alias = "updateId", default)]
+    pub update_id: u64,              // 更新 id，越大越新
+
+    #[serde(alias = "E", alias = "eventTime", default)]
+    pub event_time: u64,             // 事件时间（现货为 0）
+
+    #[serde(alias = "T", alias = "transactionTime", default)]
+    pub transaction_time: u64,       // 撮合时间（现货为 0）
+
+    #[serde(alias = "s", alias = "symbol", default)]
+    pub symbol: String,              // 交易对
+
+    #[serde(alias = "b", alias = "bidPrice", default)]
+    pub bid_price: String,           // 买一价
+
+    #[serde(alias = "B", alias = "bidQty", default)]
+    pub bid_qty: String,             // 买一量
+
+    #[serde(alias = "a", alias = "askPrice", default)]
+    pub ask_price: String,           // 卖一价
+
+    #[serde(alias = "A", alias = "askQty", default)]
+    pub ask_qty: String,             // 卖一量
+
+    #[serde(skip)]
+    pub received_timestamp: u128,    // 记录 WebSocket 接收到的时间戳
+
+    #[serde(skip)]
+    pub stream: String,              // 来源流名称，例如 "btcusdt@bookTicker"
+
+    #[serde(skip)]
+    pub contract_size: Option<ContractSize>, // 数量单位为张时每张的大小，数量为币时为 None
+
+    #[serde(flatten)]
+    pub extra: HashMap<String, Value>, // 捕获额外的未知字段
+}
+
+/// 标记价格与资金费率事件，对应 <symbol>@markPrice / <symbol>@markPrice@1s / !markPrice@arr
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct MarkPriceEvent {
+    #[serde(alias = "e", alias = "event", default)]
+    pub event: String,               // 事件类型 "markPriceUpdate"
+
+    #[serde(alias = "E", alias = "eventTime", default)]
+    pub event_time: u64,             // 事件时间
+
+    #[serde(alias = "s", alias = "symbol", default)]
+    pub symbol: String,              // 交易对
+
+    #[serde(alias = "p", alias = "markPrice", default)]
+    pub mark_price: String,          // 标记价格
+
+    #[serde(alias = "i", alias = "indexPrice", default)]
+    pub index_price: String,         // 现货指数价格
+
+    #[serde(alias = "P", alias = "estimatedSettlePrice", default)]
+    pub estimated_settle_price: String, // 预估结算价（仅在结算前最后一小时有意义）
+
+    #[serde(alias = "r", alias = "fundingRate", default)]
+    pub funding_rate: String,        // 资金费率（交割合约为空）
+
+    #[serde(alias = "T", alias = "nextFundingTime", default)]
+    pub next_funding_time: u64,      // 下次资金费时间
+
+    #[serde(skip)]
+    pub received_timestamp: u128,    // 记录 WebSocket 接收到的时间戳
+
+    #[serde(skip)]
+    pub stream: String,              // 来源流名称，例如 "btcusdt@markPrice@1s"
+
+    #[serde(flatten)]
+    pub extra: HashMap<String, Value>, // 捕获额外的未知字段
+}
+
+/// 强平订单事件，对应 <symbol>@forceOrder 与全市场 !forceOrder@arr
+/// 交易所每个交易对每秒最多推送一条（最近一笔），不是完整的强平明细
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct LiquidationEvent {
+    #[serde(alias = "e", alias = "event", default)]
+    pub event: String,               // 事件类型 "forceOrder"
+
+    #[serde(alias = "E", alias = "eventTime", default)]
+    pub event_time: u64,             // 事件时间
+
+    #[serde(alias = "o")]
+    pub order: LiquidationOrder,     // 强平订单
+
+    #[serde(skip)]
+    pub received_timestamp: u128,    // 记录 WebSocket 接收到的时间戳
+
+    #[serde(skip)]
+    pub stream: String,              // 来源流名称，例如 "btcusdt@forceOrder"
+
+    #[serde(flatten)]
+    pub extra: HashMap<String, Value>, // 捕获额外的未知字段
+}
+
 #[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct LiquidationOrder {
+    #[serde(alias = "s", default)]
+    pub symbol: String,              // 交易对
+
+    #[serde(alias = "S", default)]
+    pub side: String,                // 订单方向，"SELL" 为多头被强平，"BUY" 为空头被强平
+
+    #[serde(alias = "o", default)]
+    pub order_type: String,          // 订单类型
+
+    #[serde(alias = "f", default)]
+    pub time_in_force: String,       // 有效方式
+
+    #[serde(alias = "q", default)]
+    pub quantity: String,            // 订单数量
+
+    #[serde(alias = "p", default)]
+    pub price: String,               // 订单价格
+
+    #[serde(alias = "ap", default)]
+    pub avg_price: String,           // 平均成交价
+
+    #[serde(alias = "X", default)]
+    pub status: String,              // 订单状态
+
+    #[serde(alias = "l", default)]
+    pub last_filled_qty: String,     // 最近一次成交量
+
+    #[serde(alias = "z", default)]
+    pub filled_qty: String,          // 累计成交量
+
+    #[serde(alias = "T", default)]
+    pub trade_time: u64,             // 成交时间
+
+    #[serde(skip)]
+    pub contract_size: Option<ContractSize>, // 数量单位为张时每张的大小，数量为币时为 None
+}
+
+impl LiquidationOrder {
+    /// 名义价值：按累计成交量与平均成交价计算，尚无成交时按订单数量与订单价格估算
+    pub fn notional(&self) -> f64 {
+        let filled: f64 = self.filled_qty.parse().unwrap_or(0.0);
+        let avg: f64 = self.avg_price.parse().unwrap_or(0.0);
+        if filled > 0.0 && avg > 0.0 {
+            return notional(&self.avg_price, &self.filled_qty, self.contract_size);
+        }
+        notional(&self.price, &self.quantity, self.contract_size)
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
 pub struct DepthEvent {
     #[serde(alias = "e", alias = "event", default)]
     pub event: String,               // 事件类型
@@ -97,10 +656,23 @@ pub struct DepthEvent {
     #[serde(skip)]
     pub received_timestamp: u128,    // 记录 WebSocket 接收到的时间戳
 
+    #[serde(skip)]
+    pub stream: String,              // 来源流名称，例如 "btcusdt@depth@100ms"
+
+    #[serde(skip)]
+    pub contract_size: Option<ContractSize>, // 档位数量单位为张时每张的大小，数量为币时为 None
+
     #[serde(flatten)]
     pub extra: HashMap<String, Value>, // 捕获额外的未知字段
 }
 
+impl DepthEvent {
+    /// 档位 (价格, 数量) 以币计的数量
+    pub fn level_base_quantity(&self, level: &(String, String)) -> f64 {
+        base_quantity(&level.0, &level.1, self.contract_size)
+    }
+}
+
 // #[derive(Debug, Clone, Serialize, Deserialize)]
 // pub struct OrderBookEvent {
 //     pub symbol: String,
@@ -1,2 +1,4 @@
 pub mod event_dispatcher;
-pub mod event;
\ No newline at end of file
+pub mod event;
+pub mod market_data;
+pub mod user_data;
@@ -0,0 +1,364 @@
+// event_engine/market_data.rs
+
+// 与交易所无关的行情数据模型：价格与数量均为数值，并标明来源交易所与品种。
+// 各 agent 按 EventEmission 配置分发原始事件（AggTrade、Depth 等）、这里的归一化事件或两者，
+// 处理器只订阅归一化事件即可同时覆盖所有交易所。
+// 数量单位：Trade 已折算为币；订单簿与最优买卖价（BookDelta、BookSnapshot、Bbo）保持交易所原始单位，
+// 合约品种为张，并通过 contract_size 标明每张大小，需要币数量时用 base_quantity 折算
+
+use std::error::Error;
+
+use serde::{Deserialize, Serialize};
+
+use crate::event::{AggTradeEvent, BookTickerEvent, ContractSize, DepthEvent, TradeEvent};
+
+/// 买卖方向
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Side {
+    Buy,
+    Sell,
+}
+
+impl Side {
+    /// 币安风格的 "买方是否为挂单方" 转换为 taker 方向
+    pub fn from_buyer_maker(is_buyer_maker: bool) -> Self {
+        if is_buyer_maker { Side::Sell } else { Side::Buy }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Side::Buy => "buy",
+            Side::Sell => "sell",
+        }
+    }
+}
+
+/// 价格档位
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct PriceLevel {
+    pub price: f64,
+    /// 数量为 0 表示删除该档位
+    pub quantity: f64,
+}
+
+impl PriceLevel {
+    /// 解析交易所的 (价格, 数量) 字符串。无法解析、非有限或为负的档位返回错误，
+    /// 不能按 0 处理：数量 0 表示删除档位，会悄悄破坏订单簿
+    pub fn parse(price: &str, quantity: &str) -> Result<PriceLevel, Box<dyn Error>> {
+        let parse = |value: &str| -> Result<f64, Box<dyn Error>> {
+            match value.parse::<f64>() {
+                Ok(v) if v.is_finite() && v >= 0.0 => Ok(v),
+                _ => Err(format!("无法解析的档位 [{}, {}]", price, quantity).into()),
+            }
+        };
+        Ok(PriceLevel { price: parse(price)?, quantity: parse(quantity)? })
+    }
+
+    /// 档位数量折算为币，contract_size 为 None 时数量本身即为币
+    pub fn base_quantity(&self, contract_size: Option<ContractSize>) -> f64 {
+        match contract_size {
+            Some(size) => size.base_quantity(self.price, self.quantity),
+            None => self.quantity,
+        }
+    }
+
+    /// 解析整组档位，任一档位无法解析时整组作废
+    pub fn parse_levels(levels: &[(String, String)]) -> Result<Vec<PriceLevel>, Box<dyn Error>> {
+        levels.iter().map(|(price, qty)| PriceLevel::parse(price, qty)).collect()
+    }
+}
+
+/// 成交
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Trade {
+    pub exchange: String,           // 交易所，例如 "binance"、"okx"
+    pub instrument: String,         // 交易所原始品种名，例如 "BTCUSDT"、"BTC-USDT-SWAP"
+    pub trade_id: String,           // 成交 id（部分交易所为 UUID）
+    pub price: f64,
+    pub quantity: f64,              // 以币计的数量（币本位合约已按面值折算）
+    pub taker_side: Side,           // 主动成交方向
+    pub trade_time: u64,            // 成交时间（毫秒）
+    pub received_timestamp: u128,   // 本地接收时间（微秒）
+}
+
+impl Trade {
+    /// 名义价值
+    pub fn notional(&self) -> f64 {
+        self.price * self.quantity
+    }
+
+    /// 价格或数量无法解析时返回错误，不能按 0 分发
+    pub fn from_agg_trade(exchange: &str, event: &AggTradeEvent) -> Result<Self, Box<dyn Error>> {
+        let level = PriceLevel::parse(&event.price, &event.quantity)?;
+        Ok(Self {
+            exchange: exchange.to_string(),
+            instrument: event.symbol.clone(),
+            trade_id: event.agg_trade_id.to_string(),
+            price: level.price,
+            quantity: level.base_quantity(event.contract_size),
+            taker_side: Side::from_buyer_maker(event.is_buyer_maker),
+            trade_time: event.trade_time,
+            received_timestamp: event.received_timestamp,
+        })
+    }
+
+    /// 成交 id 优先取 raw_trade_id（非数值 id，例如 Bybit 合约的 UUID），价格或数量无法解析时返回错误
+    pub fn from_trade(exchange: &str, event: &TradeEvent) -> Result<Self, Box<dyn Error>> {
+        let level = PriceLevel::parse(&event.price, &event.quantity)?;
+        let trade_id = if event.raw_trade_id.is_empty() {
+            event.trade_id.to_string()
+        } else {
+            event.raw_trade_id.clone()
+        };
+        Ok(Self {
+            exchange: exchange.to_string(),
+            instrument: event.symbol.clone(),
+            trade_id,
+            price: level.price,
+            quantity: level.base_quantity(event.contract_size),
+            taker_side: Side::from_buyer_maker(event.is_buyer_maker),
+            trade_time: event.trade_time,
+            received_timestamp: event.received_timestamp,
+        })
+    }
+}
+
+/// 订单簿增量
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BookDelta {
+    pub exchange: String,
+    pub instrument: String,
+    pub first_update_id: u64,       // 本次增量的第一个更新 id
+    pub last_update_id: u64,        // 本次增量的最后一个更新 id
+    pub previous_update_id: u64,    // 上一条增量的最后一个更新 id（交易所不提供时为 0）
+    pub bids: Vec<PriceLevel>,      // 数量为交易所原始单位（合约为张）
+    pub asks: Vec<PriceLevel>,
+    pub event_time: u64,
+    pub received_timestamp: u128,
+    #[serde(default)]
+    pub contract_size: Option<ContractSize>, // 档位数量单位为张时每张的大小，数量为币时为 None
+}
+
+impl BookDelta {
+    /// 数量保持交易所原始单位（合约为张），与 REST 快照一致。
+    /// 任一档位无法解析时返回错误，整条增量应丢弃（之后的增量会因序号不连续触发重新同步）
+    pub fn from_depth(exchange: &str, event: &DepthEvent) -> Result<Self, Box<dyn Error>> {
+        Ok(Self {
+            exchange: exchange.to_string(),
+            instrument: event.symbol.clone(),
+            first_update_id: event.first_update_id,
+            last_update_id: event.last_update_id,
+            previous_update_id: event.previous_update_id,
+            bids: PriceLevel::parse_levels(&event.bids)?,
+            asks: PriceLevel::parse_levels(&event.asks)?,
+            event_time: event.event_time,
+            received_timestamp: event.received_timestamp,
+            contract_size: event.contract_size,
+        })
+    }
+}
+
+/// 订单簿快照（REST 获取或由行情流推送）
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BookSnapshot {
+    pub exchange: String,
+    pub instrument: String,
+    pub last_update_id: u64,
+    pub bids: Vec<PriceLevel>,      // 数量为交易所原始单位（合约为张）
+    pub asks: Vec<PriceLevel>,
+    pub event_time: u64,
+    pub received_timestamp: u128,
+    #[serde(default)]
+    pub contract_size: Option<ContractSize>, // 档位数量单位为张时每张的大小，数量为币时为 None
+}
+
+impl BookSnapshot {
+    /// 行情流推送的快照（OKX books、Bybit orderbook 的 "snapshot"），任一档位无法解析时返回错误
+    pub fn from_depth(exchange: &str, event: &DepthEvent) -> Result<Self, Box<dyn Error>> {
+        Ok(Self {
+            exchange: exchange.to_string(),
+            instrument: event.symbol.clone(),
+            last_update_id: event.last_update_id,
+            bids: PriceLevel::parse_levels(&event.bids)?,
+            asks: PriceLevel::parse_levels(&event.asks)?,
+            event_time: event.event_time,
+            received_timestamp: event.received_timestamp,
+            contract_size: event.contract_size,
+        })
+    }
+}
+
+/// 最优买卖价
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Bbo {
+    pub exchange: String,
+    pub instrument: String,
+    pub bid_price: f64,
+    pub bid_qty: f64,               // 数量为交易所原始单位（合约为张）
+    pub ask_price: f64,
+    pub ask_qty: f64,
+    pub event_time: u64,            // 交易所时间（币安现货不提供，为 0）
+    pub received_timestamp: u128,
+    #[serde(default)]
+    pub contract_size: Option<ContractSize>, // 数量单位为张时每张的大小，数量为币时为 None
+}
+
+impl Bbo {
+    pub fn mid_price(&self) -> f64 {
+        (self.bid_price + self.ask_price) / 2.0
+    }
+
+    pub fn spread(&self) -> f64 {
+        self.ask_price - self.bid_price
+    }
+
+    /// 买一量折算为币
+    pub fn bid_base_qty(&self) -> f64 {
+        PriceLevel { price: self.bid_price, quantity: self.bid_qty }.base_quantity(self.contract_size)
+    }
+
+    /// 卖一量折算为币
+    pub fn ask_base_qty(&self) -> f64 {
+        PriceLevel { price: self.ask_price, quantity: self.ask_qty }.base_quantity(self.contract_size)
+    }
+
+    /// 任一价格或数量无法解析时返回错误
+    pub fn from_book_ticker(exchange: &str, event: &BookTickerEvent) -> Result<Self, Box<dyn Error>> {
+        let bid = PriceLevel::parse(&event.bid_price, &event.bid_qty)?;
+        let ask = PriceLevel::parse(&event.ask_price, &event.ask_qty)?;
+        Ok(Self {
+            exchange: exchange.to_string(),
+            instrument: event.symbol.clone(),
+            bid_price: bid.price,
+            bid_qty: bid.quantity,
+            ask_price: ask.price,
+            ask_qty: ask.quantity,
+            event_time: event.event_time,
+            received_timestamp: event.received_timestamp,
+            contract_size: event.contract_size,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn agg_trade(price: &str, quantity: &str) -> AggTradeEvent {
+        AggTradeEvent {
+            symbol: "BTCUSD_PERP".to_string(),
+            agg_trade_id: 42,
+            price: price.to_string(),
+            quantity: quantity.to_string(),
+            trade_time: 1_000,
+            is_buyer_maker: true,
+            ..Default::default()
+        }
+    }
+
+    fn depth(bids: &[(&str, &str)]) -> DepthEvent {
+        DepthEvent {
+            event: "depthUpdate".to_string(),
+            symbol: "BTCUSDT".to_string(),
+            first_update_id: 10,
+            last_update_id: 12,
+            previous_update_id: 9,
+            bids: bids.iter().map(|(p, q)| (p.to_string(), q.to_string())).collect(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn price_level_rejects_bad_numbers() {
+        assert_eq!(PriceLevel::parse("100.5", "0").unwrap(), PriceLevel { price: 100.5, quantity: 0.0 });
+        assert!(PriceLevel::parse("", "1").is_err());
+        assert!(PriceLevel::parse("abc", "1").is_err());
+        assert!(PriceLevel::parse("100", "-1").is_err());
+        assert!(PriceLevel::parse("NaN", "1").is_err());
+        assert!(PriceLevel::parse("100", "inf").is_err());
+    }
+
+    #[test]
+    fn agg_trade_converts_contracts_to_base_quantity() {
+        let mut event = agg_trade("50000", "10");
+        event.contract_size = Some(ContractSize::Inverse(100.0));
+        let trade = Trade::from_agg_trade("binance_coin_futures", &event).unwrap();
+        assert_eq!(trade.trade_id, "42");
+        assert_eq!(trade.price, 50000.0);
+        // 10 张 × 100 美元 / 50000
+        assert!((trade.quantity - 0.02).abs() < 1e-12);
+        assert_eq!(trade.taker_side, Side::Sell);
+        assert_eq!(trade.trade_time, 1_000);
+    }
+
+    #[test]
+    fn agg_trade_rejects_bad_numbers() {
+        assert!(Trade::from_agg_trade("binance", &agg_trade("", "1")).is_err());
+        assert!(Trade::from_agg_trade("binance", &agg_trade("50000", "x")).is_err());
+    }
+
+    #[test]
+    fn trade_prefers_raw_trade_id() {
+        let mut event = TradeEvent {
+            symbol: "BTCUSDT".to_string(),
+            trade_id: 7,
+            price: "100".to_string(),
+            quantity: "2".to_string(),
+            ..Default::default()
+        };
+        let trade = Trade::from_trade("binance", &event).unwrap();
+        assert_eq!(trade.trade_id, "7");
+        assert_eq!(trade.taker_side, Side::Buy);
+        assert_eq!(trade.notional(), 200.0);
+
+        event.trade_id = 0;
+        event.raw_trade_id = "2b3c-uuid".to_string();
+        assert_eq!(Trade::from_trade("bybit", &event).unwrap().trade_id, "2b3c-uuid");
+
+        event.quantity = "-2".to_string();
+        assert!(Trade::from_trade("bybit", &event).is_err());
+    }
+
+    #[test]
+    fn book_delta_keeps_ids_and_rejects_bad_levels() {
+        let delta = BookDelta::from_depth("binance", &depth(&[("100", "1.5"), ("99", "0")])).unwrap();
+        assert_eq!((delta.first_update_id, delta.last_update_id, delta.previous_update_id), (10, 12, 9));
+        assert_eq!(delta.bids, vec![
+            PriceLevel { price: 100.0, quantity: 1.5 },
+            PriceLevel { price: 99.0, quantity: 0.0 },
+        ]);
+        assert!(delta.asks.is_empty());
+        assert!(BookDelta::from_depth("binance", &depth(&[("100", "1"), ("99", "")])).is_err());
+    }
+
+    #[test]
+    fn book_snapshot_keeps_contract_size_and_rejects_bad_levels() {
+        let mut event = depth(&[("100", "3")]);
+        event.contract_size = Some(ContractSize::Linear(0.01));
+        let snapshot = BookSnapshot::from_depth("okx", &event).unwrap();
+        assert_eq!(snapshot.last_update_id, 12);
+        // 档位保持张数，由 base_quantity 折算
+        assert_eq!(snapshot.bids[0].quantity, 3.0);
+        assert!((snapshot.bids[0].base_quantity(snapshot.contract_size) - 0.03).abs() < 1e-12);
+        assert!(BookSnapshot::from_depth("okx", &depth(&[("abc", "1")])).is_err());
+    }
+
+    #[test]
+    fn bbo_from_book_ticker() {
+        let mut event = BookTickerEvent {
+            symbol: "BTCUSDT".to_string(),
+            bid_price: "99".to_string(),
+            bid_qty: "2".to_string(),
+            ask_price: "101".to_string(),
+            ask_qty: "3".to_string(),
+            ..Default::default()
+        };
+        let bbo = Bbo::from_book_ticker("binance", &event).unwrap();
+        assert_eq!(bbo.mid_price(), 100.0);
+        assert_eq!(bbo.spread(), 2.0);
+        assert_eq!((bbo.bid_base_qty(), bbo.ask_base_qty()), (2.0, 3.0));
+
+        event.ask_qty = String::new();
+        assert!(Bbo::from_book_ticker("binance", &event).is_err());
+    }
+}
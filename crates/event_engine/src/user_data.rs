@@ -0,0 +1,263 @@
+// event_engine/user_data.rs
+
+// 币安合约用户数据流（listenKey）推送的账户与订单事件
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(tag = "e")]  // 根据 JSON 中 "e" 字段来区分不同事件
+pub enum UserDataEvent {
+    #[serde(rename = "ORDER_TRADE_UPDATE")]
+    OrderUpdate(OrderUpdateEvent),
+    #[serde(rename = "ACCOUNT_UPDATE")]
+    AccountUpdate(AccountUpdateEvent),
+    #[serde(rename = "MARGIN_CALL")]
+    MarginCall(MarginCallEvent),
+    // listenKey 过期后连接不再推送数据，需要重新创建 listenKey 并重连
+    #[serde(rename = "listenKeyExpired")]
+    ListenKeyExpired(ListenKeyExpiredEvent),
+    // 其他事件（ACCOUNT_CONFIG_UPDATE、TRADE_LITE 等）暂不处理
+    #[serde(other)]
+    Unknown,
+}
+
+/// 订单 / 成交更新，对应 ORDER_TRADE_UPDATE
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct OrderUpdateEvent {
+    #[serde(alias = "e", alias = "event", default)]
+    pub event: String,               // 事件类型 "ORDER_TRADE_UPDATE"
+
+    #[serde(alias = "E", alias = "eventTime", default)]
+    pub event_time: u64,             // 事件时间
+
+    #[serde(alias = "T", alias = "transactionTime", default)]
+    pub transaction_time: u64,       // 撮合时间
+
+    #[serde(alias = "o")]
+    pub order: OrderUpdate,          // 订单详情
+
+    #[serde(skip)]
+    pub received_timestamp: u128,    // 记录 WebSocket 接收到的时间戳
+
+    #[serde(flatten)]
+    pub extra: HashMap<String, Value>, // 捕获额外的未知字段
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct OrderUpdate {
+    #[serde(alias = "s", default)]
+    pub symbol: String,              // 交易对
+
+    #[serde(alias = "c", default)]
+    pub client_order_id: String,     // 客户端自定义订单 id
+
+    #[serde(alias = "S", default)]
+    pub side: String,                // 订单方向 "BUY" / "SELL"
+
+    #[serde(alias = "o", default)]
+    pub order_type: String,          // 订单类型
+
+    #[serde(alias = "f", default)]
+    pub time_in_force: String,       // 有效方式
+
+    #[serde(alias = "q", default)]
+    pub quantity: String,            // 订单数量
+
+    #[serde(alias = "p", default)]
+    pub price: String,               // 订单价格
+
+    #[serde(alias = "ap", default)]
+    pub avg_price: String,           // 平均成交价
+
+    #[serde(alias = "sp", default)]
+    pub stop_price: String,          // 触发价
+
+    #[serde(alias = "x", default)]
+    pub execution_type: String,      // 本次事件的执行类型：NEW / TRADE / CANCELED / EXPIRED 等
+
+    #[serde(alias = "X", default)]
+    pub status: String,              // 订单当前状态
+
+    #[serde(alias = "i", default)]
+    pub order_id: u64,               // 订单 id
+
+    #[serde(alias = "l", default)]
+    pub last_filled_qty: String,     // 本次成交量
+
+    #[serde(alias = "z", default)]
+    pub filled_qty: String,          // 累计成交量
+
+    #[serde(alias = "L", default)]
+    pub last_filled_price: String,   // 本次成交价
+
+    #[serde(alias = "N", default)]
+    pub commission_asset: Option<String>, // 手续费资产，无成交时为空
+
+    #[serde(alias = "n", default)]
+    pub commission: Option<String>,  // 本次成交手续费
+
+    #[serde(alias = "T", default)]
+    pub trade_time: u64,             // 成交时间
+
+    #[serde(alias = "t", default)]
+    pub trade_id: u64,               // 成交 id
+
+    #[serde(alias = "m", default)]
+    pub is_maker: bool,              // 本次成交是否为挂单方
+
+    #[serde(alias = "R", default)]
+    pub reduce_only: bool,           // 是否只减仓
+
+    #[serde(alias = "ps", default)]
+    pub position_side: String,       // 持仓方向 BOTH / LONG / SHORT
+
+    #[serde(alias = "rp", default)]
+    pub realized_profit: String,     // 本次成交实现盈亏
+}
+
+impl OrderUpdate {
+    /// 本次事件是否包含成交
+    pub fn is_fill(&self) -> bool {
+        self.execution_type == "TRADE"
+    }
+}
+
+/// 余额与持仓更新，对应 ACCOUNT_UPDATE
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct AccountUpdateEvent {
+    #[serde(alias = "e", alias = "event", default)]
+    pub event: String,               // 事件类型 "ACCOUNT_UPDATE"
+
+    #[serde(alias = "E", alias = "eventTime", default)]
+    pub event_time: u64,             // 事件时间
+
+    #[serde(alias = "T", alias = "transactionTime", default)]
+    pub transaction_time: u64,       // 撮合时间
+
+    #[serde(alias = "a")]
+    pub account: AccountUpdate,      // 账户变动
+
+    #[serde(skip)]
+    pub received_timestamp: u128,    // 记录 WebSocket 接收到的时间戳
+
+    #[serde(flatten)]
+    pub extra: HashMap<String, Value>, // 捕获额外的未知字段
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct AccountUpdate {
+    #[serde(alias = "m", default)]
+    pub reason: String,              // 变动原因：ORDER / FUNDING_FEE / DEPOSIT 等
+
+    #[serde(alias = "B", default)]
+    pub balances: Vec<BalanceUpdate>, // 余额变动
+
+    #[serde(alias = "P", default)]
+    pub positions: Vec<PositionUpdate>, // 持仓变动
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct BalanceUpdate {
+    #[serde(alias = "a", default)]
+    pub asset: String,               // 资产
+
+    #[serde(alias = "wb", default)]
+    pub wallet_balance: String,      // 钱包余额
+
+    #[serde(alias = "cw", default)]
+    pub cross_wallet_balance: String, // 除逐仓保证金外的钱包余额
+
+    #[serde(alias = "bc", default)]
+    pub balance_change: String,      // 除盈亏与手续费外的余额变化
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct PositionUpdate {
+    #[serde(alias = "s", default)]
+    pub symbol: String,              // 交易对
+
+    #[serde(alias = "pa", default)]
+    pub position_amount: String,     // 持仓数量，空头为负
+
+    #[serde(alias = "ep", default)]
+    pub entry_price: String,         // 开仓均价
+
+    #[serde(alias = "bep", default)]
+    pub breakeven_price: String,     // 盈亏平衡价
+
+    #[serde(alias = "cr", default)]
+    pub accumulated_realized: String, // 累计实现盈亏
+
+    #[serde(alias = "up", default)]
+    pub unrealized_pnl: String,      // 未实现盈亏
+
+    #[serde(alias = "mt", default)]
+    pub margin_type: String,         // 保证金模式 isolated / cross
+
+    #[serde(alias = "iw", default)]
+    pub isolated_wallet: String,     // 逐仓保证金
+
+    #[serde(alias = "ps", default)]
+    pub position_side: String,       // 持仓方向 BOTH / LONG / SHORT
+}
+
+/// 追加保证金通知，对应 MARGIN_CALL
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct MarginCallEvent {
+    #[serde(alias = "e", alias = "event", default)]
+    pub event: String,               // 事件类型 "MARGIN_CALL"
+
+    #[serde(alias = "E", alias = "eventTime", default)]
+    pub event_time: u64,             // 事件时间
+
+    #[serde(alias = "cw", default)]
+    pub cross_wallet_balance: String, // 全仓钱包余额（仅全仓时推送）
+
+    #[serde(alias = "p", default)]
+    pub positions: Vec<MarginCallPosition>, // 涉及的持仓
+
+    #[serde(skip)]
+    pub received_timestamp: u128,    // 记录 WebSocket 接收到的时间戳
+
+    #[serde(flatten)]
+    pub extra: HashMap<String, Value>, // 捕获额外的未知字段
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct MarginCallPosition {
+    #[serde(alias = "s", default)]
+    pub symbol: String,              // 交易对
+
+    #[serde(alias = "ps", default)]
+    pub position_side: String,       // 持仓方向
+
+    #[serde(alias = "pa", default)]
+    pub position_amount: String,     // 持仓数量
+
+    #[serde(alias = "mt", default)]
+    pub margin_type: String,         // 保证金模式
+
+    #[serde(alias = "iw", default)]
+    pub isolated_wallet: String,     // 逐仓保证金
+
+    #[serde(alias = "mp", default)]
+    pub mark_price: String,          // 标记价格
+
+    #[serde(alias = "up", default)]
+    pub unrealized_pnl: String,      // 未实现盈亏
+
+    #[serde(alias = "mm", default)]
+    pub maintenance_margin: String,  // 所需维持保证金
+}
+
+/// listenKey 过期通知
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct ListenKeyExpiredEvent {
+    #[serde(alias = "E", alias = "eventTime", default)]
+    pub event_time: u64,             // 事件时间
+
+    #[serde(alias = "listenKey", default)]
+    pub listen_key: String,          // 已过期的 listenKey
+}
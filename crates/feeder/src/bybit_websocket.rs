@@ -0,0 +1,452 @@
+// feeder/bybit_websocket.rs
+
+use async_trait::async_trait;
+use futures_util::{SinkExt, StreamExt};
+use serde_json::{json, Value};
+use std::collections::{HashSet, VecDeque};
+use std::error::Error;
+use std::io;
+use std::time::{Duration, Instant};
+use tokio::net::TcpStream;
+use tokio::sync::mpsc;
+use tokio::time::timeout;
+use tokio_tungstenite::{connect_async, tungstenite::Message, MaybeTlsStream, WebSocketStream};
+use url::Url;
+
+use crate::endpoint::WsEndpoint;
+use crate::lifecycle::{LifecycleEmitter, LifecycleEvent};
+use crate::reconnect::{ReconnectPolicy, ReconnectState};
+use crate::command::{self, WsCommand};
+use crate::websocket::WebSocket;
+
+type WsStream = WebSocketStream<MaybeTlsStream<TcpStream>>;
+
+type MessageCallback = Box<dyn FnMut(String) + Send + 'static>;
+
+/// 心跳间隔，Bybit 建议每 20 秒发送一次 {"op": "ping"}
+const PING_INTERVAL: Duration = Duration::from_secs(20);
+
+/// 等待交易所应答的默认超时
+const DEFAULT_ACK_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// 单次订阅请求最多携带的 topic 数（现货限制为 10，合约不限，统一按 10 分批）
+const MAX_ARGS_PER_REQUEST: usize = 10;
+
+/// 读取结果，在 select 中先取出再处理，避免借用跨越分支
+enum Incoming {
+    Message(Option<Result<Message, tokio_tungstenite::tungstenite::Error>>),
+    Heartbeat,
+    Resync(String),
+    Command(WsCommand),
+}
+
+/// Bybit v5 公共频道客户端，流名称直接使用 topic，例如 "publicTrade.BTCUSDT"、
+/// "orderbook.50.BTCUSDT"、"tickers.BTCUSDT"。
+/// 订阅与心跳都通过 op 消息完成：{"op": "subscribe", "args": [...], "req_id": "..."}、{"op": "ping"}
+pub struct BybitWebSocketClient {
+    ws_stream: Option<WsStream>,
+    endpoint: WsEndpoint,
+    /// 已确认的订阅 topic
+    subscribed_streams: HashSet<String>,
+    on_message_callback: Option<MessageCallback>,
+    /// 等待应答期间收到的行情消息，监听循环启动后优先回调
+    buffered_messages: VecDeque<String>,
+    ack_timeout: Duration,
+    /// 下一个请求 id
+    next_request_id: u64,
+    /// 已发送 ping、尚未收到应答
+    awaiting_pong: bool,
+
+    /// 断线重连策略与连续失败次数
+    reconnect: ReconnectState,
+    /// 连接编号与生命周期回调
+    lifecycle: LifecycleEmitter,
+
+    /// 重新订阅请求（例如订单簿增量不连续时需要新的快照），监听循环中处理
+    resync_tx: mpsc::UnboundedSender<String>,
+    resync_rx: mpsc::UnboundedReceiver<String>,
+
+    /// 订阅、取消订阅与查询命令，监听循环在两次读取之间执行
+    command_tx: mpsc::UnboundedSender<WsCommand>,
+    command_rx: mpsc::UnboundedReceiver<WsCommand>,
+}
+
+impl BybitWebSocketClient {
+    /// 使用 Bybit 合约（linear）公共频道
+    pub fn new() -> Self {
+        Self::with_endpoint(WsEndpoint::bybit_linear())
+    }
+
+    pub fn with_endpoint(endpoint: WsEndpoint) -> Self {
+        let (resync_tx, resync_rx) = mpsc::unbounded_channel();
+        let (command_tx, command_rx) = mpsc::unbounded_channel();
+        Self {
+            ws_stream: None,
+            endpoint,
+            subscribed_streams: HashSet::new(),
+            on_message_callback: None,
+            buffered_messages: VecDeque::new(),
+            ack_timeout: DEFAULT_ACK_TIMEOUT,
+            next_request_id: 1,
+            awaiting_pong: false,
+            reconnect: ReconnectState::default(),
+            lifecycle: LifecycleEmitter::default(),
+            resync_tx,
+            resync_rx,
+            command_tx,
+            command_rx,
+        }
+    }
+
+    /// 设置消息回调
+    pub fn set_message_callback<F>(&mut self, callback: F)
+    where
+        F: FnMut(String) + Send + 'static,
+    {
+        self.on_message_callback = Some(Box::new(callback));
+    }
+
+    /// 设置生命周期回调
+    pub fn set_lifecycle_callback<F>(&mut self, callback: F)
+    where
+        F: FnMut(u64, LifecycleEvent) + Send + 'static,
+    {
+        self.lifecycle.set_callback(callback);
+    }
+
+    /// 设置连接编号
+    pub fn set_connection_id(&mut self, id: u64) {
+        self.lifecycle.set_connection_id(id);
+    }
+
+    /// 设置断线重连策略
+    pub fn set_reconnect_policy(&mut self, policy: ReconnectPolicy) {
+        self.reconnect.policy = policy;
+    }
+
+    /// 设置等待应答的超时时间
+    pub fn set_ack_timeout(&mut self, ack_timeout: Duration) {
+        self.ack_timeout = ack_timeout;
+    }
+
+    /// 重新订阅句柄：发送 topic 即可让监听循环对其取消订阅再订阅，交易所随后推送新的快照
+    pub fn resync_handle(&self) -> mpsc::UnboundedSender<String> {
+        self.resync_tx.clone()
+    }
+
+    /// 命令句柄：监听循环运行期间通过它订阅、取消订阅和查询，不会打断监听循环；
+    /// 连接断开时命令在重连成功后执行
+    pub fn command_handle(&self) -> mpsc::UnboundedSender<WsCommand> {
+        self.command_tx.clone()
+    }
+
+    /// 当前端点配置
+    pub fn endpoint(&self) -> &WsEndpoint {
+        &self.endpoint
+    }
+
+    /// 当前已订阅的 topic
+    pub fn subscribed_streams(&self) -> &HashSet<String> {
+        &self.subscribed_streams
+    }
+
+    /// 是否已建立连接
+    pub fn is_connected(&self) -> bool {
+        self.ws_stream.is_some()
+    }
+
+    fn url(&self) -> String {
+        format!("{}://{}", self.endpoint.scheme(), self.endpoint.base_url)
+    }
+
+    /// 丢弃当前连接；若之前处于连接状态则上报 Disconnected
+    fn mark_disconnected(&mut self, reason: &str) {
+        self.lifecycle.disconnect(&mut self.ws_stream, reason, &self.subscribed_streams);
+    }
+
+    fn sorted_streams(&self) -> Vec<String> {
+        let mut streams: Vec<String> = self.subscribed_streams.iter().cloned().collect();
+        streams.sort();
+        streams
+    }
+
+    /// 建立一条新的底层连接，不借用 self
+    async fn open_stream(url_str: String) -> Result<WsStream, Box<dyn Error>> {
+        println!("尝试连接: {}", url_str);
+        let url = Url::parse(&url_str)?;
+        let (ws_stream, _) = connect_async(url).await?;
+        Ok(ws_stream)
+    }
+
+    /// 发送操作请求，返回请求 id
+    async fn send_op(&mut self, op: &str, args: &[String]) -> Result<String, Box<dyn Error>> {
+        let req_id = self.next_request_id.to_string();
+        self.next_request_id += 1;
+        let msg = if args.is_empty() {
+            json!({ "op": op, "req_id": req_id })
+        } else {
+            json!({ "op": op, "args": args, "req_id": req_id })
+        }
+        .to_string();
+        self.send(&msg).await?;
+        if op != "ping" {
+            println!("已发送请求: {}", msg);
+        }
+        Ok(req_id)
+    }
+
+    /// 等待指定请求的应答：{"success": true, "ret_msg": "", "op": "subscribe", "req_id": "1", ...}；
+    /// 期间收到的行情消息先缓存
+    async fn await_response(&mut self, req_id: &str) -> Result<(), Box<dyn Error>> {
+        let deadline = Instant::now() + self.ack_timeout;
+        loop {
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            let ws = match self.ws_stream.as_mut() {
+                Some(ws) => ws,
+                None => return Err(Box::new(io::Error::new(io::ErrorKind::Other, "WebSocket 未连接"))),
+            };
+            let text = match timeout(remaining, ws.next()).await {
+                Err(_) => return Err(format!("等待请求 {} 应答超时", req_id).into()),
+                Ok(None) => return Err("WebSocket 已关闭".into()),
+                Ok(Some(Err(e))) => return Err(Box::new(e)),
+                Ok(Some(Ok(Message::Text(text)))) => text,
+                Ok(Some(Ok(Message::Ping(data)))) => {
+                    ws.send(Message::Pong(data)).await?;
+                    continue;
+                }
+                Ok(Some(Ok(_))) => continue,
+            };
+            let value: Value = match serde_json::from_str(&text) {
+                Ok(value) => value,
+                Err(_) => continue,
+            };
+            if value.get("topic").is_some() {
+                self.buffered_messages.push_back(text);
+                continue;
+            }
+            if is_pong(&value) {
+                self.awaiting_pong = false;
+                continue;
+            }
+            if value.get("req_id").and_then(|v| v.as_str()) != Some(req_id) {
+                continue;
+            }
+            if value.get("success").and_then(|v| v.as_bool()).unwrap_or(false) {
+                return Ok(());
+            }
+            let ret_msg = value.get("ret_msg").and_then(|v| v.as_str()).unwrap_or("");
+            return Err(format!("请求 {} 失败: {}", req_id, ret_msg).into());
+        }
+    }
+
+    /// 分批发送订阅类请求并逐批等待应答
+    async fn request(&mut self, op: &str, streams: &[String]) -> Result<(), Box<dyn Error>> {
+        for chunk in streams.chunks(MAX_ARGS_PER_REQUEST) {
+            let req_id = self.send_op(op, chunk).await?;
+            self.await_response(&req_id).await?;
+        }
+        Ok(())
+    }
+
+    /// 重新建立连接并恢复全部订阅
+    async fn reconnect(&mut self) -> Result<(), Box<dyn Error>> {
+        let ws_stream = Self::open_stream(self.url()).await?;
+        self.ws_stream = Some(ws_stream);
+        self.awaiting_pong = false;
+        let streams = self.sorted_streams();
+        if !streams.is_empty() {
+            self.request("subscribe", &streams).await?;
+        }
+        Ok(())
+    }
+
+    /// 处理一条文本消息：心跳与操作应答不进入回调
+    fn handle_text(&mut self, text: String) {
+        if let Ok(value) = serde_json::from_str::<Value>(&text) {
+            if value.get("topic").is_none() {
+                if is_pong(&value) {
+                    self.awaiting_pong = false;
+                } else if value.get("success").and_then(|v| v.as_bool()) == Some(false) {
+                    eprintln!("[Bybit] 错误应答: {}", text);
+                }
+                return;
+            }
+        }
+        if let Some(ref mut callback) = self.on_message_callback {
+            callback(text);
+        }
+    }
+}
+
+/// 心跳应答：现货为 {"success": true, "ret_msg": "pong", "op": "ping"}，合约为 {"op": "pong", ...}
+fn is_pong(value: &Value) -> bool {
+    match value.get("op").and_then(|v| v.as_str()) {
+        Some("pong") => true,
+        Some("ping") => value.get("ret_msg").and_then(|v| v.as_str()) == Some("pong"),
+        _ => false,
+    }
+}
+
+impl Default for BybitWebSocketClient {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[async_trait]
+impl WebSocket for BybitWebSocketClient {
+    /// 连接后订阅传入的 topic（Bybit 不支持在 URL 中携带订阅）
+    async fn connect(&mut self, streams: Vec<&str>) -> Result<(), Box<dyn Error>> {
+        let ws_stream = Self::open_stream(self.url()).await?;
+        self.ws_stream = Some(ws_stream);
+        self.awaiting_pong = false;
+        println!("连接成功");
+        if !streams.is_empty() {
+            self.subscribe(streams.clone()).await?;
+        }
+        let streams = streams.iter().map(|s| s.to_string()).collect();
+        self.lifecycle.emit(LifecycleEvent::Connected { streams });
+        Ok(())
+    }
+
+    async fn send(&mut self, msg: &str) -> Result<(), Box<dyn Error>> {
+        if let Some(ref mut ws) = self.ws_stream {
+            ws.send(Message::Text(msg.to_string())).await?;
+            Ok(())
+        } else {
+            Err(Box::new(io::Error::new(io::ErrorKind::Other, "WebSocket 未连接")))
+        }
+    }
+
+    async fn read_message(&mut self) -> Result<Message, Box<dyn Error>> {
+        if let Some(ref mut ws) = self.ws_stream {
+            match ws.next().await {
+                Some(msg) => Ok(msg?),
+                None => Err(Box::new(io::Error::new(io::ErrorKind::Other, "WebSocket 已关闭"))),
+            }
+        } else {
+            Err(Box::new(io::Error::new(io::ErrorKind::Other, "WebSocket 未连接")))
+        }
+    }
+
+    async fn listen_loop(&mut self) -> Result<(), Box<dyn Error>> {
+        let mut last_ping = Instant::now();
+        loop {
+            if self.ws_stream.is_none() {
+                if self.subscribed_streams.is_empty() {
+                    return Err("无任何已记录的订阅流，无法重连".into());
+                }
+                let attempt = self.reconnect.next_attempt()?;
+                self.reconnect.backoff("[Bybit] ", attempt).await;
+                let failed = match self.reconnect().await {
+                    Ok(()) => None,
+                    Err(e) => Some(e.to_string()),
+                };
+                if let Some(e) = failed {
+                    eprintln!("[Bybit] 重连失败: {}", e);
+                    self.ws_stream = None;
+                    self.reconnect.failed(attempt);
+                    continue;
+                }
+                self.reconnect.succeeded();
+                last_ping = Instant::now();
+                println!("[Bybit] 重连并重新订阅成功");
+                let streams = self.sorted_streams();
+                self.lifecycle.emit(LifecycleEvent::Resubscribed { streams });
+            }
+
+            while let Some(text) = self.buffered_messages.pop_front() {
+                if let Some(ref mut callback) = self.on_message_callback {
+                    callback(text);
+                }
+            }
+
+            // 与 OKX 不同，Bybit 即使有数据推送也要求定时发送心跳
+            let until_ping = PING_INTERVAL.saturating_sub(last_ping.elapsed());
+            let incoming = match self.ws_stream.as_mut() {
+                Some(ws) => tokio::select! {
+                    read = timeout(until_ping, ws.next()) => match read {
+                        Ok(msg) => Incoming::Message(msg),
+                        Err(_) => Incoming::Heartbeat,
+                    },
+                    Some(stream) = self.resync_rx.recv() => Incoming::Resync(stream),
+                    Some(command) = self.command_rx.recv() => Incoming::Command(command),
+                },
+                None => continue,
+            };
+
+            match incoming {
+                Incoming::Command(command) => command::execute(self, command).await,
+                Incoming::Heartbeat => {
+                    if self.awaiting_pong {
+                        self.mark_disconnected("ping 无响应");
+                        continue;
+                    }
+                    last_ping = Instant::now();
+                    self.awaiting_pong = true;
+                    let sent = self.send_op("ping", &[]).await.map(|_| ()).map_err(|e| e.to_string());
+                    if let Err(e) = sent {
+                        self.mark_disconnected(&format!("发送 ping 失败: {}", e));
+                    }
+                }
+                Incoming::Resync(stream) => {
+                    if !self.subscribed_streams.contains(&stream) {
+                        continue;
+                    }
+                    // 应答由 handle_text 忽略；重新订阅后交易所会推送新的快照
+                    println!("[Bybit] 重新订阅 {}", stream);
+                    let streams = vec![stream];
+                    let unsubscribed = self.send_op("unsubscribe", &streams).await.map(|_| ()).map_err(|e| e.to_string());
+                    let sent = match unsubscribed {
+                        Ok(()) => self.send_op("subscribe", &streams).await.map(|_| ()).map_err(|e| e.to_string()),
+                        Err(e) => Err(e),
+                    };
+                    if let Err(e) = sent {
+                        self.mark_disconnected(&format!("重新订阅失败: {}", e));
+                    }
+                }
+                Incoming::Message(None) => self.mark_disconnected("WebSocket 已关闭"),
+                Incoming::Message(Some(Err(e))) => {
+                    eprintln!("[Bybit] 读取消息错误: {}，准备重连", e);
+                    self.mark_disconnected(&format!("读取消息错误: {}", e));
+                }
+                Incoming::Message(Some(Ok(message))) => match message {
+                    Message::Text(text) => self.handle_text(text),
+                    Message::Ping(data) => {
+                        if let Some(ref mut ws) = self.ws_stream {
+                            let _ = ws.send(Message::Pong(data)).await;
+                        }
+                    }
+                    Message::Close(frame) => {
+                        println!("[Bybit] 收到关闭消息: {:?}", frame);
+                        self.mark_disconnected(&format!("收到关闭帧: {:?}", frame));
+                    }
+                    _ => {}
+                },
+            }
+        }
+    }
+
+    async fn subscribe(&mut self, streams: Vec<&str>) -> Result<(), Box<dyn Error>> {
+        let streams: Vec<String> = streams.iter().map(|s| s.to_string()).collect();
+        self.request("subscribe", &streams).await?;
+        self.subscribed_streams.extend(streams.iter().cloned());
+        println!("订阅已确认: {:?}", streams);
+        Ok(())
+    }
+
+    async fn unsubscribe(&mut self, streams: Vec<&str>) -> Result<(), Box<dyn Error>> {
+        let streams: Vec<String> = streams.iter().map(|s| s.to_string()).collect();
+        self.request("unsubscribe", &streams).await?;
+        for s in &streams {
+            self.subscribed_streams.remove(s);
+        }
+        println!("取消订阅已确认: {:?}", streams);
+        Ok(())
+    }
+
+    /// Bybit 没有查询订阅列表的操作，返回本地记录的已确认订阅
+    async fn list_subscriptions(&mut self) -> Result<Vec<String>, Box<dyn Error>> {
+        Ok(self.sorted_streams())
+    }
+}
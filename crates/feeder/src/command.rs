@@ -0,0 +1,50 @@
+// feeder/command.rs
+
+use tokio::sync::oneshot;
+
+use crate::websocket::WebSocket;
+
+/// 发给运行中连接的命令。监听循环在两次读取之间处理命令，进行中的重连、应答等待不会被打断；
+/// 执行结果通过 oneshot 返回（Box<dyn Error> 不是 Send，错误以字符串返回）
+pub enum WsCommand {
+    Subscribe(Vec<String>, oneshot::Sender<Result<(), String>>),
+    Unsubscribe(Vec<String>, oneshot::Sender<Result<(), String>>),
+    ListSubscriptions(oneshot::Sender<Result<Vec<String>, String>>),
+}
+
+impl WsCommand {
+    /// 不执行命令，直接以错误应答（例如调用方校验未通过）
+    pub fn reject(self, reason: String) {
+        match self {
+            WsCommand::Subscribe(_, reply) | WsCommand::Unsubscribe(_, reply) => {
+                let _ = reply.send(Err(reason));
+            }
+            WsCommand::ListSubscriptions(reply) => {
+                let _ = reply.send(Err(reason));
+            }
+        }
+    }
+}
+
+/// 在连接上执行一条命令并把结果发回调用方
+pub async fn execute<W>(ws: &mut W, command: WsCommand)
+where
+    W: WebSocket + Send + ?Sized,
+{
+    match command {
+        WsCommand::Subscribe(streams, reply) => {
+            let streams: Vec<&str> = streams.iter().map(|s| s.as_str()).collect();
+            let result = ws.subscribe(streams).await.map_err(|e| e.to_string());
+            let _ = reply.send(result);
+        }
+        WsCommand::Unsubscribe(streams, reply) => {
+            let streams: Vec<&str> = streams.iter().map(|s| s.as_str()).collect();
+            let result = ws.unsubscribe(streams).await.map_err(|e| e.to_string());
+            let _ = reply.send(result);
+        }
+        WsCommand::ListSubscriptions(reply) => {
+            let result = ws.list_subscriptions().await.map_err(|e| e.to_string());
+            let _ = reply.send(result);
+        }
+    }
+}
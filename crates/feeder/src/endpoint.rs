@@ -0,0 +1,216 @@
+// feeder/endpoint.rs
+
+use serde::Deserialize;
+
+/// URL 路径风格，决定订阅流如何拼接到连接地址上
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PathStyle {
+    /// 币安默认风格：单一流使用 /ws/<stream>，多流使用 /stream?streams=<s1>/<s2>
+    Binance,
+    /// 始终使用原始流风格：/ws/<s1>/<s2>/...（消息不带 stream 包装）
+    Raw,
+    /// 始终使用组合流风格：/stream?streams=<s1>/<s2>（消息带 {"stream","data"} 包装）
+    Combined,
+}
+
+/// WebSocket 端点配置：基础地址、路径风格、是否启用 TLS
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct WsEndpoint {
+    /// 主机地址（可带端口与前缀路径），不含协议头，例如 "fstream.binance.com"、"127.0.0.1:9001"
+    pub base_url: String,
+    /// 路径风格
+    #[serde(default = "default_path_style")]
+    pub path_style: PathStyle,
+    /// 是否启用 TLS（wss://），本地 mock 服务一般关闭
+    #[serde(default = "default_tls")]
+    pub tls: bool,
+}
+
+fn default_path_style() -> PathStyle {
+    PathStyle::Binance
+}
+
+fn default_tls() -> bool {
+    true
+}
+
+impl Default for WsEndpoint {
+    fn default() -> Self {
+        Self::binance_futures()
+    }
+}
+
+impl WsEndpoint {
+    pub fn new(base_url: &str, path_style: PathStyle, tls: bool) -> Self {
+        Self {
+            base_url: base_url.trim_end_matches('/').to_string(),
+            path_style,
+            tls,
+        }
+    }
+
+    /// U 本位合约正式环境
+    pub fn binance_futures() -> Self {
+        Self::new("fstream.binance.com", PathStyle::Binance, true)
+    }
+
+    /// U 本位合约测试网
+    pub fn binance_futures_testnet() -> Self {
+        Self::new("stream.binancefuture.com", PathStyle::Binance, true)
+    }
+
+    /// 币本位合约正式环境
+    pub fn binance_coin_futures() -> Self {
+        Self::new("dstream.binance.com", PathStyle::Binance, true)
+    }
+
+    /// 币本位合约测试网
+    pub fn binance_coin_futures_testnet() -> Self {
+        Self::new("dstream.binancefuture.com", PathStyle::Binance, true)
+    }
+
+    /// 现货正式环境
+    pub fn binance_spot() -> Self {
+        Self::new("stream.binance.com:9443", PathStyle::Binance, true)
+    }
+
+    /// 现货测试网
+    pub fn binance_spot_testnet() -> Self {
+        Self::new("stream.testnet.binance.vision", PathStyle::Binance, true)
+    }
+
+    /// OKX 公共频道（无需登录），连接地址固定，订阅通过 op 消息完成，路径风格不起作用
+    pub fn okx_public() -> Self {
+        Self::new("ws.okx.com:8443/ws/v5/public", PathStyle::Raw, true)
+    }
+
+    /// OKX 模拟盘公共频道
+    pub fn okx_public_demo() -> Self {
+        Self::new("wspap.okx.com:8443/ws/v5/public", PathStyle::Raw, true)
+    }
+
+    /// Bybit v5 公共频道：USDT / USDC 永续与交割合约
+    pub fn bybit_linear() -> Self {
+        Self::new("stream.bybit.com/v5/public/linear", PathStyle::Raw, true)
+    }
+
+    /// Bybit v5 公共频道：现货
+    pub fn bybit_spot() -> Self {
+        Self::new("stream.bybit.com/v5/public/spot", PathStyle::Raw, true)
+    }
+
+    /// Bybit v5 公共频道：反向合约
+    pub fn bybit_inverse() -> Self {
+        Self::new("stream.bybit.com/v5/public/inverse", PathStyle::Raw, true)
+    }
+
+    /// Bybit 测试网：USDT / USDC 合约
+    pub fn bybit_linear_testnet() -> Self {
+        Self::new("stream-testnet.bybit.com/v5/public/linear", PathStyle::Raw, true)
+    }
+
+    /// 本地回放 / mock 服务，例如 "127.0.0.1:9001"，不启用 TLS
+    pub fn local(addr: &str) -> Self {
+        Self::new(addr, PathStyle::Binance, false)
+    }
+
+    /// 协议头
+    pub fn scheme(&self) -> &'static str {
+        if self.tls { "wss" } else { "ws" }
+    }
+
+    /// 给定数量的流连接后，消息是否为组合流格式（带 {"stream","data"} 包装）
+    pub fn is_combined(&self, stream_count: usize) -> bool {
+        match self.path_style {
+            PathStyle::Binance => stream_count > 1,
+            PathStyle::Raw => false,
+            PathStyle::Combined => true,
+        }
+    }
+
+    /// 根据订阅流列表构造连接 URL
+    /// 单一流：<scheme>://<base>/ws/<streamName>
+    /// 多流：<scheme>://<base>/stream?streams=<stream1>/<stream2>/...
+    pub fn build_url(&self, streams: &[&str]) -> String {
+        let base = format!("{}://{}", self.scheme(), self.base_url);
+        // 用户数据流的 listenKey 区分大小写且不含 '@'，保持原样
+        let lowered: Vec<String> = streams
+            .iter()
+            .map(|s| if s.contains('@') { s.to_lowercase() } else { s.to_string() })
+            .collect();
+        if self.is_combined(lowered.len()) {
+            format!("{}/stream?streams={}", base, lowered.join("/"))
+        } else {
+            format!("{}/ws/{}", base, lowered.join("/"))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn binance_style_switches_on_stream_count() {
+        let endpoint = WsEndpoint::binance_futures();
+        assert!(!endpoint.is_combined(1));
+        assert!(endpoint.is_combined(2));
+        assert_eq!(endpoint.build_url(&["btcusdt@depth@100ms"]), "wss://fstream.binance.com/ws/btcusdt@depth@100ms");
+        assert_eq!(
+            endpoint.build_url(&["btcusdt@aggTrade", "ethusdt@aggTrade"]),
+            "wss://fstream.binance.com/stream?streams=btcusdt@aggtrade/ethusdt@aggtrade"
+        );
+    }
+
+    #[test]
+    fn presets_use_their_hosts() {
+        let cases = [
+            (WsEndpoint::binance_futures_testnet(), "wss://stream.binancefuture.com/ws/btcusdt@trade"),
+            (WsEndpoint::binance_coin_futures(), "wss://dstream.binance.com/ws/btcusdt@trade"),
+            (WsEndpoint::binance_coin_futures_testnet(), "wss://dstream.binancefuture.com/ws/btcusdt@trade"),
+            (WsEndpoint::binance_spot(), "wss://stream.binance.com:9443/ws/btcusdt@trade"),
+            (WsEndpoint::binance_spot_testnet(), "wss://stream.testnet.binance.vision/ws/btcusdt@trade"),
+        ];
+        for (endpoint, url) in cases {
+            assert_eq!(endpoint.build_url(&["BTCUSDT@trade"]), url);
+        }
+        assert_eq!(WsEndpoint::default(), WsEndpoint::binance_futures());
+    }
+
+    #[test]
+    fn raw_style_never_wraps() {
+        let endpoint = WsEndpoint::new("127.0.0.1:9001/", PathStyle::Raw, false);
+        assert_eq!(endpoint.base_url, "127.0.0.1:9001");
+        assert!(!endpoint.is_combined(3));
+        assert_eq!(endpoint.build_url(&["btcusdt@trade", "ethusdt@trade"]), "ws://127.0.0.1:9001/ws/btcusdt@trade/ethusdt@trade");
+        for endpoint in [WsEndpoint::okx_public(), WsEndpoint::bybit_linear(), WsEndpoint::bybit_spot(), WsEndpoint::bybit_inverse()] {
+            assert!(!endpoint.is_combined(2));
+            assert_eq!(endpoint.scheme(), "wss");
+        }
+    }
+
+    #[test]
+    fn combined_style_always_wraps() {
+        let endpoint = WsEndpoint::new("127.0.0.1:9001", PathStyle::Combined, false);
+        assert!(endpoint.is_combined(1));
+        assert_eq!(endpoint.build_url(&["btcusdt@trade"]), "ws://127.0.0.1:9001/stream?streams=btcusdt@trade");
+    }
+
+    #[test]
+    fn local_endpoint_keeps_listen_key_case() {
+        let endpoint = WsEndpoint::local("127.0.0.1:9001");
+        assert_eq!(endpoint.scheme(), "ws");
+        // listenKey 不含 '@'，区分大小写
+        assert_eq!(endpoint.build_url(&["AbCdListenKey"]), "ws://127.0.0.1:9001/ws/AbCdListenKey");
+    }
+
+    #[test]
+    fn deserializes_with_defaults() {
+        let endpoint: WsEndpoint = serde_json::from_str(r#"{"base_url": "fstream.binance.com"}"#).unwrap();
+        assert_eq!(endpoint, WsEndpoint::binance_futures());
+        let endpoint: WsEndpoint = serde_json::from_str(r#"{"base_url": "127.0.0.1:9001", "path_style": "combined", "tls": false}"#).unwrap();
+        assert_eq!(endpoint.path_style, PathStyle::Combined);
+        assert!(!endpoint.tls);
+    }
+}
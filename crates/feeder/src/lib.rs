@@ -1,3 +1,14 @@
 pub mod websocket;
+pub mod endpoint;
+pub mod pool;
+pub mod response;
+pub mod reconnect;
+pub mod rollover;
+pub mod watchdog;
+pub mod lifecycle;
+pub mod okx_websocket;
+pub mod bybit_websocket;
+pub mod listen_key;
+pub mod command;
 // pub mod binance_ws;
 // pub mod feeder_manager;
@@ -0,0 +1,61 @@
+// feeder/lifecycle.rs
+
+use std::collections::HashSet;
+
+use crate::watchdog::StallReport;
+
+/// 连接生命周期事件，供上层（如 MarketAgent）转换为事件引擎中的事件
+#[derive(Debug, Clone)]
+pub enum LifecycleEvent {
+    /// 连接建立，附带 URL 中携带的流
+    Connected { streams: Vec<String> },
+    /// 连接断开（服务端关闭、读错误、静默等），附带原因与断开时订阅的流
+    Disconnected { reason: String, streams: Vec<String> },
+    /// 断线重连后已恢复全部订阅
+    Resubscribed { streams: Vec<String> },
+    /// 连接静默超过阈值，随后会触发 Disconnected 与重连
+    Stale(StallReport),
+}
+
+/// 生命周期回调：参数为连接编号与事件
+pub type LifecycleCallback = Box<dyn FnMut(u64, LifecycleEvent) + Send + 'static>;
+
+/// 连接编号与生命周期回调，各交易所客户端共用同一套上报逻辑
+#[derive(Default)]
+pub(crate) struct LifecycleEmitter {
+    /// 连接编号（连接池内唯一），随生命周期事件一起上报
+    connection_id: u64,
+    callback: Option<LifecycleCallback>,
+}
+
+impl LifecycleEmitter {
+    pub(crate) fn set_connection_id(&mut self, id: u64) {
+        self.connection_id = id;
+    }
+
+    pub(crate) fn connection_id(&self) -> u64 {
+        self.connection_id
+    }
+
+    pub(crate) fn set_callback<F>(&mut self, callback: F)
+    where
+        F: FnMut(u64, LifecycleEvent) + Send + 'static,
+    {
+        self.callback = Some(Box::new(callback));
+    }
+
+    pub(crate) fn emit(&mut self, event: LifecycleEvent) {
+        if let Some(ref mut callback) = self.callback {
+            callback(self.connection_id, event);
+        }
+    }
+
+    /// 丢弃当前连接；若之前处于连接状态则上报 Disconnected，附带断开时订阅的流
+    pub(crate) fn disconnect<S>(&mut self, ws_stream: &mut Option<S>, reason: &str, subscribed: &HashSet<String>) {
+        if ws_stream.take().is_some() {
+            let mut streams: Vec<String> = subscribed.iter().cloned().collect();
+            streams.sort();
+            self.emit(LifecycleEvent::Disconnected { reason: reason.to_string(), streams });
+        }
+    }
+}
@@ -0,0 +1,88 @@
+// feeder/listen_key.rs
+
+use std::error::Error;
+use std::time::Duration;
+
+use reqwest::{Client, Method};
+use serde_json::Value;
+
+/// 单次请求的超时时间，避免交易所无响应时 keepalive 一直挂起
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// 建立连接的超时时间
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// 用户数据流 listenKey 的 REST 管理：创建、延长有效期、关闭。
+/// listenKey 有效期 60 分钟，需要定期 keepalive；过期后服务端推送 listenKeyExpired
+#[derive(Clone)]
+pub struct ListenKeyClient {
+    client: Client,
+    /// REST 基础地址，例如 "https://fapi.binance.com"，本地 mock 为 "http://127.0.0.1:9002"
+    base_url: String,
+    /// 接口路径，U 本位合约为 "/fapi/v1/listenKey"
+    path: String,
+    api_key: String,
+}
+
+impl ListenKeyClient {
+    pub fn new(base_url: &str, path: &str, api_key: &str) -> Self {
+        Self {
+            client: Client::builder()
+                .timeout(REQUEST_TIMEOUT)
+                .connect_timeout(CONNECT_TIMEOUT)
+                .build()
+                .expect("创建 HTTP 客户端失败"),
+            base_url: base_url.trim_end_matches('/').to_string(),
+            path: path.to_string(),
+            api_key: api_key.to_string(),
+        }
+    }
+
+    /// U 本位合约
+    pub fn binance_futures(api_key: &str) -> Self {
+        Self::new("https://fapi.binance.com", "/fapi/v1/listenKey", api_key)
+    }
+
+    fn url(&self) -> String {
+        format!("{}{}", self.base_url, self.path)
+    }
+
+    async fn request(&self, method: Method, listen_key: Option<&str>) -> Result<Value, Box<dyn Error>> {
+        let mut request = self
+            .client
+            .request(method, self.url())
+            .header("X-MBX-APIKEY", &self.api_key);
+        // 合约接口不需要 listenKey 参数，现货需要，统一带上
+        if let Some(key) = listen_key {
+            request = request.query(&[("listenKey", key)]);
+        }
+        let response = request.send().await?;
+        let status = response.status();
+        let body = response.text().await?;
+        if !status.is_success() {
+            return Err(format!("listenKey 请求失败: {} {}", status, body).into());
+        }
+        Ok(serde_json::from_str(&body).unwrap_or(Value::Null))
+    }
+
+    /// 创建 listenKey（已存在有效的 listenKey 时交易所返回同一个并延长有效期）
+    pub async fn create(&self) -> Result<String, Box<dyn Error>> {
+        let body = self.request(Method::POST, None).await?;
+        body["listenKey"]
+            .as_str()
+            .map(|s| s.to_string())
+            .ok_or_else(|| format!("响应中没有 listenKey: {}", body).into())
+    }
+
+    /// 延长 listenKey 有效期
+    pub async fn keepalive(&self, listen_key: &str) -> Result<(), Box<dyn Error>> {
+        self.request(Method::PUT, Some(listen_key)).await?;
+        Ok(())
+    }
+
+    /// 关闭 listenKey，之后的连接不再推送数据
+    pub async fn close(&self, listen_key: &str) -> Result<(), Box<dyn Error>> {
+        self.request(Method::DELETE, Some(listen_key)).await?;
+        Ok(())
+    }
+}
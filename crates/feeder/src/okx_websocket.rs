@@ -0,0 +1,456 @@
+// feeder/okx_websocket.rs
+
+use async_trait::async_trait;
+use futures_util::{SinkExt, StreamExt};
+use serde_json::{json, Value};
+use std::collections::{HashSet, VecDeque};
+use std::error::Error;
+use std::io;
+use std::time::{Duration, Instant};
+use tokio::net::TcpStream;
+use tokio::sync::mpsc;
+use tokio::time::timeout;
+use tokio_tungstenite::{connect_async, tungstenite::Message, MaybeTlsStream, WebSocketStream};
+use url::Url;
+
+use crate::endpoint::WsEndpoint;
+use crate::lifecycle::{LifecycleEmitter, LifecycleEvent};
+use crate::reconnect::{ReconnectPolicy, ReconnectState};
+use crate::command::{self, WsCommand};
+use crate::websocket::WebSocket;
+
+type WsStream = WebSocketStream<MaybeTlsStream<TcpStream>>;
+
+type MessageCallback = Box<dyn FnMut(String) + Send + 'static>;
+
+/// 超过该时间没有收到任何消息时发送文本 "ping"（OKX 30 秒无数据会断开连接）
+const PING_INTERVAL: Duration = Duration::from_secs(20);
+
+/// 等待交易所应答的默认超时
+const DEFAULT_ACK_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// 将 "<instId>@<channel>" 形式的流名称转换为 OKX 订阅参数，
+/// 例如 "BTC-USDT-SWAP@books" -> {"channel": "books", "instId": "BTC-USDT-SWAP"}
+pub fn channel_arg(stream: &str) -> Result<Value, Box<dyn Error>> {
+    match stream.rsplit_once('@') {
+        Some((inst_id, channel)) if !inst_id.is_empty() && !channel.is_empty() => {
+            Ok(json!({ "channel": channel, "instId": inst_id }))
+        }
+        _ => Err(format!("无效的 OKX 流名称: {}（应为 <instId>@<channel>）", stream).into()),
+    }
+}
+
+/// 由消息中的 arg 还原流名称
+pub fn stream_of(arg: &Value) -> Option<String> {
+    let channel = arg.get("channel")?.as_str()?;
+    let inst_id = arg.get("instId")?.as_str()?;
+    Some(format!("{}@{}", inst_id, channel))
+}
+
+/// 读取结果，在 select 中先取出再处理，避免借用跨越分支
+enum Incoming {
+    Message(Option<Result<Message, tokio_tungstenite::tungstenite::Error>>),
+    Idle,
+    Resync(String),
+    Command(WsCommand),
+}
+
+/// OKX 公共频道客户端：订阅 trades / books / tickers 等无需登录的频道。
+/// 与币安不同，OKX 连接地址固定，所有订阅都通过 {"op": "subscribe", "args": [...]} 完成，
+/// 心跳由客户端发送文本 "ping"、服务端回复 "pong"
+pub struct OkxWebSocketClient {
+    ws_stream: Option<WsStream>,
+    endpoint: WsEndpoint,
+    /// 已确认的订阅流（"<instId>@<channel>"）
+    subscribed_streams: HashSet<String>,
+    on_message_callback: Option<MessageCallback>,
+    /// 等待应答期间收到的行情消息，监听循环启动后优先回调
+    buffered_messages: VecDeque<String>,
+    ack_timeout: Duration,
+    /// 已发送 ping、尚未收到 pong
+    awaiting_pong: bool,
+
+    /// 断线重连策略与连续失败次数
+    reconnect: ReconnectState,
+    /// 连接编号与生命周期回调
+    lifecycle: LifecycleEmitter,
+
+    /// 重新订阅请求（例如订单簿校验和不一致时需要新的快照），监听循环中处理
+    resync_tx: mpsc::UnboundedSender<String>,
+    resync_rx: mpsc::UnboundedReceiver<String>,
+
+    /// 订阅、取消订阅与查询命令，监听循环在两次读取之间执行
+    command_tx: mpsc::UnboundedSender<WsCommand>,
+    command_rx: mpsc::UnboundedReceiver<WsCommand>,
+}
+
+impl OkxWebSocketClient {
+    /// 使用 OKX 公共频道正式环境
+    pub fn new() -> Self {
+        Self::with_endpoint(WsEndpoint::okx_public())
+    }
+
+    pub fn with_endpoint(endpoint: WsEndpoint) -> Self {
+        let (resync_tx, resync_rx) = mpsc::unbounded_channel();
+        let (command_tx, command_rx) = mpsc::unbounded_channel();
+        Self {
+            ws_stream: None,
+            endpoint,
+            subscribed_streams: HashSet::new(),
+            on_message_callback: None,
+            buffered_messages: VecDeque::new(),
+            ack_timeout: DEFAULT_ACK_TIMEOUT,
+            awaiting_pong: false,
+            reconnect: ReconnectState::default(),
+            lifecycle: LifecycleEmitter::default(),
+            resync_tx,
+            resync_rx,
+            command_tx,
+            command_rx,
+        }
+    }
+
+    /// 设置消息回调
+    pub fn set_message_callback<F>(&mut self, callback: F)
+    where
+        F: FnMut(String) + Send + 'static,
+    {
+        self.on_message_callback = Some(Box::new(callback));
+    }
+
+    /// 设置生命周期回调
+    pub fn set_lifecycle_callback<F>(&mut self, callback: F)
+    where
+        F: FnMut(u64, LifecycleEvent) + Send + 'static,
+    {
+        self.lifecycle.set_callback(callback);
+    }
+
+    /// 设置连接编号
+    pub fn set_connection_id(&mut self, id: u64) {
+        self.lifecycle.set_connection_id(id);
+    }
+
+    /// 设置断线重连策略
+    pub fn set_reconnect_policy(&mut self, policy: ReconnectPolicy) {
+        self.reconnect.policy = policy;
+    }
+
+    /// 设置等待应答的超时时间
+    pub fn set_ack_timeout(&mut self, ack_timeout: Duration) {
+        self.ack_timeout = ack_timeout;
+    }
+
+    /// 重新订阅句柄：发送流名称即可让监听循环对该流取消订阅再订阅，
+    /// 可在消息回调等同步代码中使用
+    pub fn resync_handle(&self) -> mpsc::UnboundedSender<String> {
+        self.resync_tx.clone()
+    }
+
+    /// 命令句柄：监听循环运行期间通过它订阅、取消订阅和查询，不会打断监听循环；
+    /// 连接断开时命令在重连成功后执行
+    pub fn command_handle(&self) -> mpsc::UnboundedSender<WsCommand> {
+        self.command_tx.clone()
+    }
+
+    /// 当前端点配置
+    pub fn endpoint(&self) -> &WsEndpoint {
+        &self.endpoint
+    }
+
+    /// 当前已订阅的流
+    pub fn subscribed_streams(&self) -> &HashSet<String> {
+        &self.subscribed_streams
+    }
+
+    /// 是否已建立连接
+    pub fn is_connected(&self) -> bool {
+        self.ws_stream.is_some()
+    }
+
+    fn url(&self) -> String {
+        format!("{}://{}", self.endpoint.scheme(), self.endpoint.base_url)
+    }
+
+    /// 丢弃当前连接；若之前处于连接状态则上报 Disconnected
+    fn mark_disconnected(&mut self, reason: &str) {
+        self.lifecycle.disconnect(&mut self.ws_stream, reason, &self.subscribed_streams);
+    }
+
+    fn sorted_streams(&self) -> Vec<String> {
+        let mut streams: Vec<String> = self.subscribed_streams.iter().cloned().collect();
+        streams.sort();
+        streams
+    }
+
+    /// 建立一条新的底层连接，不借用 self
+    async fn open_stream(url_str: String) -> Result<WsStream, Box<dyn Error>> {
+        println!("尝试连接: {}", url_str);
+        let url = Url::parse(&url_str)?;
+        let (ws_stream, _) = connect_async(url).await?;
+        Ok(ws_stream)
+    }
+
+    /// 发送订阅类操作：{"op": "subscribe", "args": [{"channel": ..., "instId": ...}]}
+    async fn send_op(&mut self, op: &str, streams: &[String]) -> Result<(), Box<dyn Error>> {
+        let args = streams
+            .iter()
+            .map(|s| channel_arg(s))
+            .collect::<Result<Vec<Value>, _>>()?;
+        let msg = json!({ "op": op, "args": args }).to_string();
+        self.send(&msg).await?;
+        println!("已发送请求: {}", msg);
+        Ok(())
+    }
+
+    /// 等待每个流的应答；期间收到的行情消息先缓存。
+    /// OKX 的错误应答不带 arg，收到即视为本次请求失败
+    async fn await_acks(&mut self, op: &str, streams: &[String]) -> Result<(), Box<dyn Error>> {
+        let mut pending: HashSet<String> = streams.iter().cloned().collect();
+        let deadline = Instant::now() + self.ack_timeout;
+        while !pending.is_empty() {
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            let ws = match self.ws_stream.as_mut() {
+                Some(ws) => ws,
+                None => return Err(Box::new(io::Error::new(io::ErrorKind::Other, "WebSocket 未连接"))),
+            };
+            let text = match timeout(remaining, ws.next()).await {
+                Err(_) => return Err(format!("等待 {} 应答超时: {:?}", op, pending).into()),
+                Ok(None) => return Err("WebSocket 已关闭".into()),
+                Ok(Some(Err(e))) => return Err(Box::new(e)),
+                Ok(Some(Ok(Message::Text(text)))) => text,
+                Ok(Some(Ok(Message::Ping(data)))) => {
+                    ws.send(Message::Pong(data)).await?;
+                    continue;
+                }
+                Ok(Some(Ok(_))) => continue,
+            };
+            if text == "pong" {
+                self.awaiting_pong = false;
+                continue;
+            }
+            let value: Value = match serde_json::from_str(&text) {
+                Ok(value) => value,
+                Err(_) => continue,
+            };
+            match value.get("event").and_then(|e| e.as_str()) {
+                Some("error") => {
+                    return Err(format!(
+                        "{} 失败: code={} msg={}",
+                        op,
+                        value.get("code").and_then(|c| c.as_str()).unwrap_or(""),
+                        value.get("msg").and_then(|m| m.as_str()).unwrap_or("")
+                    )
+                    .into());
+                }
+                Some(event) if event == op => {
+                    if let Some(stream) = value.get("arg").and_then(stream_of) {
+                        pending.remove(&stream);
+                    }
+                }
+                Some(_) => {}
+                None => self.buffered_messages.push_back(text),
+            }
+        }
+        Ok(())
+    }
+
+    /// 重新建立连接并恢复全部订阅
+    async fn reconnect(&mut self) -> Result<(), Box<dyn Error>> {
+        let ws_stream = Self::open_stream(self.url()).await?;
+        self.ws_stream = Some(ws_stream);
+        self.awaiting_pong = false;
+        let streams = self.sorted_streams();
+        if !streams.is_empty() {
+            self.send_op("subscribe", &streams).await?;
+            self.await_acks("subscribe", &streams).await?;
+        }
+        Ok(())
+    }
+
+    /// 处理一条文本消息：pong 与操作应答不进入回调
+    fn handle_text(&mut self, text: String) {
+        if text == "pong" {
+            self.awaiting_pong = false;
+            return;
+        }
+        if let Ok(value) = serde_json::from_str::<Value>(&text) {
+            match value.get("event").and_then(|e| e.as_str()) {
+                Some("error") => {
+                    eprintln!("[OKX] 错误应答: {}", text);
+                    return;
+                }
+                Some("notice") => {
+                    // 服务升级前的断线通知，随后连接会被关闭并自动重连
+                    println!("[OKX] 通知: {}", text);
+                    return;
+                }
+                Some(_) => return,
+                None => {}
+            }
+        }
+        if let Some(ref mut callback) = self.on_message_callback {
+            callback(text);
+        }
+    }
+}
+
+impl Default for OkxWebSocketClient {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[async_trait]
+impl WebSocket for OkxWebSocketClient {
+    /// 连接后逐一订阅传入的流（OKX 不支持在 URL 中携带订阅）
+    async fn connect(&mut self, streams: Vec<&str>) -> Result<(), Box<dyn Error>> {
+        let ws_stream = Self::open_stream(self.url()).await?;
+        self.ws_stream = Some(ws_stream);
+        self.awaiting_pong = false;
+        println!("连接成功");
+        if !streams.is_empty() {
+            self.subscribe(streams.clone()).await?;
+        }
+        let streams = streams.iter().map(|s| s.to_string()).collect();
+        self.lifecycle.emit(LifecycleEvent::Connected { streams });
+        Ok(())
+    }
+
+    async fn send(&mut self, msg: &str) -> Result<(), Box<dyn Error>> {
+        if let Some(ref mut ws) = self.ws_stream {
+            ws.send(Message::Text(msg.to_string())).await?;
+            Ok(())
+        } else {
+            Err(Box::new(io::Error::new(io::ErrorKind::Other, "WebSocket 未连接")))
+        }
+    }
+
+    async fn read_message(&mut self) -> Result<Message, Box<dyn Error>> {
+        if let Some(ref mut ws) = self.ws_stream {
+            match ws.next().await {
+                Some(msg) => Ok(msg?),
+                None => Err(Box::new(io::Error::new(io::ErrorKind::Other, "WebSocket 已关闭"))),
+            }
+        } else {
+            Err(Box::new(io::Error::new(io::ErrorKind::Other, "WebSocket 未连接")))
+        }
+    }
+
+    async fn listen_loop(&mut self) -> Result<(), Box<dyn Error>> {
+        loop {
+            if self.ws_stream.is_none() {
+                if self.subscribed_streams.is_empty() {
+                    return Err("无任何已记录的订阅流，无法重连".into());
+                }
+                let attempt = self.reconnect.next_attempt()?;
+                self.reconnect.backoff("[OKX] ", attempt).await;
+                let failed = match self.reconnect().await {
+                    Ok(()) => None,
+                    Err(e) => Some(e.to_string()),
+                };
+                if let Some(e) = failed {
+                    eprintln!("[OKX] 重连失败: {}", e);
+                    self.ws_stream = None;
+                    self.reconnect.failed(attempt);
+                    continue;
+                }
+                self.reconnect.succeeded();
+                println!("[OKX] 重连并重新订阅成功");
+                let streams = self.sorted_streams();
+                self.lifecycle.emit(LifecycleEvent::Resubscribed { streams });
+            }
+
+            while let Some(text) = self.buffered_messages.pop_front() {
+                if let Some(ref mut callback) = self.on_message_callback {
+                    callback(text);
+                }
+            }
+
+            let incoming = match self.ws_stream.as_mut() {
+                Some(ws) => tokio::select! {
+                    read = timeout(PING_INTERVAL, ws.next()) => match read {
+                        Ok(msg) => Incoming::Message(msg),
+                        Err(_) => Incoming::Idle,
+                    },
+                    Some(stream) = self.resync_rx.recv() => Incoming::Resync(stream),
+                    Some(command) = self.command_rx.recv() => Incoming::Command(command),
+                },
+                None => continue,
+            };
+
+            match incoming {
+                Incoming::Command(command) => command::execute(self, command).await,
+                Incoming::Idle => {
+                    if self.awaiting_pong {
+                        self.mark_disconnected("ping 无响应");
+                        continue;
+                    }
+                    self.awaiting_pong = true;
+                    if let Err(e) = self.send("ping").await.map_err(|e| e.to_string()) {
+                        self.mark_disconnected(&format!("发送 ping 失败: {}", e));
+                    }
+                }
+                Incoming::Resync(stream) => {
+                    if !self.subscribed_streams.contains(&stream) {
+                        continue;
+                    }
+                    // 应答由 handle_text 忽略；重新订阅后交易所会推送新的快照
+                    println!("[OKX] 重新订阅 {}", stream);
+                    let streams = vec![stream];
+                    let unsubscribed = self.send_op("unsubscribe", &streams).await.map_err(|e| e.to_string());
+                    let sent = match unsubscribed {
+                        Ok(()) => self.send_op("subscribe", &streams).await.map_err(|e| e.to_string()),
+                        Err(e) => Err(e),
+                    };
+                    if let Err(e) = sent {
+                        self.mark_disconnected(&format!("重新订阅失败: {}", e));
+                    }
+                }
+                Incoming::Message(None) => self.mark_disconnected("WebSocket 已关闭"),
+                Incoming::Message(Some(Err(e))) => {
+                    eprintln!("[OKX] 读取消息错误: {}，准备重连", e);
+                    self.mark_disconnected(&format!("读取消息错误: {}", e));
+                }
+                Incoming::Message(Some(Ok(message))) => match message {
+                    Message::Text(text) => self.handle_text(text),
+                    Message::Ping(data) => {
+                        if let Some(ref mut ws) = self.ws_stream {
+                            let _ = ws.send(Message::Pong(data)).await;
+                        }
+                    }
+                    Message::Close(frame) => {
+                        println!("[OKX] 收到关闭消息: {:?}", frame);
+                        self.mark_disconnected(&format!("收到关闭帧: {:?}", frame));
+                    }
+                    _ => {}
+                },
+            }
+        }
+    }
+
+    async fn subscribe(&mut self, streams: Vec<&str>) -> Result<(), Box<dyn Error>> {
+        let streams: Vec<String> = streams.iter().map(|s| s.to_string()).collect();
+        self.send_op("subscribe", &streams).await?;
+        self.await_acks("subscribe", &streams).await?;
+        self.subscribed_streams.extend(streams.iter().cloned());
+        println!("订阅已确认: {:?}", streams);
+        Ok(())
+    }
+
+    async fn unsubscribe(&mut self, streams: Vec<&str>) -> Result<(), Box<dyn Error>> {
+        let streams: Vec<String> = streams.iter().map(|s| s.to_string()).collect();
+        self.send_op("unsubscribe", &streams).await?;
+        self.await_acks("unsubscribe", &streams).await?;
+        for s in &streams {
+            self.subscribed_streams.remove(s);
+        }
+        println!("取消订阅已确认: {:?}", streams);
+        Ok(())
+    }
+
+    /// OKX 没有查询订阅列表的操作，返回本地记录的已确认订阅
+    async fn list_subscriptions(&mut self) -> Result<Vec<String>, Box<dyn Error>> {
+        Ok(self.sorted_streams())
+    }
+}
@@ -0,0 +1,488 @@
+// feeder/pool.rs
+
+use futures_util::stream::{FuturesUnordered, StreamExt};
+use std::collections::HashSet;
+use std::error::Error;
+use std::sync::{Arc, Mutex};
+use tokio::sync::{mpsc, watch};
+
+use crate::command::WsCommand;
+use crate::endpoint::WsEndpoint;
+use crate::lifecycle::LifecycleEvent;
+use crate::websocket::{BinanceWebSocketClient, WebSocket, MAX_STREAMS_PER_CONNECTION};
+
+// 各连接的监听循环可能运行在不同线程上，共享回调用 Arc<Mutex> 包装
+type SharedCallback = Arc<Mutex<Box<dyn FnMut(String) + Send + 'static>>>;
+type SharedLifecycleCallback = Arc<Mutex<Box<dyn FnMut(u64, LifecycleEvent) + Send + 'static>>>;
+
+/// 连接池：把订阅流自动分散到多个 BinanceWebSocketClient 连接上，
+/// 对外表现为一个连接 + 一个统一的消息回调
+pub struct BinanceWebSocketPool {
+    /// 新建连接时使用的端点
+    endpoint: WsEndpoint,
+    /// 每个连接最多承载的流数量（不超过币安的 200 上限）
+    max_streams_per_connection: usize,
+    /// 池中的连接
+    connections: Vec<BinanceWebSocketClient>,
+    /// 统一的消息回调，由所有连接共享
+    on_message_callback: Option<SharedCallback>,
+    /// 统一的生命周期回调，由所有连接共享
+    on_lifecycle_callback: Option<SharedLifecycleCallback>,
+    /// 下一个连接编号
+    next_connection_id: u64,
+    /// 订阅、取消订阅与查询命令，监听循环运行期间通过它调整连接
+    command_tx: mpsc::UnboundedSender<WsCommand>,
+    command_rx: mpsc::UnboundedReceiver<WsCommand>,
+}
+
+impl BinanceWebSocketPool {
+    pub fn new(endpoint: WsEndpoint) -> Self {
+        let (command_tx, command_rx) = mpsc::unbounded_channel();
+        Self {
+            endpoint,
+            max_streams_per_connection: MAX_STREAMS_PER_CONNECTION,
+            connections: Vec::new(),
+            on_message_callback: None,
+            on_lifecycle_callback: None,
+            next_connection_id: 0,
+            command_tx,
+            command_rx,
+        }
+    }
+
+    /// 由一个已建立的连接构造连接池（兼容原有单连接用法）
+    pub fn from_client(client: BinanceWebSocketClient) -> Self {
+        let mut pool = Self::new(client.endpoint().clone());
+        pool.next_connection_id = client.connection_id() + 1;
+        pool.connections.push(client);
+        pool
+    }
+
+    /// 新建连接时使用的端点
+    pub fn endpoint(&self) -> &WsEndpoint {
+        &self.endpoint
+    }
+
+    /// 命令句柄：监听循环运行期间通过它订阅、取消订阅和查询，不会打断监听循环
+    pub fn command_handle(&self) -> mpsc::UnboundedSender<WsCommand> {
+        self.command_tx.clone()
+    }
+
+    /// 设置每个连接承载的流数量上限（主要用于测试分片逻辑）
+    pub fn set_max_streams_per_connection(&mut self, max: usize) {
+        self.max_streams_per_connection = max.clamp(1, MAX_STREAMS_PER_CONNECTION);
+    }
+
+    /// 设置统一消息回调，所有连接（包括之后新建的连接）的文本消息都会汇总到这里
+    pub fn set_message_callback<F>(&mut self, callback: F)
+    where
+        F: FnMut(String) + Send + 'static,
+    {
+        let shared: SharedCallback = Arc::new(Mutex::new(Box::new(callback)));
+        for client in self.connections.iter_mut() {
+            Self::attach_callback(client, &shared);
+        }
+        self.on_message_callback = Some(shared);
+    }
+
+    fn attach_callback(client: &mut BinanceWebSocketClient, shared: &SharedCallback) {
+        let shared = shared.clone();
+        client.set_message_callback(move |msg: String| {
+            (shared.lock().unwrap())(msg);
+        });
+    }
+
+    /// 设置统一生命周期回调，回调参数中的连接编号可区分具体是哪个连接
+    pub fn set_lifecycle_callback<F>(&mut self, callback: F)
+    where
+        F: FnMut(u64, LifecycleEvent) + Send + 'static,
+    {
+        let shared: SharedLifecycleCallback = Arc::new(Mutex::new(Box::new(callback)));
+        for client in self.connections.iter_mut() {
+            Self::attach_lifecycle_callback(client, &shared);
+        }
+        self.on_lifecycle_callback = Some(shared);
+    }
+
+    fn attach_lifecycle_callback(client: &mut BinanceWebSocketClient, shared: &SharedLifecycleCallback) {
+        let shared = shared.clone();
+        client.set_lifecycle_callback(move |id: u64, event: LifecycleEvent| {
+            (shared.lock().unwrap())(id, event);
+        });
+    }
+
+    /// 当前连接数
+    pub fn connection_count(&self) -> usize {
+        self.connections.len()
+    }
+
+    /// 所有已订阅的流
+    pub fn subscribed_streams(&self) -> HashSet<String> {
+        self.connections
+            .iter()
+            .flat_map(|c| c.subscribed_streams().iter().cloned())
+            .collect()
+    }
+
+    /// 各连接承载的流数量
+    pub fn loads(&self) -> Vec<usize> {
+        self.connections.iter().map(|c| c.subscribed_streams().len()).collect()
+    }
+
+    /// 新建一个连接并直接在 URL 中携带这批流
+    async fn open_connection(&mut self, streams: Vec<&str>) -> Result<(), Box<dyn Error>> {
+        let mut client = BinanceWebSocketClient::with_endpoint(self.endpoint.clone());
+        client.set_connection_id(self.next_connection_id);
+        self.next_connection_id += 1;
+        // 先挂上生命周期回调，使新连接的 Connected 事件也能上报
+        if let Some(ref shared) = self.on_lifecycle_callback {
+            Self::attach_lifecycle_callback(&mut client, shared);
+        }
+        client.connect(streams).await?;
+        if let Some(ref shared) = self.on_message_callback {
+            Self::attach_callback(&mut client, shared);
+        }
+        self.connections.push(client);
+        println!("[连接池] 新建连接，当前连接数: {}", self.connections.len());
+        Ok(())
+    }
+
+    /// 订阅：跳过已订阅的流，优先填充负载最低的连接，全部满载时新建连接
+    pub async fn subscribe(&mut self, streams: Vec<&str>) -> Result<(), Box<dyn Error>> {
+        let existing = self.subscribed_streams();
+        let mut pending: Vec<&str> = Vec::new();
+        for s in streams {
+            if !existing.contains(s) && !pending.contains(&s) {
+                pending.push(s);
+            }
+        }
+
+        while !pending.is_empty() {
+            // 找到负载最低且未满的连接
+            let target = self
+                .connections
+                .iter()
+                .enumerate()
+                .filter(|(_, c)| c.subscribed_streams().len() < self.max_streams_per_connection)
+                .min_by_key(|(_, c)| c.subscribed_streams().len())
+                .map(|(i, c)| (i, self.max_streams_per_connection - c.subscribed_streams().len()));
+
+            match target {
+                Some((index, free)) => {
+                    let take = free.min(pending.len());
+                    let batch: Vec<&str> = pending.drain(..take).collect();
+                    self.connections[index].subscribe(batch).await?;
+                }
+                None => {
+                    let take = self.max_streams_per_connection.min(pending.len());
+                    let batch: Vec<&str> = pending.drain(..take).collect();
+                    self.open_connection(batch).await?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// 取消订阅：从承载该流的连接上取消，之后尝试合并低负载连接
+    pub async fn unsubscribe(&mut self, streams: Vec<&str>) -> Result<(), Box<dyn Error>> {
+        for client in self.connections.iter_mut() {
+            let owned: Vec<&str> = streams
+                .iter()
+                .copied()
+                .filter(|s| client.subscribed_streams().contains(*s))
+                .collect();
+            if !owned.is_empty() {
+                client.unsubscribe(owned).await?;
+            }
+        }
+        self.rebalance().await
+    }
+
+    /// 向每个连接查询交易所侧的订阅列表并汇总，同时以此校正本地记录
+    pub async fn list_subscriptions(&mut self) -> Result<Vec<String>, Box<dyn Error>> {
+        let mut all = Vec::new();
+        for client in self.connections.iter_mut() {
+            all.extend(client.list_subscriptions().await?);
+        }
+        Ok(all)
+    }
+
+    /// 重新平衡：若把负载最低的连接上的流迁移到其他连接后仍不超限，则迁移并关闭该连接
+    pub async fn rebalance(&mut self) -> Result<(), Box<dyn Error>> {
+        // 先移除已无任何订阅的连接
+        let before = self.connections.len();
+        self.connections.retain(|c| !c.subscribed_streams().is_empty());
+        if self.connections.len() != before {
+            println!("[连接池] 关闭空闲连接 {} 个，当前连接数: {}", before - self.connections.len(), self.connections.len());
+        }
+
+        while self.connections.len() > 1 {
+            let total: usize = self.loads().iter().sum();
+            let needed = total.div_ceil(self.max_streams_per_connection);
+            if needed >= self.connections.len() {
+                break;
+            }
+
+            // 选出负载最低的连接作为迁出方
+            let (src, _) = self
+                .connections
+                .iter()
+                .enumerate()
+                .min_by_key(|(_, c)| c.subscribed_streams().len())
+                .map(|(i, c)| (i, c.subscribed_streams().len()))
+                .unwrap();
+            let mut source = self.connections.remove(src);
+            let moving: Vec<String> = source.subscribed_streams().iter().cloned().collect();
+            let mut moving_ref: Vec<&str> = moving.iter().map(|s| s.as_str()).collect();
+
+            // 先在其他连接上订阅（先建后断，避免数据缺口），再取消源连接上的订阅
+            for client in self.connections.iter_mut() {
+                if moving_ref.is_empty() {
+                    break;
+                }
+                let free = self.max_streams_per_connection.saturating_sub(client.subscribed_streams().len());
+                if free == 0 {
+                    continue;
+                }
+                let take = free.min(moving_ref.len());
+                let batch: Vec<&str> = moving_ref.drain(..take).collect();
+                client.subscribe(batch).await?;
+            }
+            let moved: Vec<&str> = moving.iter().map(|s| s.as_str()).collect();
+            source.unsubscribe(moved).await?;
+            println!("[连接池] 已迁移 {} 个流并关闭连接，当前连接数: {}", moving.len(), self.connections.len());
+        }
+        Ok(())
+    }
+
+    /// 同时运行所有连接的监听循环。
+    /// 收到命令时让各连接在下一次读取前暂停（不打断进行中的重连或应答等待），
+    /// 在池上执行命令（可能新建、迁移或关闭连接）后再恢复监听。
+    /// 某个连接重连耗尽返回错误时只移除该分片，并把它的流重新分配到其他连接或新连接上
+    pub async fn listen_loop(&mut self) -> Result<(), Box<dyn Error>> {
+        if self.connections.is_empty() {
+            return Err("连接池为空，无法启动监听".into());
+        }
+        loop {
+            let (pause_tx, pause) = watch::channel(false);
+            let mut failed: Vec<(u64, String)> = Vec::new();
+            let command = {
+                // 不用 try_join_all：它会缓存各连接的返回值，而 Box<dyn Error> 不是 Send，错误先转为字符串
+                let mut loops: FuturesUnordered<_> = self
+                    .connections
+                    .iter_mut()
+                    .map(|c| {
+                        let id = c.connection_id();
+                        let listen = c.listen_until(pause.clone());
+                        async move { (id, listen.await.map_err(|e| e.to_string())) }
+                    })
+                    .collect();
+                let command = loop {
+                    tokio::select! {
+                        // 取消全部订阅后连接池可能为空，此时只等待命令
+                        Some((id, result)) = loops.next(), if !loops.is_empty() => {
+                            if let Err(e) = result {
+                                failed.push((id, e));
+                                break None;
+                            }
+                        }
+                        Some(command) = self.command_rx.recv() => break Some(command),
+                    }
+                };
+                let _ = pause_tx.send(true);
+                while let Some((id, result)) = loops.next().await {
+                    if let Err(e) = result {
+                        failed.push((id, e));
+                    }
+                }
+                command
+            };
+            if !failed.is_empty() {
+                self.replace_failed(failed).await?;
+            }
+            if let Some(command) = command {
+                self.execute(command).await;
+            }
+        }
+    }
+
+    /// 移除失效的连接，把它们承载的流重新订阅到其余连接或新连接上。
+    /// 重新订阅失败且池中已无连接时返回错误，否则只记录日志，其余分片继续运行
+    async fn replace_failed(&mut self, failed: Vec<(u64, String)>) -> Result<(), Box<dyn Error>> {
+        let mut orphaned: Vec<String> = Vec::new();
+        for (id, reason) in failed {
+            eprintln!("[连接池] 连接 {} 失效: {}，移除该分片并重新分配其订阅", id, reason);
+            if let Some(index) = self.connections.iter().position(|c| c.connection_id() == id) {
+                let client = self.connections.remove(index);
+                orphaned.extend(client.subscribed_streams().iter().cloned());
+            }
+        }
+        orphaned.sort();
+        let streams: Vec<&str> = orphaned.iter().map(|s| s.as_str()).collect();
+        let result = self.subscribe(streams).await.map_err(|e| e.to_string());
+        match result {
+            Ok(()) => {
+                println!("[连接池] 已重新分配 {} 个流，当前连接数: {}", orphaned.len(), self.connections.len());
+                Ok(())
+            }
+            Err(e) if self.connections.is_empty() => Err(format!("连接池已无可用连接，重新订阅失败: {}", e).into()),
+            Err(e) => {
+                eprintln!("[连接池] 重新订阅失效分片的流失败: {}", e);
+                Ok(())
+            }
+        }
+    }
+
+    /// 在池上执行一条命令并把结果发回调用方
+    async fn execute(&mut self, command: WsCommand) {
+        match command {
+            WsCommand::Subscribe(streams, reply) => {
+                let streams: Vec<&str> = streams.iter().map(|s| s.as_str()).collect();
+                let result = self.subscribe(streams).await.map_err(|e| e.to_string());
+                let _ = reply.send(result);
+            }
+            WsCommand::Unsubscribe(streams, reply) => {
+                let streams: Vec<&str> = streams.iter().map(|s| s.as_str()).collect();
+                let result = self.unsubscribe(streams).await.map_err(|e| e.to_string());
+                let _ = reply.send(result);
+            }
+            WsCommand::ListSubscriptions(reply) => {
+                let result = self.list_subscriptions().await.map_err(|e| e.to_string());
+                let _ = reply.send(result);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::reconnect::ReconnectPolicy;
+    use futures_util::SinkExt;
+    use serde_json::{json, Value};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::time::Duration;
+    use tokio::net::TcpListener;
+    use tokio_tungstenite::tungstenite::Message;
+
+    /// 本地 WebSocket mock：对每个带 id 的请求回复成功应答；close_first 为 true 时第一个连接建立后立即关闭
+    async fn start_mock(close_first: bool) -> WsEndpoint {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap().to_string();
+        let accepted = Arc::new(AtomicUsize::new(0));
+        tokio::spawn(async move {
+            while let Ok((socket, _)) = listener.accept().await {
+                let first = accepted.fetch_add(1, Ordering::SeqCst) == 0;
+                tokio::spawn(async move {
+                    let Ok(mut ws) = tokio_tungstenite::accept_async(socket).await else {
+                        return;
+                    };
+                    if first && close_first {
+                        let _ = ws.close(None).await;
+                        return;
+                    }
+                    while let Some(Ok(message)) = ws.next().await {
+                        let Message::Text(text) = message else { continue };
+                        let Ok(request) = serde_json::from_str::<Value>(&text) else { continue };
+                        let reply = json!({ "result": null, "id": request["id"] });
+                        let _ = ws.send(Message::Text(reply.to_string())).await;
+                    }
+                });
+            }
+        });
+        WsEndpoint::local(&addr)
+    }
+
+    fn streams(range: std::ops::Range<usize>) -> Vec<String> {
+        range.map(|i| format!("s{}@trade", i)).collect()
+    }
+
+    fn refs(streams: &[String]) -> Vec<&str> {
+        streams.iter().map(|s| s.as_str()).collect()
+    }
+
+    #[tokio::test]
+    async fn subscribe_fills_least_loaded_connection() {
+        let mut pool = BinanceWebSocketPool::new(start_mock(false).await);
+        pool.set_max_streams_per_connection(2);
+
+        pool.subscribe(refs(&streams(0..3))).await.unwrap();
+        assert_eq!(pool.loads(), vec![2, 1]);
+
+        // 已订阅的流被跳过，新流优先进入负载最低的连接
+        let mut batch = streams(0..1);
+        batch.push("s3@trade".to_string());
+        pool.subscribe(refs(&batch)).await.unwrap();
+        assert_eq!(pool.loads(), vec![2, 2]);
+
+        // 全部满载后新建连接
+        pool.subscribe(refs(&streams(4..5))).await.unwrap();
+        assert_eq!(pool.loads(), vec![2, 2, 1]);
+        assert_eq!(pool.subscribed_streams().len(), 5);
+    }
+
+    #[tokio::test]
+    async fn connection_cap_is_clamped_to_exchange_limit() {
+        let mut pool = BinanceWebSocketPool::new(start_mock(false).await);
+        pool.set_max_streams_per_connection(1000);
+        pool.subscribe(refs(&streams(0..MAX_STREAMS_PER_CONNECTION + 1))).await.unwrap();
+        assert_eq!(pool.loads(), vec![MAX_STREAMS_PER_CONNECTION, 1]);
+
+        // 上限至少为 1：两个连接都已满载，新流进入新连接
+        pool.set_max_streams_per_connection(0);
+        pool.subscribe(refs(&streams(300..301))).await.unwrap();
+        assert_eq!(pool.loads(), vec![MAX_STREAMS_PER_CONNECTION, 1, 1]);
+    }
+
+    #[tokio::test]
+    async fn unsubscribe_merges_underloaded_connections() {
+        let mut pool = BinanceWebSocketPool::new(start_mock(false).await);
+        pool.set_max_streams_per_connection(2);
+        pool.subscribe(refs(&streams(0..3))).await.unwrap();
+        assert_eq!(pool.connection_count(), 2);
+
+        // 剩余两个流一个连接即可承载，低负载连接上的流迁移后关闭该连接
+        pool.unsubscribe(vec!["s0@trade"]).await.unwrap();
+        assert_eq!(pool.loads(), vec![2]);
+        let expected: HashSet<String> = ["s1@trade", "s2@trade"].iter().map(|s| s.to_string()).collect();
+        assert_eq!(pool.subscribed_streams(), expected);
+
+        // 取消全部订阅后关闭空闲连接
+        pool.unsubscribe(vec!["s1@trade", "s2@trade"]).await.unwrap();
+        assert_eq!(pool.connection_count(), 0);
+    }
+
+    #[tokio::test]
+    async fn failed_shard_is_replaced_without_ending_the_pool() {
+        let endpoint = start_mock(true).await;
+        let mut client = BinanceWebSocketClient::with_endpoint(endpoint);
+        let mut policy = ReconnectPolicy::default();
+        policy.max_attempts = Some(0);
+        client.set_reconnect_policy(policy);
+        client.connect(vec!["s0@trade", "s1@trade"]).await.unwrap();
+
+        let mut pool = BinanceWebSocketPool::from_client(client);
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        pool.set_lifecycle_callback(move |id, event| {
+            let _ = tx.send((id, event));
+        });
+
+        // 第一个连接被服务端关闭且不允许重连，连接池应新建连接接管它的流
+        let replaced = async {
+            while let Some((id, event)) = rx.recv().await {
+                if let LifecycleEvent::Connected { streams } = event {
+                    return (id, streams);
+                }
+            }
+            panic!("生命周期回调已关闭");
+        };
+        let (id, streams) = tokio::select! {
+            result = pool.listen_loop() => panic!("监听循环提前结束: {:?}", result.map_err(|e| e.to_string())),
+            replaced = tokio::time::timeout(Duration::from_secs(5), replaced) => replaced.unwrap(),
+        };
+        assert_eq!(id, 1);
+        assert_eq!(streams, vec!["s0@trade".to_string(), "s1@trade".to_string()]);
+        assert_eq!(pool.connection_count(), 1);
+        assert_eq!(pool.connections[0].connection_id(), 1);
+    }
+}
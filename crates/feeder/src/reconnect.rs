@@ -0,0 +1,229 @@
+// feeder/reconnect.rs
+
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// 放弃重连时的回调：参数为已尝试次数与最后一次失败原因
+type GiveUpCallback = Box<dyn FnMut(u32, &str) + Send + 'static>;
+
+/// 断线重连策略：指数退避 + 随机抖动 + 最大尝试次数
+pub struct ReconnectPolicy {
+    /// 第一次重连前的等待时间
+    pub initial_delay: Duration,
+    /// 退避等待的上限
+    pub max_delay: Duration,
+    /// 每次失败后等待时间的放大倍数
+    pub multiplier: f64,
+    /// 抖动比例，0.2 表示在 ±20% 范围内随机，避免多连接同时重连
+    pub jitter: f64,
+    /// 最大连续尝试次数，None 表示无限重试
+    pub max_attempts: Option<u32>,
+    on_give_up: Option<GiveUpCallback>,
+}
+
+impl Default for ReconnectPolicy {
+    fn default() -> Self {
+        Self {
+            initial_delay: Duration::from_millis(500),
+            max_delay: Duration::from_secs(60),
+            multiplier: 2.0,
+            jitter: 0.2,
+            max_attempts: None,
+            on_give_up: None,
+        }
+    }
+}
+
+impl ReconnectPolicy {
+    /// 固定间隔、无抖动（等价于原先每次等待 3 秒的行为）
+    pub fn fixed(delay: Duration) -> Self {
+        Self {
+            initial_delay: delay,
+            max_delay: delay,
+            multiplier: 1.0,
+            jitter: 0.0,
+            ..Self::default()
+        }
+    }
+
+    /// 设置放弃重连时的回调
+    pub fn set_give_up_callback<F>(&mut self, callback: F)
+    where
+        F: FnMut(u32, &str) + Send + 'static,
+    {
+        self.on_give_up = Some(Box::new(callback));
+    }
+
+    /// 第 attempt 次尝试（从 1 开始）是否允许
+    pub fn allows(&self, attempt: u32) -> bool {
+        self.max_attempts.map(|max| attempt <= max).unwrap_or(true)
+    }
+
+    /// 第 attempt 次尝试（从 1 开始）前需要等待的时间
+    pub fn delay_for(&self, attempt: u32) -> Duration {
+        let exp = attempt.saturating_sub(1).min(32) as i32;
+        let base = self.initial_delay.as_secs_f64() * self.multiplier.max(1.0).powi(exp);
+        let capped = base.min(self.max_delay.as_secs_f64());
+        let jitter = self.jitter.clamp(0.0, 1.0);
+        // 在 [1 - jitter, 1 + jitter] 内随机缩放
+        let factor = 1.0 + jitter * (2.0 * random_unit() - 1.0);
+        Duration::from_secs_f64((capped * factor).max(0.0))
+    }
+
+    /// 通知放弃重连
+    pub(crate) fn give_up(&mut self, attempts: u32, reason: &str) {
+        eprintln!("重连 {} 次后放弃: {}", attempts, reason);
+        if let Some(ref mut callback) = self.on_give_up {
+            callback(attempts, reason);
+        }
+    }
+}
+
+/// [0, 1) 的伪随机数，仅用于抖动，不要求密码学强度
+fn random_unit() -> f64 {
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_nanos() as u64)
+        .unwrap_or(0);
+    // xorshift64*
+    let mut x = nanos ^ 0x9E37_79B9_7F4A_7C15;
+    x ^= x >> 12;
+    x ^= x << 25;
+    x ^= x >> 27;
+    let r = x.wrapping_mul(0x2545_F491_4F6C_DD1D);
+    (r >> 11) as f64 / (1u64 << 53) as f64
+}
+
+/// 重连计数：按策略决定是否继续尝试与退避多久，重连成功后清零
+#[derive(Default)]
+pub(crate) struct ReconnectState {
+    pub(crate) policy: ReconnectPolicy,
+    /// 当前连续重连失败次数
+    attempts: u32,
+}
+
+impl ReconnectState {
+    /// 下一次尝试的序号（从 1 开始）；超过最大次数时通知放弃并返回原因
+    pub(crate) fn next_attempt(&mut self) -> Result<u32, String> {
+        let attempt = self.attempts + 1;
+        if self.policy.allows(attempt) {
+            return Ok(attempt);
+        }
+        let reason = format!("已达到最大重连次数 {}", self.attempts);
+        self.policy.give_up(self.attempts, &reason);
+        Err(reason)
+    }
+
+    /// 按策略等待第 attempt 次重连。返回的计时器不借用 self（策略中的回调不是 Sync），监听循环的 future 仍是 Send
+    pub(crate) fn backoff(&self, label: &str, attempt: u32) -> tokio::time::Sleep {
+        let delay = self.policy.delay_for(attempt);
+        println!("{}第 {} 次重连，等待 {:?}", label, attempt, delay);
+        tokio::time::sleep(delay)
+    }
+
+    /// 记录第 attempt 次重连失败
+    pub(crate) fn failed(&mut self, attempt: u32) {
+        self.attempts = attempt;
+    }
+
+    /// 重连成功，清零计数
+    pub(crate) fn succeeded(&mut self) {
+        self.attempts = 0;
+    }
+}
+
+/// 故障注入点，测试时用于模拟断线与重连失败
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FaultPoint {
+    /// 读取下一条消息之前，附带本连接已收到的文本消息数
+    BeforeRead { messages_received: u64 },
+    /// 发起第 attempt 次重连之前
+    BeforeReconnect { attempt: u32 },
+}
+
+/// 注入的故障类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InjectedFault {
+    /// 主动发送 Close 帧，模拟服务端正常断开
+    Close,
+    /// 直接丢弃连接，模拟 TCP 断开或读错误
+    Drop,
+    /// 让本次重连尝试失败
+    FailReconnect,
+}
+
+/// 故障注入钩子：在每个注入点被调用，返回 Some 即注入对应故障
+pub type FaultInjector = Box<dyn FnMut(FaultPoint) -> Option<InjectedFault> + Send + 'static>;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn no_jitter() -> ReconnectPolicy {
+        ReconnectPolicy {
+            initial_delay: Duration::from_millis(500),
+            max_delay: Duration::from_secs(10),
+            multiplier: 2.0,
+            jitter: 0.0,
+            ..ReconnectPolicy::default()
+        }
+    }
+
+    #[test]
+    fn delay_grows_exponentially_up_to_the_cap() {
+        let policy = no_jitter();
+        assert_eq!(policy.delay_for(1), Duration::from_millis(500));
+        assert_eq!(policy.delay_for(2), Duration::from_secs(1));
+        assert_eq!(policy.delay_for(3), Duration::from_secs(2));
+        assert_eq!(policy.delay_for(5), Duration::from_secs(8));
+        assert_eq!(policy.delay_for(6), Duration::from_secs(10));
+        // 指数被限制在 32 以内，极大的尝试次数也不会溢出
+        assert_eq!(policy.delay_for(u32::MAX), Duration::from_secs(10));
+        // attempt 从 1 开始，0 按第一次处理
+        assert_eq!(policy.delay_for(0), Duration::from_millis(500));
+    }
+
+    #[test]
+    fn jitter_stays_within_bounds() {
+        let policy = ReconnectPolicy { jitter: 0.2, ..no_jitter() };
+        for _ in 0..1000 {
+            let delay = policy.delay_for(3).as_secs_f64();
+            assert!((1.6..=2.4).contains(&delay), "delay {} out of range", delay);
+        }
+    }
+
+    #[test]
+    fn fixed_policy_never_backs_off() {
+        let policy = ReconnectPolicy::fixed(Duration::from_secs(3));
+        for attempt in 1..10 {
+            assert_eq!(policy.delay_for(attempt), Duration::from_secs(3));
+        }
+    }
+
+    #[test]
+    fn max_attempts_limits_retries() {
+        let policy = ReconnectPolicy { max_attempts: Some(3), ..no_jitter() };
+        assert!(policy.allows(3));
+        assert!(!policy.allows(4));
+        assert!(no_jitter().allows(u32::MAX));
+    }
+
+    #[test]
+    fn state_counts_failures_until_give_up() {
+        let given_up = std::sync::Arc::new(std::sync::Mutex::new(None));
+        let mut policy = ReconnectPolicy { max_attempts: Some(2), ..no_jitter() };
+        let record = given_up.clone();
+        policy.set_give_up_callback(move |attempts, _| *record.lock().unwrap() = Some(attempts));
+        let mut state = ReconnectState { policy, ..ReconnectState::default() };
+
+        assert_eq!(state.next_attempt(), Ok(1));
+        state.failed(1);
+        assert_eq!(state.next_attempt(), Ok(2));
+        state.failed(2);
+        assert!(state.next_attempt().is_err());
+        assert_eq!(*given_up.lock().unwrap(), Some(2));
+
+        // 成功后重新计数
+        state.succeeded();
+        assert_eq!(state.next_attempt(), Ok(1));
+    }
+}
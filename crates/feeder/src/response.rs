@@ -0,0 +1,146 @@
+// feeder/response.rs
+
+use serde_json::Value;
+use std::error::Error;
+use std::fmt;
+
+/// 订阅类请求（SUBSCRIBE / UNSUBSCRIBE / LIST_SUBSCRIPTIONS）的应答
+#[derive(Debug, Clone)]
+pub enum WsResponse {
+    /// 成功：{"result": null, "id": 1} 或 {"result": ["btcusdt@aggTrade"], "id": 3}
+    Ok { id: u64, result: Value },
+    /// 失败：{"code": 2, "msg": "Invalid request", "id": 1}
+    /// 或 {"error": {"code": 2, "msg": "..."}, "id": 1}
+    Err(WsApiError),
+}
+
+impl WsResponse {
+    pub fn id(&self) -> u64 {
+        match self {
+            WsResponse::Ok { id, .. } => *id,
+            WsResponse::Err(e) => e.id,
+        }
+    }
+
+    /// 尝试将一条文本消息解析为请求应答；行情数据返回 None
+    pub fn parse(text: &str) -> Option<WsResponse> {
+        // 快速过滤：行情数据以 {"e": 或 {"stream": 开头，不做 JSON 解析
+        let head = text.trim_start();
+        if head.starts_with("{\"e\"") || head.starts_with("{\"stream\"") {
+            return None;
+        }
+        let value: Value = serde_json::from_str(text).ok()?;
+        let obj = value.as_object()?;
+        let id = obj.get("id")?.as_u64()?;
+
+        if let Some(err) = obj.get("error") {
+            return Some(WsResponse::Err(WsApiError::decode(id, err)));
+        }
+        if obj.contains_key("code") {
+            return Some(WsResponse::Err(WsApiError::decode(id, &value)));
+        }
+        if obj.contains_key("result") {
+            let result = obj.get("result").cloned().unwrap_or(Value::Null);
+            return Some(WsResponse::Ok { id, result });
+        }
+        None
+    }
+}
+
+/// 交易所返回的请求错误
+#[derive(Debug, Clone)]
+pub struct WsApiError {
+    pub id: u64,
+    pub code: i64,
+    pub msg: String,
+}
+
+impl WsApiError {
+    fn decode(id: u64, value: &Value) -> Self {
+        Self {
+            id,
+            code: value.get("code").and_then(|c| c.as_i64()).unwrap_or(-1),
+            msg: value
+                .get("msg")
+                .and_then(|m| m.as_str())
+                .unwrap_or("未知错误")
+                .to_string(),
+        }
+    }
+
+    /// 币安文档中的错误码说明
+    pub fn description(&self) -> &'static str {
+        match self.code {
+            0 => "未知属性",
+            1 => "无效的值类型",
+            2 => "无效的请求",
+            3 => "无效的 JSON",
+            _ => "未知错误码",
+        }
+    }
+}
+
+impl fmt::Display for WsApiError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "请求 {} 被拒绝: code={} ({}) msg={}",
+            self.id,
+            self.code,
+            self.description(),
+            self.msg
+        )
+    }
+}
+
+impl Error for WsApiError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_success_responses() {
+        match WsResponse::parse(r#"{"result":null,"id":1}"#) {
+            Some(WsResponse::Ok { id, result }) => {
+                assert_eq!(id, 1);
+                assert!(result.is_null());
+            }
+            other => panic!("unexpected {:?}", other),
+        }
+        match WsResponse::parse(r#"{"result":["btcusdt@aggTrade"],"id":3}"#) {
+            Some(WsResponse::Ok { id, result }) => {
+                assert_eq!(id, 3);
+                assert_eq!(result[0], "btcusdt@aggTrade");
+            }
+            other => panic!("unexpected {:?}", other),
+        }
+    }
+
+    #[test]
+    fn parses_both_error_shapes() {
+        for text in [
+            r#"{"code":2,"msg":"Invalid request","id":7}"#,
+            r#"{"error":{"code":2,"msg":"Invalid request"},"id":7}"#,
+        ] {
+            match WsResponse::parse(text) {
+                Some(WsResponse::Err(e)) => {
+                    assert_eq!(e.id, 7);
+                    assert_eq!(e.code, 2);
+                    assert_eq!(e.msg, "Invalid request");
+                }
+                other => panic!("unexpected {:?}", other),
+            }
+        }
+    }
+
+    #[test]
+    fn market_data_is_not_a_response() {
+        assert!(WsResponse::parse(r#"{"e":"aggTrade","E":1,"s":"BTCUSDT"}"#).is_none());
+        assert!(WsResponse::parse(r#"{"stream":"btcusdt@aggTrade","data":{"e":"aggTrade"}}"#).is_none());
+        // 缺少 id 或不是 JSON 对象
+        assert!(WsResponse::parse(r#"{"result":null}"#).is_none());
+        assert!(WsResponse::parse("pong").is_none());
+        assert!(WsResponse::parse(r#"[{"id":1}]"#).is_none());
+    }
+}
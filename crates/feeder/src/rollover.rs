@@ -0,0 +1,122 @@
+// feeder/rollover.rs
+
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{HashSet, VecDeque};
+use std::hash::{Hash, Hasher};
+use std::time::Duration;
+
+/// 币安单个连接的最长有效期
+pub const CONNECTION_LIFETIME: Duration = Duration::from_secs(24 * 3600);
+
+/// 先建后断（make-before-break）换连配置
+#[derive(Debug, Clone)]
+pub struct RolloverConfig {
+    /// 在连接到期前多久开始建立备用连接
+    pub lead: Duration,
+    /// 新旧连接至少并行的时间，保证备用连接上的所有流都已开始推送
+    pub min_overlap: Duration,
+    /// 新旧连接最多并行的时间，超过后无论是否观察到重复消息都切换
+    pub max_overlap: Duration,
+    /// 切换后继续去重的时间，用于吸收落后一侧的重复消息
+    pub dedup_grace: Duration,
+    /// 去重窗口保留的最近消息数量
+    pub dedup_capacity: usize,
+}
+
+impl Default for RolloverConfig {
+    fn default() -> Self {
+        Self {
+            lead: Duration::from_secs(10 * 60),
+            min_overlap: Duration::from_secs(2),
+            max_overlap: Duration::from_secs(10),
+            dedup_grace: Duration::from_secs(5),
+            dedup_capacity: 4096,
+        }
+    }
+}
+
+/// 最近消息的指纹窗口，用于新旧连接并行期间去重
+pub struct RecentMessages {
+    seen: HashSet<u64>,
+    order: VecDeque<u64>,
+    capacity: usize,
+}
+
+impl RecentMessages {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            seen: HashSet::with_capacity(capacity),
+            order: VecDeque::with_capacity(capacity),
+            capacity: capacity.max(1),
+        }
+    }
+
+    /// 记录一条消息，已存在时返回 true（重复）
+    pub fn check_and_insert(&mut self, text: &str) -> bool {
+        let mut hasher = DefaultHasher::new();
+        text.hash(&mut hasher);
+        let fingerprint = hasher.finish();
+
+        if !self.seen.insert(fingerprint) {
+            return true;
+        }
+        self.order.push_back(fingerprint);
+        if self.order.len() > self.capacity
+            && let Some(oldest) = self.order.pop_front()
+        {
+            self.seen.remove(&oldest);
+        }
+        false
+    }
+
+    pub fn clear(&mut self) {
+        self.seen.clear();
+        self.order.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn detects_duplicates() {
+        let mut recent = RecentMessages::new(4);
+        assert!(!recent.check_and_insert("a"));
+        assert!(!recent.check_and_insert("b"));
+        assert!(recent.check_and_insert("a"));
+        assert!(recent.check_and_insert("b"));
+        assert!(!recent.check_and_insert("c"));
+    }
+
+    #[test]
+    fn evicts_oldest_beyond_capacity() {
+        let mut recent = RecentMessages::new(2);
+        recent.check_and_insert("a");
+        recent.check_and_insert("b");
+        recent.check_and_insert("c");
+        // "a" 已被淘汰，再次出现视为新消息；"c" 仍在窗口内
+        assert!(!recent.check_and_insert("a"));
+        assert!(recent.check_and_insert("c"));
+    }
+
+    #[test]
+    fn duplicates_do_not_refresh_position() {
+        let mut recent = RecentMessages::new(2);
+        recent.check_and_insert("a");
+        recent.check_and_insert("b");
+        assert!(recent.check_and_insert("a"));
+        recent.check_and_insert("c");
+        // 重复命中不改变淘汰顺序，"a" 仍是最早的一条
+        assert!(!recent.check_and_insert("a"));
+    }
+
+    #[test]
+    fn zero_capacity_keeps_one_and_clear_resets() {
+        let mut recent = RecentMessages::new(0);
+        recent.check_and_insert("a");
+        assert!(recent.check_and_insert("a"));
+        recent.clear();
+        assert!(!recent.check_and_insert("a"));
+    }
+}
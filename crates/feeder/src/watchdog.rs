@@ -0,0 +1,158 @@
+// feeder/watchdog.rs
+
+use std::time::Duration;
+
+/// 连接静默检测配置：按流设置允许的最长静默时间
+/// 半开的 TCP 连接既不会收到 Close 帧也不会报错，只能靠"多久没收到数据"来判断
+#[derive(Debug, Clone)]
+pub struct WatchdogConfig {
+    /// 是否启用
+    pub enabled: bool,
+    /// 未匹配任何规则的流允许的最长静默时间
+    pub default_max_silence: Duration,
+    /// 按流名片段匹配（不区分大小写）的静默阈值，先匹配先生效，
+    /// 例如 ("@depth", 15s) 表示深度流 15 秒无数据即视为异常
+    pub stream_max_silence: Vec<(String, Duration)>,
+    /// 不参与静默判断的流（按流名片段匹配）：成交、强平等事件驱动的流在行情清淡时可能长时间没有数据。
+    /// stream_max_silence 中的规则优先，为这类流设置阈值即可重新纳入检测
+    pub exempt_streams: Vec<String>,
+}
+
+impl Default for WatchdogConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            default_max_silence: Duration::from_secs(120),
+            stream_max_silence: vec![
+                // 深度、最优挂单、标记价格、K线推送频率固定且很高；
+                // 全市场流（!bookTicker、!markPrice@arr）前面没有 "@"
+                ("@depth".to_string(), Duration::from_secs(15)),
+                ("bookticker".to_string(), Duration::from_secs(15)),
+                ("markprice".to_string(), Duration::from_secs(15)),
+                ("@kline".to_string(), Duration::from_secs(30)),
+            ],
+            // 有成交才推送；强平流可能数小时没有数据
+            exempt_streams: vec!["@aggtrade".to_string(), "@trade".to_string(), "forceorder".to_string()],
+        }
+    }
+}
+
+impl WatchdogConfig {
+    /// 关闭静默检测
+    pub fn disabled() -> Self {
+        Self {
+            enabled: false,
+            ..Self::default()
+        }
+    }
+
+    /// 设置某类流的静默阈值（已存在则覆盖）
+    pub fn set_stream_threshold(&mut self, pattern: &str, max_silence: Duration) {
+        let pattern = pattern.to_lowercase();
+        match self.stream_max_silence.iter_mut().find(|(p, _)| *p == pattern) {
+            Some(rule) => rule.1 = max_silence,
+            None => self.stream_max_silence.insert(0, (pattern, max_silence)),
+        }
+    }
+
+    /// 让某类流不参与静默判断
+    pub fn exempt_stream(&mut self, pattern: &str) {
+        let pattern = pattern.to_lowercase();
+        if !self.exempt_streams.contains(&pattern) {
+            self.exempt_streams.push(pattern);
+        }
+    }
+
+    /// 单个流的静默阈值，不参与静默判断的流返回 None
+    pub fn threshold_for(&self, stream: &str) -> Option<Duration> {
+        let stream = stream.to_lowercase();
+        if let Some((_, max_silence)) = self.stream_max_silence.iter().find(|(pattern, _)| stream.contains(pattern.as_str())) {
+            return Some(*max_silence);
+        }
+        if self.exempt_streams.iter().any(|pattern| stream.contains(pattern.as_str())) {
+            return None;
+        }
+        Some(self.default_max_silence)
+    }
+
+    /// 整个连接的静默阈值：取连接上参与判断的流中最严格的一个；
+    /// 没有流或所有流都不参与判断时返回 None，该连接不做静默检测
+    pub fn connection_threshold<'a, I>(&self, streams: I) -> Option<Duration>
+    where
+        I: IntoIterator<Item = &'a String>,
+    {
+        streams
+            .into_iter()
+            .filter_map(|s| self.threshold_for(s))
+            .min()
+    }
+}
+
+/// 静默报告
+#[derive(Debug, Clone)]
+pub struct StallReport {
+    /// 已静默的时间
+    pub silent_for: Duration,
+    /// 触发的阈值
+    pub threshold: Duration,
+    /// 该连接上订阅的流
+    pub streams: Vec<String>,
+    /// 本连接在静默前收到的消息数
+    pub messages_received: u64,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn streams(names: &[&str]) -> Vec<String> {
+        names.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn matches_stream_rules_case_insensitively() {
+        let config = WatchdogConfig::default();
+        assert_eq!(config.threshold_for("btcusdt@depth@100ms"), Some(Duration::from_secs(15)));
+        assert_eq!(config.threshold_for("btcusdt@bookTicker"), Some(Duration::from_secs(15)));
+        assert_eq!(config.threshold_for("!bookTicker"), Some(Duration::from_secs(15)));
+        assert_eq!(config.threshold_for("!markPrice@arr@1s"), Some(Duration::from_secs(15)));
+        assert_eq!(config.threshold_for("btcusdt@kline_1m"), Some(Duration::from_secs(30)));
+        // 未匹配任何规则的流使用默认阈值
+        assert_eq!(config.threshold_for("btcusdt@ticker"), Some(Duration::from_secs(120)));
+    }
+
+    #[test]
+    fn event_driven_streams_are_exempt() {
+        let config = WatchdogConfig::default();
+        assert_eq!(config.threshold_for("btcusdt@aggTrade"), None);
+        assert_eq!(config.threshold_for("btcusdt@trade"), None);
+        assert_eq!(config.threshold_for("!forceOrder@arr"), None);
+        assert_eq!(config.connection_threshold(&streams(&["btcusdt@aggTrade", "!forceOrder@arr"])), None);
+        assert_eq!(config.connection_threshold(&streams(&[])), None);
+    }
+
+    #[test]
+    fn connection_uses_strictest_stream() {
+        let config = WatchdogConfig::default();
+        let threshold = config.connection_threshold(&streams(&["btcusdt@ticker", "btcusdt@kline_1m", "btcusdt@aggTrade"]));
+        assert_eq!(threshold, Some(Duration::from_secs(30)));
+        let threshold = config.connection_threshold(&streams(&["btcusdt@kline_1m", "ethusdt@depth@100ms"]));
+        assert_eq!(threshold, Some(Duration::from_secs(15)));
+    }
+
+    #[test]
+    fn explicit_threshold_opts_exempt_stream_in() {
+        let mut config = WatchdogConfig::default();
+        config.set_stream_threshold("@aggTrade", Duration::from_secs(60));
+        assert_eq!(config.threshold_for("btcusdt@aggtrade"), Some(Duration::from_secs(60)));
+        // 覆盖已有规则而不是追加
+        config.set_stream_threshold("@depth", Duration::from_secs(5));
+        assert_eq!(config.threshold_for("btcusdt@depth"), Some(Duration::from_secs(5)));
+        assert_eq!(config.stream_max_silence.iter().filter(|(p, _)| p == "@depth").count(), 1);
+
+        config.exempt_stream("@kline");
+        assert_eq!(config.threshold_for("btcusdt@kline_1m"), Some(Duration::from_secs(30)));
+        config.exempt_stream("@ticker");
+        assert_eq!(config.threshold_for("btcusdt@ticker"), None);
+    }
+}
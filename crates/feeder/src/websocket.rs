@@ -1,20 +1,29 @@
 use async_trait::async_trait;
 use futures_util::{SinkExt, StreamExt};
 use serde_json::json;
+use serde_json::Value;
 use std::error::Error;
 use std::time::{Duration, Instant};
 use tokio::net::TcpStream;
-use tokio::time::sleep;
+use tokio::sync::watch;
+use tokio::time::{sleep, timeout};
 use tokio_tungstenite::{connect_async, tungstenite::Message, WebSocketStream};
 use tokio_tungstenite::MaybeTlsStream;
 use std::io;
-use std::cell::RefCell;
-use std::rc::Rc;
 use url::Url;
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet, VecDeque};
 
+use crate::endpoint::WsEndpoint;
+use crate::response::WsResponse;
+use crate::reconnect::{FaultInjector, FaultPoint, InjectedFault, ReconnectPolicy, ReconnectState};
+use crate::rollover::{RecentMessages, RolloverConfig, CONNECTION_LIFETIME};
+use crate::watchdog::{StallReport, WatchdogConfig};
+use crate::lifecycle::{LifecycleEmitter, LifecycleEvent};
 
-#[async_trait(?Send)]
+/// 币安单个连接最多可订阅的流数量
+pub const MAX_STREAMS_PER_CONNECTION: usize = 200;
+
+#[async_trait]
 pub trait WebSocket {
     /// 建立连接：根据传入的订阅流构造 URL 并连接
     async fn connect(&mut self, streams: Vec<&str>) -> Result<(), Box<dyn Error>>;
@@ -24,16 +33,45 @@ pub trait WebSocket {
     async fn read_message(&mut self) -> Result<Message, Box<dyn Error >>;
     /// 监听循环：处理消息、回复 ping、检测断线等
     async fn listen_loop(&mut self) -> Result<(), Box<dyn Error >>;
-    /// 发送订阅消息（单个连接最多200个流，且受限于每秒10条消息）
+    /// 发送订阅消息并等待交易所确认（单个连接最多200个流，且受限于每秒10条消息）
     async fn subscribe(&mut self, streams: Vec<&str>) -> Result<(), Box<dyn Error >>;
+    /// 发送取消订阅消息并等待交易所确认
+    async fn unsubscribe(&mut self, streams: Vec<&str>) -> Result<(), Box<dyn Error >>;
+    /// 查询交易所侧当前连接的订阅列表
+    async fn list_subscriptions(&mut self) -> Result<Vec<String>, Box<dyn Error >>;
 }
 
 // 修改 BinanceWebSocketClient，增加一个 on_message 回调属性
-type MessageCallback = Box<dyn FnMut(String) + 'static>;
+// 回调需要 Send，客户端才能在多线程运行时中跨线程移动
+type MessageCallback = Box<dyn FnMut(String) + Send + 'static>;
+
+type WsStream = WebSocketStream<MaybeTlsStream<TcpStream>>;
+
+/// 连接静默时的回调
+type StallCallback = Box<dyn FnMut(&StallReport) + Send + 'static>;
+
+/// 备用连接建立失败后，再次尝试前的等待时间
+const STANDBY_RETRY_INTERVAL: Duration = Duration::from_secs(30);
+
+/// 等待交易所应答的默认超时
+const DEFAULT_ACK_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// 换连期间与当前连接并行的备用连接
+struct StandbyConnection {
+    stream: WsStream,
+    opened_at: Instant,
+}
+
+/// 已发送、尚未收到应答的请求
+#[derive(Debug, Clone)]
+struct PendingRequest {
+    method: String,
+    params: Vec<String>,
+}
 
 pub struct BinanceWebSocketClient {
     /// 内部保存连接后的 WebSocketStream
-    ws_stream: Option<WebSocketStream<MaybeTlsStream<TcpStream>>>,
+    ws_stream: Option<WsStream>,
     /// 记录连接建立时间，用于判断24小时有效期
     connection_start: Option<Instant>,
 
@@ -42,33 +80,217 @@ pub struct BinanceWebSocketClient {
 
     // /// 记录上次订阅的流列表
     last_subscribed_streams: HashSet<String>,
+
+    /// 端点配置（基础地址、路径风格、TLS）
+    endpoint: WsEndpoint,
+
+    /// 下一个请求 id（单调递增）
+    next_request_id: u64,
+    /// 等待应答的请求，key 为请求 id
+    pending_requests: HashMap<u64, PendingRequest>,
+    /// 等待应答期间收到的行情消息，监听循环启动后优先回调
+    buffered_messages: VecDeque<String>,
+    /// 等待应答的超时时间
+    ack_timeout: Duration,
+
+    /// 最近一次 connect 是否为组合流格式，重连时保持一致的消息格式
+    combined_format: bool,
+    /// 断线重连策略与连续失败次数
+    reconnect: ReconnectState,
+    /// 故障注入钩子（测试用）
+    fault_injector: Option<FaultInjector>,
+    /// 当前连接已收到的文本消息数
+    messages_received: u64,
+
+    /// 先建后断换连配置
+    rollover: RolloverConfig,
+    /// 换连期间的备用连接
+    standby: Option<StandbyConnection>,
+    /// 备用连接建立失败后的下次重试时间
+    standby_retry_at: Option<Instant>,
+    /// 并行期间是否已观察到重复消息（说明新旧连接的数据已重叠）
+    overlap_duplicate_seen: bool,
+    /// 并行期间的去重窗口
+    dedup: RecentMessages,
+    /// 切换完成后继续去重的截止时间
+    dedup_until: Option<Instant>,
+
+    /// 静默检测配置
+    watchdog: WatchdogConfig,
+    /// 静默回调
+    on_stall_callback: Option<StallCallback>,
+    /// 最近一次收到行情数据的时间
+    last_data_at: Option<Instant>,
+
+    /// 连接编号与生命周期回调
+    lifecycle: LifecycleEmitter,
 }
 
 impl BinanceWebSocketClient {
+    /// 使用默认端点（U 本位合约正式环境）
     pub fn new() -> Self {
+        Self::with_endpoint(WsEndpoint::default())
+    }
+
+    /// 使用指定端点（现货、测试网、本地 mock 等）
+    pub fn with_endpoint(endpoint: WsEndpoint) -> Self {
         Self {
             ws_stream: None,
             connection_start: None,
             on_message_callback: None,
             last_subscribed_streams: HashSet::new(),
+            endpoint,
+            next_request_id: 1,
+            pending_requests: HashMap::new(),
+            buffered_messages: VecDeque::new(),
+            ack_timeout: DEFAULT_ACK_TIMEOUT,
+            combined_format: false,
+            reconnect: ReconnectState::default(),
+            fault_injector: None,
+            messages_received: 0,
+            rollover: RolloverConfig::default(),
+            standby: None,
+            standby_retry_at: None,
+            overlap_duplicate_seen: false,
+            dedup: RecentMessages::new(RolloverConfig::default().dedup_capacity),
+            dedup_until: None,
+            watchdog: WatchdogConfig::default(),
+            on_stall_callback: None,
+            last_data_at: None,
+            lifecycle: LifecycleEmitter::default(),
         }
     }
 
-    /// 根据订阅流列表构造连接 URL
-    /// 单一流：wss://fstream.binance.com/ws/<streamName>
-    /// 多流：wss://fstream.binance.com/stream?streams=/<stream1>/<stream2>/...
-    fn build_url(&self, streams: &[&str]) -> String {
-        let base = "wss://fstream.binance.com";
-        if streams.len() == 1 {
-            format!("{}/ws/{}", base, streams[0].to_lowercase())
+    /// 设置连接编号
+    pub fn set_connection_id(&mut self, id: u64) {
+        self.lifecycle.set_connection_id(id);
+    }
+
+    /// 连接编号
+    pub fn connection_id(&self) -> u64 {
+        self.lifecycle.connection_id()
+    }
+
+    /// 设置生命周期回调（连接建立、断开、重连后恢复订阅、静默）
+    pub fn set_lifecycle_callback<F>(&mut self, callback: F)
+    where
+        F: FnMut(u64, LifecycleEvent) + Send + 'static,
+    {
+        self.lifecycle.set_callback(callback);
+    }
+
+    /// 丢弃当前连接；若之前处于连接状态则上报 Disconnected
+    fn mark_disconnected(&mut self, reason: &str) {
+        self.lifecycle.disconnect(&mut self.ws_stream, reason, &self.last_subscribed_streams);
+    }
+
+    fn sorted_streams(&self) -> Vec<String> {
+        let mut streams: Vec<String> = self.last_subscribed_streams.iter().cloned().collect();
+        streams.sort();
+        streams
+    }
+
+    /// 设置静默检测配置
+    pub fn set_watchdog_config(&mut self, config: WatchdogConfig) {
+        self.watchdog = config;
+    }
+
+    /// 设置连接静默时的回调（静默后会强制重连）
+    pub fn set_stall_callback<F>(&mut self, callback: F)
+    where
+        F: FnMut(&StallReport) + Send + 'static,
+    {
+        self.on_stall_callback = Some(Box::new(callback));
+    }
+
+    /// 上报静默并丢弃当前连接（包括备用连接），由监听循环按重连策略重连
+    fn report_stall(&mut self, threshold: Duration) {
+        let report = StallReport {
+            silent_for: self.last_data_at.map(|t| t.elapsed()).unwrap_or_default(),
+            threshold,
+            streams: self.sorted_streams(),
+            messages_received: self.messages_received,
+        };
+        eprintln!(
+            "[静默检测] 已 {:?} 未收到数据（阈值 {:?}），强制重连",
+            report.silent_for, report.threshold
+        );
+        if let Some(ref mut callback) = self.on_stall_callback {
+            callback(&report);
+        }
+        self.lifecycle.emit(LifecycleEvent::Stale(report));
+        self.mark_disconnected("连接静默超时");
+        self.standby = None;
+    }
+
+    /// 设置先建后断换连配置
+    pub fn set_rollover_config(&mut self, config: RolloverConfig) {
+        self.dedup = RecentMessages::new(config.dedup_capacity);
+        self.rollover = config;
+    }
+
+    /// 设置断线重连策略
+    pub fn set_reconnect_policy(&mut self, policy: ReconnectPolicy) {
+        self.reconnect.policy = policy;
+    }
+
+    /// 设置故障注入钩子，用于测试断线与重连逻辑
+    pub fn set_fault_injector<F>(&mut self, injector: F)
+    where
+        F: FnMut(FaultPoint) -> Option<InjectedFault> + Send + 'static,
+    {
+        self.fault_injector = Some(Box::new(injector));
+    }
+
+    fn inject(&mut self, point: FaultPoint) -> Option<InjectedFault> {
+        self.fault_injector.as_mut().and_then(|injector| injector(point))
+    }
+
+    /// 按记录的订阅集合重建连接：
+    /// 组合流格式直接在 URL 中携带全部流；原始流格式只携带一个流，其余通过 SUBSCRIBE 恢复，
+    /// 以保证重连前后的消息格式一致
+    async fn reconnect_recorded(&mut self) -> Result<(), Box<dyn Error >> {
+        let mut streams: Vec<String> = self
+            .last_subscribed_streams
+            .iter()
+            .cloned()
+            .collect(); // 完全拷贝出 Vec<String>
+        if streams.is_empty() {
+            return Err("无任何已记录的订阅流，无法重连".into());
+        }
+        streams.sort();
+
+        if self.combined_format {
+            let streams_ref: Vec<&str> = streams.iter().map(|s| s.as_str()).collect();
+            self.connect(streams_ref).await?;
         } else {
-            let combined = streams
-                .iter()
-                .map(|s| s.to_lowercase())
-                .collect::<Vec<String>>()
-                .join("/");
-            format!("{}/stream?streams=/{}", base, combined)
+            self.connect(vec![streams[0].as_str()]).await?;
+            let rest: Vec<&str> = streams[1..].iter().map(|s| s.as_str()).collect();
+            if !rest.is_empty() {
+                self.subscribe(rest).await?;
+            }
         }
+        Ok(())
+    }
+
+    /// 设置等待交易所应答的超时时间
+    pub fn set_ack_timeout(&mut self, ack_timeout: Duration) {
+        self.ack_timeout = ack_timeout;
+    }
+
+    /// 修改端点配置，下次 connect 时生效
+    pub fn set_endpoint(&mut self, endpoint: WsEndpoint) {
+        self.endpoint = endpoint;
+    }
+
+    /// 当前端点配置
+    pub fn endpoint(&self) -> &WsEndpoint {
+        &self.endpoint
+    }
+
+    /// 根据订阅流列表构造连接 URL，具体格式由端点的路径风格决定
+    fn build_url(&self, streams: &[&str]) -> String {
+        self.endpoint.build_url(streams)
     }
 
     /// 回复 ping 帧，发送 pong 帧（允许发送不成对的pong帧）
@@ -88,209 +310,531 @@ impl BinanceWebSocketClient {
     /// 设置消息回调
     pub fn set_message_callback<F>(&mut self, callback: F)
     where
-        F: FnMut(String) + 'static,
+        F: FnMut(String) + Send + 'static,
     {
         self.on_message_callback = Some(Box::new(callback));
     }
-}
 
-#[async_trait(?Send)]
-impl WebSocket for BinanceWebSocketClient {
-    async fn connect(&mut self, streams: Vec<&str>) -> Result<(), Box<dyn Error >> {
-        // 检查订阅流数量
-        if streams.len() > 200 {
-            return Err(Box::new(std::io::Error::new(std::io::ErrorKind::Other, "单个连接最多订阅 200 个 Streams")))
-        }
-        let url_str = self.build_url(&streams);
+    /// 当前已订阅的流
+    pub fn subscribed_streams(&self) -> &HashSet<String> {
+        &self.last_subscribed_streams
+    }
+
+    /// 是否已建立连接
+    pub fn is_connected(&self) -> bool {
+        self.ws_stream.is_some()
+    }
+
+    /// 发送请求，返回本次请求分配的 id
+    /// 币安请求格式：{ "method": "SUBSCRIBE", "params": ["stream1", "stream2"], "id": 1 }
+    async fn send_request(&mut self, method: &str, params: Vec<String>) -> Result<u64, Box<dyn Error >> {
+        let id = self.next_request_id;
+        self.next_request_id += 1;
+
+        let msg_text = Self::build_request(method, &params, id);
+        // 简单速率控制：每条消息间隔至少100ms，确保不超过每秒10条
+        sleep(Duration::from_millis(100)).await;
+        self.send(&msg_text).await?;
+        self.pending_requests.insert(id, PendingRequest { method: method.to_string(), params });
+
+        println!("已发送请求: {}", msg_text);
+        Ok(id)
+    }
+
+    fn build_request(method: &str, params: &[String], id: u64) -> String {
+        let request = if params.is_empty() {
+            json!({ "method": method, "id": id })
+        } else {
+            json!({ "method": method, "params": params, "id": id })
+        };
+        request.to_string()
+    }
+
+    /// 建立一条新的底层连接（不修改当前连接状态）
+    /// 不借用 self，使 connect 等 future 在客户端非 Sync 时仍然是 Send
+    async fn open_stream(url_str: String) -> Result<WsStream, Box<dyn Error >> {
         println!("尝试连接: {}", url_str);
         let url = Url::parse(&url_str)
                     .map_err(|e| Box::<dyn std::error::Error >::from(Box::new(e)))?;
 
         let (ws_stream, _) = connect_async(url).await
                     .map_err(|e| Box::<dyn std::error::Error >::from(Box::new(e)))?;
-        self.ws_stream = Some(ws_stream);
-        self.connection_start = Some(Instant::now());
-        println!("连接成功");
-        Ok(())
+        Ok(ws_stream)
     }
 
-    async fn send(&mut self, msg: &str) -> Result<(), Box<dyn Error >> {
-        if let Some(ref mut ws) = self.ws_stream {
-            ws.send(Message::Text(msg.to_string())).await
+    /// 是否到了建立备用连接的时间（到期前 lead 时间）
+    fn rollover_due(&self) -> bool {
+        self.ws_stream.is_some()
+            && self.standby.is_none()
+            && self
+                .connection_start
+                .map(|t| t.elapsed() + self.rollover.lead >= CONNECTION_LIFETIME)
+                .unwrap_or(false)
+            && self.standby_retry_at.map(|t| Instant::now() >= t).unwrap_or(true)
+    }
+
+    /// 建立备用连接并订阅相同的流，消息格式与当前连接保持一致
+    async fn open_standby(&mut self) -> Result<(), Box<dyn Error >> {
+        let mut streams: Vec<String> = self.last_subscribed_streams.iter().cloned().collect();
+        if streams.is_empty() {
+            return Ok(());
+        }
+        streams.sort();
+        let split = if self.combined_format { streams.len() } else { 1 };
+        let url_streams: Vec<&str> = streams[..split].iter().map(|s| s.as_str()).collect();
+        let mut standby = Self::open_stream(self.build_url(&url_streams)).await?;
+
+        let rest: Vec<String> = streams[split..].to_vec();
+        if !rest.is_empty() {
+            let id = self.next_request_id;
+            self.next_request_id += 1;
+            let msg_text = Self::build_request("SUBSCRIBE", &rest, id);
+            standby.send(Message::Text(msg_text)).await
                     .map_err(|e| Box::<dyn std::error::Error >::from(Box::new(e)))?;
-            Ok(())
-        } else {
-            Err(Box::new(io::Error::new(io::ErrorKind::Other, "WebSocket 未连接")))
+            self.pending_requests.insert(id, PendingRequest { method: "SUBSCRIBE".to_string(), params: rest });
         }
+
+        self.standby = Some(StandbyConnection { stream: standby, opened_at: Instant::now() });
+        self.overlap_duplicate_seen = false;
+        self.dedup.clear();
+        println!("[换连] 备用连接已建立，新旧连接并行接收");
+        Ok(())
     }
 
-    async fn read_message(&mut self) -> Result<Message, Box<dyn Error >> {
-        if let Some(ref mut ws) = self.ws_stream {
-            if let Some(msg) = ws.next().await {
-                let m = msg.map_err(|e| Box::new(e) as Box<dyn std::error::Error >)?;
-                Ok(m)
-            } else {
-                Err(Box::new(std::io::Error::new(std::io::ErrorKind::Other, "WebSocket 已关闭")))
+    /// 将备用连接提升为当前连接，返回被替换的旧连接
+    fn promote_standby(&mut self) -> Option<WsStream> {
+        let standby = self.standby.take()?;
+        let old = self.ws_stream.replace(standby.stream);
+        self.connection_start = Some(standby.opened_at);
+        self.last_data_at = Some(Instant::now());
+        self.standby_retry_at = None;
+        self.messages_received = 0;
+        self.dedup_until = Some(Instant::now() + self.rollover.dedup_grace);
+        old
+    }
+
+    /// 并行足够久且数据已重叠（或超过最长并行时间）时，切换到备用连接并关闭旧连接
+    async fn maybe_complete_rollover(&mut self) {
+        let ready = match self.standby {
+            Some(ref standby) => {
+                let elapsed = standby.opened_at.elapsed();
+                (self.overlap_duplicate_seen && elapsed >= self.rollover.min_overlap)
+                    || elapsed >= self.rollover.max_overlap
             }
-        } else {
-            Err(Box::new(io::Error::new(io::ErrorKind::Other, "WebSocket 未连接")))
+            None => false,
+        };
+        if ready {
+            if let Some(mut old) = self.promote_standby() {
+                let _ = old.close(None).await;
+            }
+            println!("[换连] 已切换到新连接并关闭旧连接");
         }
     }
 
-    async fn listen_loop(&mut self) -> Result<(), Box<dyn Error >> {
-        // 外层循环用于断线重连与定时重连（24小时断线重连）
-        let mut count = 0;
-        // let mut first_text_received = false;
+    /// 是否需要对消息去重（并行期间及切换后的宽限期内）
+    fn dedup_active(&mut self) -> bool {
+        if self.standby.is_some() {
+            return true;
+        }
+        match self.dedup_until {
+            Some(t) if Instant::now() < t => true,
+            Some(_) => {
+                self.dedup_until = None;
+                self.dedup.clear();
+                false
+            }
+            None => false,
+        }
+    }
 
+    /// 读取一条消息；换连期间同时读取新旧两条连接，返回值中的 bool 表示是否来自备用连接
+    async fn read_either(&mut self) -> (bool, Result<Message, Box<dyn Error >>) {
+        if self.standby.is_none() || self.ws_stream.is_none() {
+            return (false, self.read_message().await);
+        }
+        let primary = self.ws_stream.as_mut().unwrap();
+        let standby = &mut self.standby.as_mut().unwrap().stream;
+        tokio::select! {
+            msg = primary.next() => (false, Self::map_read(msg)),
+            msg = standby.next() => (true, Self::map_read(msg)),
+        }
+    }
+
+    /// 带静默检测的读取：超过阈值仍未收到任何行情数据则视为半开连接，上报后返回 None
+    async fn read_with_watchdog(&mut self) -> Option<(bool, Result<Message, Box<dyn Error >>)> {
+        if !self.watchdog.enabled {
+            return Some(self.read_either().await);
+        }
+        // 连接上只有成交、强平等事件驱动的流时不做静默检测
+        let Some(threshold) = self.watchdog.connection_threshold(self.last_subscribed_streams.iter()) else {
+            return Some(self.read_either().await);
+        };
+        let silent = self.last_data_at.map(|t| t.elapsed()).unwrap_or_default();
+        match timeout(threshold.saturating_sub(silent), self.read_either()).await {
+            Ok(result) => Some(result),
+            Err(_) => {
+                self.report_stall(threshold);
+                None
+            }
+        }
+    }
+
+    fn map_read(msg: Option<Result<Message, tokio_tungstenite::tungstenite::Error>>) -> Result<Message, Box<dyn Error >> {
+        match msg {
+            Some(m) => m.map_err(|e| Box::new(e) as Box<dyn std::error::Error >),
+            None => Err(Box::new(std::io::Error::new(std::io::ErrorKind::Other, "WebSocket 已关闭"))),
+        }
+    }
+
+    /// 等待指定 id 的应答；期间收到的行情消息先缓存，其他请求的应答照常处理
+    async fn await_response(&mut self, id: u64) -> Result<Value, Box<dyn Error >> {
+        let deadline = Instant::now() + self.ack_timeout;
         loop {
-            // ✅ 手动测试用：运行后立即断开一次连接以触发重连逻辑
-            if self.ws_stream.is_some() && count == 0 {
-                count += 1;
-                println!("[测试] 触发手动关闭连接");
-                self.ws_stream
-                    .as_mut()
-                    .unwrap()
-                    .send(Message::Close(None))
-                    .await?;
-                // 确保 break 掉当前循环迭代（模拟服务端断连）
-                continue;
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            let message = match timeout(remaining, self.read_message()).await {
+                Ok(result) => result?,
+                Err(_) => {
+                    // 保留 pending 记录，迟到的应答仍会在监听循环中生效
+                    return Err(Box::new(io::Error::new(
+                        io::ErrorKind::TimedOut,
+                        format!("等待请求 {} 的应答超时（{:?}）", id, self.ack_timeout),
+                    )));
+                }
+            };
+            match message {
+                Message::Text(text) => match WsResponse::parse(&text) {
+                    Some(response) => {
+                        let response_id = response.id();
+                        let outcome = self.apply_response(response);
+                        if response_id == id {
+                            return outcome;
+                        }
+                    }
+                    None => {
+                        self.last_data_at = Some(Instant::now());
+                        self.buffered_messages.push_back(text);
+                    }
+                },
+                Message::Ping(data) => self.send_pong(data).await?,
+                Message::Close(frame) => {
+                    self.mark_disconnected("等待应答时收到关闭帧");
+                    return Err(Box::new(io::Error::new(
+                        io::ErrorKind::Other,
+                        format!("等待请求 {} 的应答时连接关闭: {:?}", id, frame),
+                    )));
+                }
+                _ => {}
             }
+        }
+    }
 
-            let needs_reconnect = self.ws_stream.is_none()
-                || self.connection_start.map(|t| t.elapsed() > Duration::from_secs(24 * 3600)).unwrap_or(true);
+    /// 处理应答：只有交易所确认成功后才更新已订阅集合
+    fn apply_response(&mut self, response: WsResponse) -> Result<Value, Box<dyn Error >> {
+        match response {
+            WsResponse::Ok { id, result } => {
+                match self.pending_requests.remove(&id) {
+                    Some(request) => match request.method.as_str() {
+                        "SUBSCRIBE" => {
+                            for s in request.params {
+                                self.last_subscribed_streams.insert(s);
+                            }
+                        }
+                        "UNSUBSCRIBE" => {
+                            for s in &request.params {
+                                self.last_subscribed_streams.remove(s);
+                            }
+                        }
+                        "LIST_SUBSCRIPTIONS" => {
+                            if let Some(list) = result.as_array() {
+                                self.sync_subscriptions(list);
+                            }
+                        }
+                        _ => {}
+                    },
+                    None => println!("收到未知请求 {} 的应答: {}", id, result),
+                }
+                Ok(result)
+            }
+            WsResponse::Err(e) => {
+                self.pending_requests.remove(&e.id);
+                eprintln!("{}", e);
+                Err(Box::new(e))
+            }
+        }
+    }
 
-            if needs_reconnect {
-                println!("连接不存在或已过期，准备重连...");
+    /// 以交易所返回的订阅列表为准更新本地记录（大小写不敏感，保留本地原始写法）
+    fn sync_subscriptions(&mut self, list: &[Value]) {
+        let confirmed: Vec<&str> = list.iter().filter_map(|v| v.as_str()).collect();
+        let mut synced = HashSet::new();
+        for name in confirmed {
+            let local = self
+                .last_subscribed_streams
+                .iter()
+                .find(|s| s.eq_ignore_ascii_case(name))
+                .cloned();
+            synced.insert(local.unwrap_or_else(|| name.to_string()));
+        }
+        self.last_subscribed_streams = synced;
+    }
+
+    /// 监听循环，pause 变为 true 时在下一次读取前返回 Ok。
+    /// 只在两次读取之间检查，进行中的重连、应答等待与换连不会被打断，连接池借此安全地调整连接
+    pub async fn listen_until(&mut self, mut pause: watch::Receiver<bool>) -> Result<(), Box<dyn Error >> {
+        // 外层循环用于断线重连与定时重连（24小时断线重连）
+        // 正常情况下在到期前通过备用连接先建后断完成换连，到期重连只作为兜底
+        // let mut first_text_received = false;
 
-                let streams: Vec<String> = self
-                    .last_subscribed_streams
-                    .iter()
-                    .cloned()
-                    .collect(); // 完全拷贝出 Vec<String>
+        loop {
+            let mut expired = self
+                .connection_start
+                .map(|t| t.elapsed() > CONNECTION_LIFETIME)
+                .unwrap_or(true);
+
+            // 当前连接已断开或到期但备用连接可用：直接切换，避免数据缺口
+            if (self.ws_stream.is_none() || expired) && self.standby.is_some() {
+                if let Some(mut old) = self.promote_standby() {
+                    let _ = old.close(None).await;
+                }
+                expired = false;
+                println!("[换连] 当前连接不可用，已切换到备用连接");
+            }
 
-                if streams.is_empty() {
+            if self.ws_stream.is_none() || expired {
+                println!("连接不存在或已过期，准备重连...");
+                if self.last_subscribed_streams.is_empty() {
                     return Err("无任何已记录的订阅流，无法重连".into());
                 }
+                let attempt = self.reconnect.next_attempt()?;
 
-                // 使用默认激活流启动连接
-                if let Err(e) = self.connect(vec!["btcusdt@aggTrade"]).await {
-                    eprintln!("重连失败: {}", e);
-                    sleep(Duration::from_secs(3)).await;
-                    continue;
+                // 意外断线时按策略退避；到期主动重连不等待
+                if self.ws_stream.is_none() {
+                    self.reconnect.backoff("", attempt).await;
                 }
-                
-                let streams_ref: Vec<&str> = streams.iter().map(|s| s.as_str()).collect();
-                // 直接再次订阅，不清除已记录流
-                if let Err(e) = self.subscribe(streams_ref).await {
-                    eprintln!("重订阅失败: {}", e);
-                    sleep(Duration::from_secs(3)).await;
+                self.mark_disconnected("连接到期");
+
+                let result = match self.inject(FaultPoint::BeforeReconnect { attempt }) {
+                    Some(InjectedFault::FailReconnect) => Err("[故障注入] 重连失败".into()),
+                    _ => self.reconnect_recorded().await,
+                };
+                if let Err(e) = result {
+                    eprintln!("重连失败: {}", e);
+                    self.ws_stream = None;
+                    self.reconnect.failed(attempt);
                     continue;
                 }
 
+                self.reconnect.succeeded();
                 println!("重连并重新订阅成功");
+                let streams = self.sorted_streams();
+                self.lifecycle.emit(LifecycleEvent::Resubscribed { streams });
             }
-            // 内层循环读取消息
-            // println!("开始监听消息");
-            match self.read_message().await {
-                Ok(message) => {
-                    match message {
-                        Message::Text(text) => {
-                            // 如果是组合 streams，payload 格式为 {"stream": "...", "data": ...}
-                            // println!("收到文本消息: {}", text);
-                            // 此处可根据业务解析并分发到 on_depth / on_trade 等回调
-                            // ✅ 首次输出文本消息
-                            // if !first_text_received {
-                            //     println!("[Websocket] 首次收到文本消息: {}", text);
-                            //     first_text_received = true;
-                            // }
-                            if let Some(ref mut callback) = self.on_message_callback {
-                                callback(text);
-                            }
-                        }
-                        Message::Ping(data) => {
-                            println!("收到 ping, 回复 pong");
-                            self.send_pong(data)
-                                .await
-                                .map_err(|e| Box::<dyn std::error::Error>::from(e))?;
 
+            // 先建后断：到期前建立备用连接，并行一段时间后再关闭旧连接
+            self.maybe_complete_rollover().await;
+            if self.rollover_due() {
+                if let Err(e) = self.open_standby().await {
+                    eprintln!("[换连] 备用连接建立失败: {}，{:?} 后重试", e, STANDBY_RETRY_INTERVAL);
+                    self.standby_retry_at = Some(Instant::now() + STANDBY_RETRY_INTERVAL);
+                }
+            }
 
+            // 先回调等待应答期间缓存的行情消息
+            while let Some(text) = self.buffered_messages.pop_front() {
+                if let Some(ref mut callback) = self.on_message_callback {
+                    callback(text);
+                }
+            }
+
+            // 故障注入：模拟服务端断开或连接丢失
+            match self.inject(FaultPoint::BeforeRead { messages_received: self.messages_received }) {
+                Some(InjectedFault::Close) => {
+                    println!("[故障注入] 发送关闭帧");
+                    if let Some(ref mut ws) = self.ws_stream {
+                        let _ = ws.send(Message::Close(None)).await;
+                    }
+                }
+                Some(InjectedFault::Drop) => {
+                    println!("[故障注入] 丢弃连接");
+                    self.mark_disconnected("[故障注入] 丢弃连接");
+                    continue;
+                }
+                _ => {}
+            }
+
+            // 内层循环读取消息
+            // println!("开始监听消息");
+            // 先处理读错误并取出消息，错误值不能跨越后续的 await（Box<dyn Error> 不是 Send）
+            // 暂停只在这里检查：读取可以安全地取消，其他 await 不会被打断
+            let (from_standby, message) = match tokio::select! {
+                read = self.read_with_watchdog() => read,
+                Ok(()) = pause.changed() => return Ok(()),
+            } {
+                None => continue,
+                Some((from_standby, Ok(message))) => (from_standby, message),
+                Some((from_standby, Err(e))) => {
+                    if from_standby {
+                        eprintln!("[换连] 备用连接读取错误: {}，{:?} 后重试", e, STANDBY_RETRY_INTERVAL);
+                        self.standby = None;
+                        self.standby_retry_at = Some(Instant::now() + STANDBY_RETRY_INTERVAL);
+                        continue;
+                    }
+                    eprintln!("读取消息错误: {}，准备重连", e);
+                    self.mark_disconnected(&format!("读取消息错误: {}", e));
+                    continue;
+                }
+            };
+            match message {
+                Message::Text(text) => {
+                    // 如果是组合 streams，payload 格式为 {"stream": "...", "data": ...}
+                    // 请求应答（{"result":null,"id":1}）不属于行情数据，不进入回调
+                    if let Some(response) = WsResponse::parse(&text) {
+                        let _ = self.apply_response(response);
+                        continue;
+                    }
+                    self.last_data_at = Some(Instant::now());
+                    // 换连期间新旧连接会收到相同的消息，只回调一次
+                    if self.dedup_active() && self.dedup.check_and_insert(&text) {
+                        if self.standby.is_some() {
+                            self.overlap_duplicate_seen = true;
                         }
-                        Message::Pong(_) => {
-                            println!("收到 pong");
-                        }
-                        Message::Binary(bin) => {
-                            println!("收到二进制消息: {:?}", bin);
-                        }
-                        Message::Close(frame) => {
-                            println!("收到关闭消息: {:?}", frame);
-                            self.ws_stream = None;
-                            sleep(Duration::from_secs(3)).await;
-                            continue;
-                        }
-                        _ => {
-                            // println!("收到default消息: {:?}", message);
+                        continue;
+                    }
+                    self.messages_received += 1;
+                    if let Some(ref mut callback) = self.on_message_callback {
+                        callback(text);
+                    }
+                }
+                Message::Ping(data) => {
+                    println!("收到 ping, 回复 pong");
+                    if from_standby {
+                        // 备用连接回复失败只放弃备用连接，不影响主连接
+                        let failed = match self.standby {
+                            Some(ref mut standby) => standby.stream.send(Message::Pong(data)).await.err(),
+                            None => None,
+                        };
+                        if let Some(e) = failed {
+                            eprintln!("备用连接回复 pong 失败: {}，放弃备用连接", e);
+                            self.standby = None;
+                            self.standby_retry_at = Some(Instant::now() + STANDBY_RETRY_INTERVAL);
                         }
+                    } else {
+                        self.send_pong(data)
+                            .await
+                            .map_err(|e| Box::<dyn std::error::Error>::from(e))?;
+                    }
+                }
+                Message::Pong(_) => {
+                    println!("收到 pong");
+                }
+                Message::Binary(bin) => {
+                    println!("收到二进制消息: {:?}", bin);
+                }
+                Message::Close(frame) => {
+                    println!("收到关闭消息: {:?}", frame);
+                    if from_standby {
+                        self.standby = None;
+                        self.standby_retry_at = Some(Instant::now() + STANDBY_RETRY_INTERVAL);
+                    } else {
+                        self.mark_disconnected(&format!("收到关闭帧: {:?}", frame));
                     }
-                        
-                },
-            Err(e) => {
-                    eprintln!("读取消息错误: {}，准备重连", e);
-                    self.ws_stream = None;
-                    sleep(Duration::from_secs(3)).await;
                     continue;
-                } 
+                }
+                _ => {
+                    // println!("收到default消息: {:?}", message);
+                }
             }
         }
-        // 断线重连前等待3秒，防止频繁重连
-        // sleep(Duration::from_secs(3)).await;
-        // Ok(())
     }
+}
 
-    async fn subscribe(&mut self, streams: Vec<&str>) -> Result<(), Box<dyn Error >> {
+#[async_trait]
+impl WebSocket for BinanceWebSocketClient {
+    async fn connect(&mut self, streams: Vec<&str>) -> Result<(), Box<dyn Error >> {
         // 检查订阅流数量
-        // if streams.len() > 200 {
-        //     return Err(Box::new(std::io::Error::new(std::io::ErrorKind::Other, "单个连接最多订阅 200 个 Streams")))
-        // }
+        if streams.len() > MAX_STREAMS_PER_CONNECTION {
+            return Err(Box::new(std::io::Error::new(std::io::ErrorKind::Other, "单个连接最多订阅 200 个 Streams")))
+        }
+        let ws_stream = Self::open_stream(self.build_url(&streams)).await?;
+        self.ws_stream = Some(ws_stream);
+        self.connection_start = Some(Instant::now());
+        self.combined_format = self.endpoint.is_combined(streams.len());
+        self.messages_received = 0;
+        self.last_data_at = Some(Instant::now());
+        // 旧连接上的请求不会再有应答
+        self.pending_requests.clear();
+        // URL 中携带的流在连接建立后即已订阅，一并记录，便于重连与分片统计
+        for s in &streams {
+            self.last_subscribed_streams.insert(s.to_string());
+        }
+        println!("连接成功");
+        let streams = streams.iter().map(|s| s.to_string()).collect();
+        self.lifecycle.emit(LifecycleEvent::Connected { streams });
+        Ok(())
+    }
 
-        // 检查已订阅数量是否已超过200
-        if self.last_subscribed_streams.len() + streams.len() > 200 {
-            return Err(Box::new(std::io::Error::new(io::ErrorKind::Other, "已订阅流数量超过200个")))
+    async fn send(&mut self, msg: &str) -> Result<(), Box<dyn Error >> {
+        if let Some(ref mut ws) = self.ws_stream {
+            ws.send(Message::Text(msg.to_string())).await
+                    .map_err(|e| Box::<dyn std::error::Error >::from(Box::new(e)))?;
+            Ok(())
+        } else {
+            Err(Box::new(io::Error::new(io::ErrorKind::Other, "WebSocket 未连接")))
         }
+    }
 
-        // let new_streams: Vec<&str> = streams
-        //     .iter()
-        //     .filter(|s| !self.last_subscribed_streams.contains(&s.to_string()))
-        //     .cloned()
-        //     .collect();
-
-        // if new_streams.is_empty() {
-        //     println!("所有流都已订阅，跳过发送");
-        //     return Ok(());
-        // }
-        // Binance 订阅消息格式示例：
-        // { "method": "SUBSCRIBE", "params": ["stream1", "stream2"], "id": 1 }
-        // let streams_lower: Vec<String> = streams.iter().map(|s| s.collect();
-        let subscribe_msg = json!({
-            "method": "SUBSCRIBE",
-            "params": streams,
-            "id": 1
-        });
-        let msg_text = subscribe_msg.to_string();
-        // 简单速率控制：每条消息间隔至少100ms，确保不超过每秒10条
-        sleep(Duration::from_millis(100)).await;
-        self.send(&msg_text).await?;
+    async fn read_message(&mut self) -> Result<Message, Box<dyn Error >> {
+        if let Some(ref mut ws) = self.ws_stream {
+            if let Some(msg) = ws.next().await {
+                let m = msg.map_err(|e| Box::new(e) as Box<dyn std::error::Error >)?;
+                Ok(m)
+            } else {
+                Err(Box::new(std::io::Error::new(std::io::ErrorKind::Other, "WebSocket 已关闭")))
+            }
+        } else {
+            Err(Box::new(io::Error::new(io::ErrorKind::Other, "WebSocket 未连接")))
+        }
+    }
 
+    async fn listen_loop(&mut self) -> Result<(), Box<dyn Error >> {
+        // 发送端在监听期间一直保留，监听循环不会被暂停
+        let (_pause_tx, pause) = watch::channel(false);
+        self.listen_until(pause).await
+    }
 
-        // 添加到已订阅集合
-        for s in streams {
-            self.last_subscribed_streams.insert(s.to_string());
+    async fn subscribe(&mut self, streams: Vec<&str>) -> Result<(), Box<dyn Error >> {
+        // 检查已订阅数量是否已超过200（已订阅过的流不重复计数）
+        let new_count = streams
+            .iter()
+            .filter(|s| !self.last_subscribed_streams.contains(**s))
+            .count();
+        if self.last_subscribed_streams.len() + new_count > MAX_STREAMS_PER_CONNECTION {
+            return Err(Box::new(std::io::Error::new(io::ErrorKind::Other, "已订阅流数量超过200个")))
         }
 
-
-        println!("已发送订阅消息: {}", msg_text);
+        let params: Vec<String> = streams.iter().map(|s| s.to_string()).collect();
+        let id = self.send_request("SUBSCRIBE", params).await?;
+        // 等待交易所确认后才记录到已订阅集合
+        self.await_response(id).await?;
+        println!("订阅已确认: {:?}", streams);
         Ok(())
     }
 
+    async fn unsubscribe(&mut self, streams: Vec<&str>) -> Result<(), Box<dyn Error >> {
+        let params: Vec<String> = streams.iter().map(|s| s.to_string()).collect();
+        let id = self.send_request("UNSUBSCRIBE", params).await?;
+        self.await_response(id).await?;
+        println!("取消订阅已确认: {:?}", streams);
+        Ok(())
+    }
 
-
+    async fn list_subscriptions(&mut self) -> Result<Vec<String>, Box<dyn Error >> {
+        let id = self.send_request("LIST_SUBSCRIPTIONS", Vec::new()).await?;
+        let result = self.await_response(id).await?;
+        let list = result
+            .as_array()
+            .map(|arr| arr.iter().filter_map(|v| v.as_str().map(|s| s.to_string())).collect())
+            .unwrap_or_default();
+        Ok(list)
+    }
 }
@@ -0,0 +1,256 @@
+// market_agent/agg_trade_gap.rs
+
+// 归集成交 id 连续性检查与 REST 补录。
+// 同一交易对的归集成交 id 严格递增且连续，重连、换连或服务端丢包都会留下缺口；
+// 发现缺口后先缓存该交易对的实时成交，补录完成后按 id 顺序与缓存一起分发；
+// 补录请求由 rest_client 完成
+
+use std::collections::HashMap;
+use std::time::Duration;
+
+use event_engine::event::{AggTradeEvent, AggTradeGapEvent};
+
+/// 默认最多补录的成交数，缺口更大时放弃补录（长时间断线后补录意义不大且会触发限频）
+pub const DEFAULT_MAX_BACKFILL: u64 = 10_000;
+
+/// 补录期间每个交易对最多缓存的实时成交数，超出后放弃补录，避免 REST 迟迟不返回时内存无限增长
+pub const DEFAULT_MAX_BUFFERED: usize = 50_000;
+
+/// 单次补录的超时时间，超时视为补录失败，缓存的实时成交照常分发
+pub const DEFAULT_BACKFILL_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// push 的处理结果
+pub enum Sequenced {
+    /// id 连续（或该交易对的第一条），可以直接分发
+    InOrder(AggTradeEvent),
+    /// 已分发过（重连或换连时的重复推送），丢弃
+    Duplicate,
+    /// 该交易对正在补录，已缓存
+    Buffered,
+    /// 已缓存，但缓存达到上限，应放弃 gap 的补录并分发缓存
+    BufferFull(AggTradeGapEvent),
+    /// 发现缺口，当前成交已缓存，需要补录 gap 中的 id 区间
+    Gap(AggTradeGapEvent),
+    /// 缺口超过补录上限，放弃补录，当前成交直接分发
+    GapTooLarge(AggTradeEvent, AggTradeGapEvent),
+}
+
+/// 按交易对跟踪最后分发的归集成交 id
+pub struct AggTradeSequencer {
+    /// 交易对 -> (最后分发的 id, 成交时间)
+    last: HashMap<String, (u64, u64)>,
+    /// 正在补录的交易对 -> (缺口, 补录期间缓存的实时成交)
+    pending: HashMap<String, (AggTradeGapEvent, Vec<AggTradeEvent>)>,
+    pub max_backfill: u64,
+    pub max_buffered: usize,
+    pub backfill_timeout: Duration,
+}
+
+impl AggTradeSequencer {
+    pub fn new() -> Self {
+        Self {
+            last: HashMap::new(),
+            pending: HashMap::new(),
+            max_backfill: DEFAULT_MAX_BACKFILL,
+            max_buffered: DEFAULT_MAX_BUFFERED,
+            backfill_timeout: DEFAULT_BACKFILL_TIMEOUT,
+        }
+    }
+
+    /// 最后分发的归集成交 id
+    pub fn last_id(&self, symbol: &str) -> Option<u64> {
+        self.last.get(&symbol.to_uppercase()).map(|(id, _)| *id)
+    }
+
+    /// 是否正在补录
+    pub fn is_backfilling(&self, symbol: &str) -> bool {
+        self.pending.contains_key(&symbol.to_uppercase())
+    }
+
+    /// gap 是否仍在等待补录结果（已超时或因缓存上限放弃的补录不再接受结果）
+    pub fn is_pending(&self, gap: &AggTradeGapEvent) -> bool {
+        self.pending
+            .get(&gap.symbol)
+            .map(|(pending, _)| pending.from_id == gap.from_id && pending.to_id == gap.to_id)
+            .unwrap_or(false)
+    }
+
+    /// 尚未完成补录的缺口
+    pub fn pending_gaps(&self) -> Vec<AggTradeGapEvent> {
+        self.pending.values().map(|(gap, _)| gap.clone()).collect()
+    }
+
+    pub fn push(&mut self, event: AggTradeEvent) -> Sequenced {
+        let symbol = event.symbol.to_uppercase();
+        if let Some((gap, buffered)) = self.pending.get_mut(&symbol) {
+            buffered.push(event);
+            if buffered.len() >= self.max_buffered {
+                return Sequenced::BufferFull(gap.clone());
+            }
+            return Sequenced::Buffered;
+        }
+        let Some(&(last_id, last_time)) = self.last.get(&symbol) else {
+            self.last.insert(symbol, (event.agg_trade_id, event.trade_time));
+            return Sequenced::InOrder(event);
+        };
+        if event.agg_trade_id <= last_id {
+            return Sequenced::Duplicate;
+        }
+        if event.agg_trade_id == last_id + 1 {
+            self.last.insert(symbol, (event.agg_trade_id, event.trade_time));
+            return Sequenced::InOrder(event);
+        }
+
+        let gap = AggTradeGapEvent {
+            symbol: symbol.clone(),
+            from_id: last_id + 1,
+            to_id: event.agg_trade_id - 1,
+            from_time: last_time,
+            to_time: event.trade_time,
+            ..Default::default()
+        };
+        if gap.to_id - gap.from_id + 1 > self.max_backfill {
+            self.last.insert(symbol, (event.agg_trade_id, event.trade_time));
+            return Sequenced::GapTooLarge(event, gap);
+        }
+        self.pending.insert(symbol, (gap.clone(), vec![event]));
+        Sequenced::Gap(gap)
+    }
+
+    /// 补录结束：recovered 为补录到的成交（按 id 升序）。
+    /// 返回应依次分发的成交（补录的在前，缓存的实时成交在后），
+    /// 以及缓存中再次出现的缺口（此时其后的成交继续缓存，需要再次补录）
+    pub fn complete_backfill(&mut self, gap: &AggTradeGapEvent, recovered: Vec<AggTradeEvent>) -> (Vec<AggTradeEvent>, Option<AggTradeGapEvent>) {
+        let buffered = self.pending.remove(&gap.symbol).map(|(_, buffered)| buffered).unwrap_or_default();
+        let mut ready = Vec::with_capacity(recovered.len() + buffered.len());
+        for event in recovered {
+            if event.agg_trade_id < gap.from_id || event.agg_trade_id > gap.to_id {
+                continue;
+            }
+            self.last.insert(gap.symbol.clone(), (event.agg_trade_id, event.trade_time));
+            ready.push(event);
+        }
+        // 未补录到的部分视为丢失，从缺口末尾继续
+        let last_time = self.last.get(&gap.symbol).map(|(_, time)| *time).unwrap_or(gap.from_time);
+        self.last.insert(gap.symbol.clone(), (gap.to_id, last_time));
+
+        let mut next_gap = None;
+        for event in buffered {
+            match self.push(event) {
+                Sequenced::InOrder(event) => ready.push(event),
+                Sequenced::Gap(gap) => next_gap = Some(gap),
+                Sequenced::GapTooLarge(event, _) => ready.push(event),
+                // 缓存达到上限由之后的实时成交再次触发
+                Sequenced::Duplicate | Sequenced::Buffered | Sequenced::BufferFull(_) => {}
+            }
+        }
+        (ready, next_gap)
+    }
+}
+
+impl Default for AggTradeSequencer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn trade(id: u64) -> AggTradeEvent {
+        AggTradeEvent {
+            symbol: "btcusdt".to_string(),
+            agg_trade_id: id,
+            trade_time: id * 10,
+            ..Default::default()
+        }
+    }
+
+    fn ids(events: &[AggTradeEvent]) -> Vec<u64> {
+        events.iter().map(|e| e.agg_trade_id).collect()
+    }
+
+    #[test]
+    fn in_order_and_duplicates() {
+        let mut seq = AggTradeSequencer::new();
+        assert!(matches!(seq.push(trade(1)), Sequenced::InOrder(_)));
+        assert!(matches!(seq.push(trade(2)), Sequenced::InOrder(_)));
+        assert!(matches!(seq.push(trade(2)), Sequenced::Duplicate));
+        assert!(matches!(seq.push(trade(1)), Sequenced::Duplicate));
+        assert_eq!(seq.last_id("BTCUSDT"), Some(2));
+    }
+
+    #[test]
+    fn gap_buffers_until_backfill_completes() {
+        let mut seq = AggTradeSequencer::new();
+        seq.push(trade(1));
+        let gap = match seq.push(trade(5)) {
+            Sequenced::Gap(gap) => gap,
+            _ => panic!("expected gap"),
+        };
+        assert_eq!((gap.symbol.as_str(), gap.from_id, gap.to_id), ("BTCUSDT", 2, 4));
+        assert_eq!((gap.from_time, gap.to_time), (10, 50));
+        assert!(seq.is_backfilling("btcusdt"));
+        assert!(seq.is_pending(&gap));
+        assert!(matches!(seq.push(trade(6)), Sequenced::Buffered));
+
+        // 补录结果中缺口范围外的成交被忽略
+        let (ready, next_gap) = seq.complete_backfill(&gap, vec![trade(1), trade(2), trade(3), trade(4), trade(5)]);
+        assert_eq!(ids(&ready), vec![2, 3, 4, 5, 6]);
+        assert!(next_gap.is_none());
+        assert!(!seq.is_pending(&gap));
+        assert_eq!(seq.last_id("BTCUSDT"), Some(6));
+    }
+
+    #[test]
+    fn partial_backfill_skips_lost_ids_and_reports_next_gap() {
+        let mut seq = AggTradeSequencer::new();
+        seq.push(trade(1));
+        let Sequenced::Gap(gap) = seq.push(trade(5)) else { panic!("expected gap") };
+        seq.push(trade(6));
+        seq.push(trade(9));
+        let (ready, next_gap) = seq.complete_backfill(&gap, vec![trade(2)]);
+        // 3、4 丢失，从缺口末尾继续；缓存中 6 与 9 之间又有缺口
+        assert_eq!(ids(&ready), vec![2, 5, 6]);
+        let next_gap = next_gap.expect("second gap");
+        assert_eq!((next_gap.from_id, next_gap.to_id), (7, 8));
+        assert!(seq.is_pending(&next_gap));
+    }
+
+    #[test]
+    fn gap_too_large_is_not_backfilled() {
+        let mut seq = AggTradeSequencer::new();
+        seq.max_backfill = 3;
+        seq.push(trade(1));
+        match seq.push(trade(10)) {
+            Sequenced::GapTooLarge(event, gap) => {
+                assert_eq!(event.agg_trade_id, 10);
+                assert_eq!((gap.from_id, gap.to_id), (2, 9));
+            }
+            _ => panic!("expected gap too large"),
+        }
+        assert!(!seq.is_backfilling("BTCUSDT"));
+        assert!(matches!(seq.push(trade(11)), Sequenced::InOrder(_)));
+    }
+
+    #[test]
+    fn buffer_cap_gives_up_backfill() {
+        let mut seq = AggTradeSequencer::new();
+        seq.max_buffered = 3;
+        seq.push(trade(1));
+        let Sequenced::Gap(gap) = seq.push(trade(5)) else { panic!("expected gap") };
+        assert!(matches!(seq.push(trade(6)), Sequenced::Buffered));
+        let full = match seq.push(trade(7)) {
+            Sequenced::BufferFull(full) => full,
+            _ => panic!("expected buffer full"),
+        };
+        assert_eq!((full.from_id, full.to_id), (gap.from_id, gap.to_id));
+        // 放弃补录：缓存的实时成交照常分发
+        let (ready, next_gap) = seq.complete_backfill(&full, Vec::new());
+        assert_eq!(ids(&ready), vec![5, 6, 7]);
+        assert!(next_gap.is_none());
+        // 迟到的补录结果不再被接受
+        assert!(!seq.is_pending(&gap));
+    }
+}
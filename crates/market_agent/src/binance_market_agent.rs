@@ -1,27 +1,30 @@
 use crate::market_agent::MarketAgent;
-use std::any::type_name;
 use async_trait::async_trait;
 use feeder::websocket::BinanceWebSocketClient;
-use feeder::websocket::WebSocket;
-use serde_json::Error as SerdeError;
+use feeder::pool::BinanceWebSocketPool;
+use feeder::command::WsCommand;
+use feeder::lifecycle::LifecycleEvent;
+use feeder::endpoint::WsEndpoint;
+use crate::market_agent::{
+    get_timestamp_us, publish_stats, run_feed, AgentCommand, CommandChannel, CommandHandle, EventEmission, FeedAgent,
+    FeedConnection, FeedMessage, StopHandle,
+};
+use crate::binance_stream::{StreamKind, StreamRouter};
+use crate::cache::MarkPriceCache;
+use crate::agg_trade_gap::{AggTradeSequencer, Sequenced};
+use crate::feed_stats::{check_stats_interval, FeedStats, DEFAULT_STATS_INTERVAL, UNROUTED_STREAM};
+use common::instrument::InstrumentRegistry;
+use rest_client::{BinanceRestClient, RestMarket};
+use tokio::sync::mpsc;
 use event_engine::event;
 use event_engine::event::BinanceEvent;
+use event_engine::event::ContractSize;
 use event_engine::event::EventType;
 use event_engine::event::EventPayload;
+use event_engine::market_data;
 use event_engine::event_dispatcher::QueueEventDispatcherProducer;
-use std::sync::Arc;
-// use tokio::sync::Mutex;
-use std::sync::Mutex;
+use std::collections::HashMap;
 use std::error::Error;
-use ringbuf::{RingBuffer, Producer, Consumer};
-use tokio::runtime::Runtime;
-
-use std::cell::RefCell;
-use std::rc::Rc;
-
-// use std::sync::atomic::{AtomicBool, Ordering};
-// use std::sync::OnceLock;
-
 use std::time::Duration;
 use chrono::Local;
 
@@ -34,93 +37,605 @@ fn get_timestamp() -> u128 {
         .as_millis()
 }
 
-fn get_timestamp_us() -> u128 {
-    std::time::SystemTime::now()
-        .duration_since(std::time::UNIX_EPOCH)
-        .unwrap()
-        .as_micros() // ✅ 以微秒（µs）为单位
+
+
+
+/// 币安市场类型，合约与现货的端点、可用流和字段略有不同
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BinanceMarket {
+    /// U 本位合约
+    UsdFutures,
+    /// 币本位合约（交割与永续），数量单位为张
+    CoinFutures,
+    /// 现货：深度事件没有 pu/T，bookTicker 没有 e/E/T，逐笔成交没有 X
+    Spot,
 }
 
+impl BinanceMarket {
+    /// 连接事件中的交易所标识
+    pub fn exchange_name(&self) -> &'static str {
+        match self {
+            BinanceMarket::UsdFutures => "binance",
+            BinanceMarket::CoinFutures => "binance_coin_futures",
+            BinanceMarket::Spot => "binance_spot",
+        }
+    }
+
+    /// 正式环境的行情端点
+    pub fn default_endpoint(&self) -> WsEndpoint {
+        match self {
+            BinanceMarket::UsdFutures => WsEndpoint::binance_futures(),
+            BinanceMarket::CoinFutures => WsEndpoint::binance_coin_futures(),
+            BinanceMarket::Spot => WsEndpoint::binance_spot(),
+        }
+    }
 
+    /// 对应的 REST 行情市场，用于补录 id 缺口
+    pub fn rest_market(&self) -> RestMarket {
+        match self {
+            BinanceMarket::UsdFutures => RestMarket::UsdFutures,
+            BinanceMarket::CoinFutures => RestMarket::CoinFutures,
+            BinanceMarket::Spot => RestMarket::Spot,
+        }
+    }
+
+    /// 该市场是否提供此类流：标记价格与强平订单只存在于合约市场
+    pub fn supports(&self, kind: StreamKind) -> bool {
+        match self {
+            BinanceMarket::UsdFutures | BinanceMarket::CoinFutures => true,
+            BinanceMarket::Spot => !matches!(kind, StreamKind::MarkPrice | StreamKind::Liquidation),
+        }
+    }
+}
 
 /// BinanceMarketAgent 实现 MarketAgent 接口，封装 BinanceWebSocketClient 与事件分发器
 // #[derive(Clone)]
 pub struct BinanceMarketAgent {
-    pub ws:  BinanceWebSocketClient,
+    /// 连接池，订阅超过单连接上限时自动分片到多个连接
+    pub ws:  BinanceWebSocketPool,
     pub event_producer: QueueEventDispatcherProducer,
+    stop: StopHandle,
+    commands: CommandChannel,
+    /// 组合流拆包与按流名称路由
+    router: StreamRouter,
+    /// 各交易对最新的标记价格与资金费率
+    pub mark_prices: MarkPriceCache,
+    /// 合约或现货
+    pub market: BinanceMarket,
+    /// 归集成交 id 连续性跟踪
+    pub agg_trades: AggTradeSequencer,
+    /// 补录使用的 REST 客户端，默认为进程内共享的正式环境客户端，可替换为指向本地 mock 的客户端
+    pub rest: BinanceRestClient,
+    /// 运行期间的消息通道，补录任务通过它交回结果
+    feed_tx: Option<mpsc::UnboundedSender<FeedMessage<Backfill>>>,
+    /// 币本位合约每张面值（美元），键为大写交易对；未配置面值的交易对无法折算数量，事件丢弃
+    pub contract_sizes: HashMap<String, f64>,
+    /// 按流统计的消息速率、解析失败、重连与延迟
+    stats: FeedStats,
+    /// FeedStats 事件的分发间隔，通过 set_stats_interval 设置
+    stats_interval: Duration,
+    /// 分发原始事件、归一化事件或两者
+    pub emission: EventEmission,
+}
+
+/// 归集成交补录结果，经 FeedMessage::Custom 交回运行循环（错误已转为字符串，Box<dyn Error> 不是 Send）
+type Backfill = (event::AggTradeGapEvent, Result<Vec<event::AggTradeEvent>, String>);
+
+
+#[async_trait]
+impl FeedConnection for BinanceWebSocketPool {
+    fn placeholder(&self) -> Self {
+        BinanceWebSocketPool::new(self.endpoint().clone())
+    }
+
+    fn set_message_callback(&mut self, callback: Box<dyn FnMut(String) + Send + 'static>) {
+        BinanceWebSocketPool::set_message_callback(self, callback);
+    }
+
+    fn set_lifecycle_callback(&mut self, callback: Box<dyn FnMut(u64, LifecycleEvent) + Send + 'static>) {
+        BinanceWebSocketPool::set_lifecycle_callback(self, callback);
+    }
+
+    fn command_handle(&self) -> mpsc::UnboundedSender<WsCommand> {
+        BinanceWebSocketPool::command_handle(self)
+    }
+
+    async fn listen_loop(&mut self) -> Result<(), Box<dyn Error>> {
+        BinanceWebSocketPool::listen_loop(self).await
+    }
 }
 
+impl FeedAgent for BinanceMarketAgent {
+    type Connection = BinanceWebSocketPool;
+    type Custom = Backfill;
 
-#[async_trait(?Send)]
+    const NAME: &'static str = "BinanceMarketAgent";
+
+    fn connection(&mut self) -> &mut BinanceWebSocketPool {
+        &mut self.ws
+    }
+
+    fn command_channel(&mut self) -> &mut CommandChannel {
+        &mut self.commands
+    }
+
+    fn stop(&self) -> &StopHandle {
+        &self.stop
+    }
+
+    fn stats_interval(&self) -> Duration {
+        self.stats_interval
+    }
+
+    fn handle_feed_message(&mut self, msg: FeedMessage<Backfill>) {
+        match msg {
+            FeedMessage::Text(text, received_timestamp) => self.on_message(text, received_timestamp),
+            FeedMessage::Lifecycle(connection_id, lifecycle) => {
+                self.stats.record_lifecycle(&lifecycle);
+                let (event_type, event) = connection_event(self.market.exchange_name(), connection_id, lifecycle);
+                self.on_connection(event_type, event);
+            }
+            FeedMessage::Custom((gap, result)) => self.finish_backfill(gap, result),
+        }
+    }
+
+    /// 运行期间的订阅先做与启动前订阅相同的校验
+    fn before_command(&mut self, command: &AgentCommand) -> Result<(), String> {
+        if let AgentCommand::Subscribe(streams, _) = command {
+            let streams: Vec<&str> = streams.iter().map(|s| s.as_str()).collect();
+            self.check_streams(&streams)?;
+        }
+        Ok(())
+    }
+
+    fn publish_stats(&mut self) {
+        publish_stats(&self.stats, &mut self.event_producer);
+    }
+}
+
+#[async_trait]
 impl MarketAgent for BinanceMarketAgent {
     async fn start(&mut self) -> Result<(), Box<dyn std::error::Error>> {
-        // 注意这里依然在单线程场景下使用裸指针
-        // 获取一个裸指针，用于在回调中操作 self
-        let self_ptr = self as *mut BinanceMarketAgent;
-        
-
-        
-        // 初始化 AtomicBool（只初始化一次）
-        // FIRST_REAL_EVENT_PRINTED.get_or_init(|| AtomicBool::new(false));
-        // 注册 WebSocket 消息回调
-        self.ws.set_message_callback(move |msg: String| {
-            let received_timestamp = get_timestamp_us();
-            // 安全地通过裸指针获取可变引用
-            let this = unsafe { &mut *self_ptr };
-            match serde_json::from_slice(msg.as_bytes()) {
-                Ok(BinanceEvent::AggTrade(mut data)) => {
-                    data.received_timestamp = received_timestamp;
-                    // println!("收到交易数据: {:?}", data);
-                    this.on_trade(data);
-                }
-                Ok(BinanceEvent::Depth(mut data)) => {
-                    // println!("收到深度数据: {:?}", data);
-                    data.received_timestamp = received_timestamp;
-                    this.on_depth(data);
-                }
-                Err(e) => {
-                    eprintln!("JSON解析失败: {} - 原始消息: {}", e, msg);
-                }
-                _ => {
-                    eprintln!("收到未处理的事件类型: {}", msg);
-                }
+        let (tx, rx) = mpsc::unbounded_channel();
+        self.feed_tx = Some(tx.clone());
+        let result = run_feed(self, tx, rx).await;
+        // 未完成的补录随代理停止而取消，缓存的实时成交照常分发
+        self.feed_tx = None;
+        for gap in self.agg_trades.pending_gaps() {
+            self.finish_backfill(gap, Err("市场代理已停止，补录取消".to_string()));
+        }
+        result
+    }
+
+    fn stop_handle(&self) -> StopHandle {
+        self.stop.clone()
+    }
+
+    fn command_handle(&self) -> CommandHandle {
+        self.commands.handle()
+    }
+
+    fn feed_stats(&self) -> FeedStats {
+        self.stats.clone()
+    }
+
+    fn set_event_emission(&mut self, emission: EventEmission) {
+        self.emission = emission;
+    }
+
+    fn on_trade(&mut self, mut event: event::AggTradeEvent) {
+        let Some(contract_size) = self.event_contract_size(&event.symbol, &event.stream) else { return };
+        event.contract_size = contract_size;
+        // 归集成交 id 出现缺口时先补录，保证分发顺序与 id 一致
+        match self.agg_trades.push(event) {
+            Sequenced::InOrder(event) => self.emit_agg_trade(event),
+            Sequenced::Duplicate | Sequenced::Buffered => {}
+            Sequenced::Gap(gap) => self.request_backfill(gap),
+            Sequenced::BufferFull(gap) => {
+                let reason = format!("补录期间缓存的成交超过上限 {}", self.agg_trades.max_buffered);
+                self.finish_backfill(gap, Err(reason));
+            }
+            Sequenced::GapTooLarge(event, mut gap) => {
+                gap.reason = format!("缺口超过补录上限 {}", self.agg_trades.max_backfill);
+                eprintln!("[BinanceMarketAgent] {} 归集成交 {}-{} {}，不补录", gap.symbol, gap.from_id, gap.to_id, gap.reason);
+                self.event_producer.fire(EventType::AggTradeGap, EventPayload::AggTradeGap(gap));
+                self.emit_agg_trade(event);
             }
+        }
+    }
+    
+    fn on_depth(&mut self, mut event: event::DepthEvent) {
+        let Some(contract_size) = self.event_contract_size(&event.symbol, &event.stream) else { return };
+        event.contract_size = contract_size;
+        // 档位无法解析时丢弃整条增量，订单簿会因序号不连续重新同步
+        let delta = self.emission.normalize(&self.stats, &event.stream, || {
+            market_data::BookDelta::from_depth(self.market.exchange_name(), &event)
         });
+        if self.emission.raw() {
+            self.event_producer.fire(EventType::Depth, EventPayload::Depth(event));
+        }
+        if let Some(delta) = delta {
+            self.event_producer.fire(EventType::BookDelta, EventPayload::BookDelta(delta));
+        }
+    }
 
-        // 直接启动 WebSocket 的监听循环
-        self.ws.listen_loop().await?;
-        Ok(())
+    fn on_raw_trade(&mut self, mut event: event::TradeEvent) {
+        let Some(contract_size) = self.event_contract_size(&event.symbol, &event.stream) else { return };
+        event.contract_size = contract_size;
+        let trade = self.emission.normalize(&self.stats, &event.stream, || market_data::Trade::from_trade(self.market.exchange_name(), &event));
+        if self.emission.raw() {
+            self.event_producer.fire(EventType::Trade, EventPayload::Trade(event));
+        }
+        if let Some(trade) = trade {
+            self.event_producer.fire(EventType::MarketTrade, EventPayload::MarketTrade(trade));
+        }
     }
-    
 
-    fn on_trade(&mut self, event: event::AggTradeEvent) {
-        self.event_producer.fire(EventType::AggTrade, EventPayload::AggTrade(event));
+    fn on_book_ticker(&mut self, mut event: event::BookTickerEvent) {
+        let Some(contract_size) = self.event_contract_size(&event.symbol, &event.stream) else { return };
+        event.contract_size = contract_size;
+        let bbo = self.emission.normalize(&self.stats, &event.stream, || market_data::Bbo::from_book_ticker(self.market.exchange_name(), &event));
+        if self.emission.raw() {
+            self.event_producer.fire(EventType::BookTicker, EventPayload::BookTicker(event));
+        }
+        if let Some(bbo) = bbo {
+            self.event_producer.fire(EventType::Bbo, EventPayload::Bbo(bbo));
+        }
     }
-    
-    fn on_depth(&mut self, event: event::DepthEvent) {
-        self.event_producer.fire(EventType::Depth, EventPayload::Depth(event));
+
+    fn on_mark_price(&mut self, event: event::MarkPriceEvent) {
+        // 先更新缓存，保证事件回调中查询到的是最新值
+        self.mark_prices.update(&event.symbol, event.clone());
+        self.event_producer.fire(EventType::MarkPrice, EventPayload::MarkPrice(event));
+    }
+
+    fn mark_price_cache(&self) -> MarkPriceCache {
+        self.mark_prices.clone()
+    }
+
+    fn on_liquidation(&mut self, mut event: event::LiquidationEvent) {
+        let Some(contract_size) = self.event_contract_size(&event.order.symbol, &event.stream) else { return };
+        event.order.contract_size = contract_size;
+        self.event_producer.fire(EventType::Liquidation, EventPayload::Liquidation(event));
+    }
+
+    fn on_kline(&mut self, event: event::KlineEvent) {
+        self.event_producer.fire(EventType::Kline, EventPayload::Kline(event));
+    }
+
+    fn on_connection(&mut self, event_type: EventType, event: event::ConnectionEvent) {
+        println!("[{}] 连接 {} {:?} {}", Local::now().format("%H:%M:%S"), event.connection_id, event_type, event.reason);
+        self.event_producer.fire(event_type, EventPayload::Connection(event));
     }
 
     async fn subscribe(&mut self, streams: Vec<&str>) -> Result<(), Box<dyn Error>> {
+        self.check_streams(&streams)?;
         self.ws.subscribe(streams).await?;
         Ok(())
     }
-}
 
-struct AgentPtr(*mut BinanceMarketAgent);
+    async fn unsubscribe(&mut self, streams: Vec<&str>) -> Result<(), Box<dyn Error>> {
+        // 连接池会在必要时合并连接
+        self.ws.unsubscribe(streams).await
+    }
 
-// 告诉编译器：我确信此指针单线程使用、安全可跨线程搬移
-unsafe impl Send for AgentPtr {}
-unsafe impl Send for BinanceMarketAgent {}
+    async fn list_subscriptions(&mut self) -> Result<Vec<String>, Box<dyn Error>> {
+        self.ws.list_subscriptions().await
+    }
+}
+
+/// 将 feeder 的生命周期事件转换为事件类型与载荷
+pub(crate) fn connection_event(exchange: &str, connection_id: u64, lifecycle: LifecycleEvent) -> (EventType, event::ConnectionEvent) {
+    let mut event = event::ConnectionEvent {
+        exchange: exchange.to_string(),
+        connection_id,
+        streams: Vec::new(),
+        reason: String::new(),
+        silent_ms: 0,
+        timestamp: get_timestamp(),
+    };
+    let event_type = match lifecycle {
+        LifecycleEvent::Connected { streams } => {
+            event.streams = streams;
+            EventType::Connected
+        }
+        LifecycleEvent::Disconnected { reason, streams } => {
+            event.reason = reason;
+            event.streams = streams;
+            EventType::Disconnected
+        }
+        LifecycleEvent::Resubscribed { streams } => {
+            event.streams = streams;
+            EventType::Resubscribed
+        }
+        LifecycleEvent::Stale(report) => {
+            event.reason = format!("{:?} 未收到数据（阈值 {:?}）", report.silent_for, report.threshold);
+            event.silent_ms = report.silent_for.as_millis() as u64;
+            event.streams = report.streams;
+            EventType::Stale
+        }
+    };
+    (event_type, event)
+}
 
 impl BinanceMarketAgent {
     pub fn new(
         ws: BinanceWebSocketClient,
         event_producer: QueueEventDispatcherProducer,
+    ) -> Self {
+        Self::with_pool(BinanceWebSocketPool::from_client(ws), event_producer)
+    }
+
+    /// 现货行情 agent，ws 应连接现货端点（见 BinanceMarket::default_endpoint）
+    pub fn spot(
+        ws: BinanceWebSocketClient,
+        event_producer: QueueEventDispatcherProducer,
+    ) -> Self {
+        Self::with_market(BinanceWebSocketPool::from_client(ws), event_producer, BinanceMarket::Spot)
+    }
+
+    /// 币本位合约行情 agent，ws 应连接 dstream 端点
+    pub fn coin_futures(
+        ws: BinanceWebSocketClient,
+        event_producer: QueueEventDispatcherProducer,
+    ) -> Self {
+        Self::with_market(BinanceWebSocketPool::from_client(ws), event_producer, BinanceMarket::CoinFutures)
+    }
+
+    /// 使用连接池构造，订阅会在池内多个连接间自动分片
+    pub fn with_pool(
+        ws: BinanceWebSocketPool,
+        event_producer: QueueEventDispatcherProducer,
+    ) -> Self {
+        Self::with_market(ws, event_producer, BinanceMarket::UsdFutures)
+    }
+
+    pub fn with_market(
+        ws: BinanceWebSocketPool,
+        event_producer: QueueEventDispatcherProducer,
+        market: BinanceMarket,
     ) -> Self {
         Self {
             ws: ws,
             event_producer: event_producer,
+            stop: StopHandle::new(),
+            commands: CommandChannel::new(),
+            router: StreamRouter::new(),
+            mark_prices: MarkPriceCache::new(),
+            market,
+            agg_trades: AggTradeSequencer::new(),
+            rest: BinanceRestClient::shared(market.rest_market()),
+            feed_tx: None,
+            contract_sizes: HashMap::new(),
+            stats: FeedStats::new(market.exchange_name()),
+            stats_interval: DEFAULT_STATS_INTERVAL,
+            emission: EventEmission::default(),
+        }
+    }
+
+    /// 设置 FeedStats 事件的分发间隔，为 0 时返回错误；需在 start 之前调用
+    pub fn set_stats_interval(&mut self, interval: Duration) -> Result<(), Box<dyn Error>> {
+        self.stats_interval = check_stats_interval(interval)?;
+        Ok(())
+    }
+
+    pub fn stats_interval(&self) -> Duration {
+        self.stats_interval
+    }
+
+    /// 设置币本位合约面值（例如来自 exchangeInfo 的 contractSize）
+    pub fn set_contract_size(&mut self, symbol: &str, contract_size: f64) {
+        self.contract_sizes.insert(symbol.to_uppercase(), contract_size);
+    }
+
+    /// 从品种元数据中读取币本位合约面值，币本位合约必须在订阅前调用
+    pub fn apply_instruments(&mut self, registry: &InstrumentRegistry) {
+        for instrument in registry.iter() {
+            if let Some(ContractSize::Inverse(contract_size)) = instrument.contract_size {
+                self.set_contract_size(&instrument.symbol, contract_size);
+            }
+        }
+    }
+
+    /// 交易对的合约面值，非币本位市场为 Ok(None)；币本位合约未加载面值时返回错误，不按交易对猜测
+    pub fn contract_size_of(&self, symbol: &str) -> Result<Option<ContractSize>, String> {
+        if self.market != BinanceMarket::CoinFutures {
+            return Ok(None);
+        }
+        match self.contract_sizes.get(&symbol.to_uppercase()) {
+            Some(size) => Ok(Some(ContractSize::Inverse(*size))),
+            None => Err(format!("{} 缺少合约面值，需先通过 apply_instruments 加载品种元数据", symbol)),
+        }
+    }
+
+    /// 事件所需的合约面值；未知面值的事件无法折算数量，记为解析失败并返回 None，事件丢弃
+    fn event_contract_size(&self, symbol: &str, stream: &str) -> Option<Option<ContractSize>> {
+        match self.contract_size_of(symbol) {
+            Ok(contract_size) => Some(contract_size),
+            Err(e) => {
+                self.stats.record_parse_failure(stream);
+                eprintln!("[BinanceMarketAgent] {} 事件丢弃: {}", stream, e);
+                None
+            }
+        }
+    }
+
+    /// 订阅市场不存在的流时服务端只会返回成功，之后再无数据，这里提前拒绝；
+    /// 币本位合约缺少面值的交易对同样拒绝，否则其事件会全部丢弃
+    fn check_streams(&self, streams: &[&str]) -> Result<(), String> {
+        for stream in streams {
+            let kind = StreamKind::from_stream(stream);
+            if !self.market.supports(kind) {
+                return Err(format!("{} 市场不提供 {} 流", self.market.exchange_name(), stream));
+            }
+            // 全市场流（!forceOrder@arr 等）在收到事件时逐条检查
+            let uses_contract_size = !matches!(kind, StreamKind::Kline | StreamKind::MarkPrice | StreamKind::Unknown);
+            if uses_contract_size && !stream.starts_with('!') {
+                let symbol = stream.split('@').next().unwrap_or(stream);
+                self.contract_size_of(symbol)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn emit_agg_trade(&mut self, event: event::AggTradeEvent) {
+        let trade = self.emission.normalize(&self.stats, &event.stream, || market_data::Trade::from_agg_trade(self.market.exchange_name(), &event));
+        if self.emission.raw() {
+            self.event_producer.fire(EventType::AggTrade, EventPayload::AggTrade(event));
+        }
+        if let Some(trade) = trade {
+            self.event_producer.fire(EventType::MarketTrade, EventPayload::MarketTrade(trade));
+        }
+    }
+
+    /// 在后台任务中通过 REST 补录缺口，结果经消息通道交回 start 的循环
+    fn request_backfill(&mut self, gap: event::AggTradeGapEvent) {
+        println!("[BinanceMarketAgent] {} 归集成交 id 缺口 {}-{}，开始补录", gap.symbol, gap.from_id, gap.to_id);
+        let Some(tx) = self.feed_tx.clone() else {
+            self.finish_backfill(gap, Err("市场代理未运行，无法补录".to_string()));
+            return;
+        };
+        let rest = self.rest.clone();
+        let timeout = self.agg_trades.backfill_timeout;
+        tokio::spawn(async move {
+            let result = match tokio::time::timeout(timeout, rest.agg_trades_by_id(&gap.symbol, gap.from_id, gap.to_id)).await {
+                Ok(result) => result.map_err(|e| e.to_string()),
+                Err(_) => Err(format!("补录超时（{} 秒）", timeout.as_secs())),
+            };
+            let _ = tx.send(FeedMessage::Custom((gap, result)));
+        });
+    }
+
+    /// 补录结束：按 id 顺序分发补录的成交与补录期间缓存的实时成交，并报告缺口
+    fn finish_backfill(&mut self, mut gap: event::AggTradeGapEvent, result: Result<Vec<event::AggTradeEvent>, String>) {
+        // 因缓存上限提前放弃的补录，结果随后才返回，此时已无需处理
+        if !self.agg_trades.is_pending(&gap) {
+            return;
+        }
+        let (recovered, reason) = match result {
+            Ok(trades) => (trades, String::new()),
+            Err(e) => (Vec::new(), e),
+        };
+        // 缺口只会出现在已通过面值检查的交易对上
+        let contract_size = self.contract_size_of(&gap.symbol).unwrap_or(None);
+        let stream = format!("{}@aggTrade", gap.symbol.to_lowercase());
+        let received_timestamp = get_timestamp_us();
+        // REST 返回的成交没有交易对、事件类型与事件时间
+        let recovered = recovered
+            .into_iter()
+            .map(|mut trade| {
+                trade.event = "aggTrade".to_string();
+                trade.event_time = trade.trade_time;
+                trade.symbol = gap.symbol.clone();
+                trade.stream = stream.clone();
+                trade.received_timestamp = received_timestamp;
+                trade.contract_size = contract_size;
+                trade.backfilled = true;
+                trade
+            })
+            .collect();
+        let (ready, next_gap) = self.agg_trades.complete_backfill(&gap, recovered);
+
+        gap.recovered = ready.iter().filter(|trade| trade.backfilled).count() as u64;
+        gap.complete = gap.recovered == gap.to_id - gap.from_id + 1;
+        gap.reason = if gap.complete || !reason.is_empty() { reason } else { "交易所未返回完整的成交".to_string() };
+        if gap.complete {
+            println!("[BinanceMarketAgent] {} 归集成交 {}-{} 已补录 {} 笔", gap.symbol, gap.from_id, gap.to_id, gap.recovered);
+        } else {
+            eprintln!("[BinanceMarketAgent] {} 归集成交 {}-{} 仅补录 {} 笔: {}", gap.symbol, gap.from_id, gap.to_id, gap.recovered, gap.reason);
+        }
+
+        for trade in ready {
+            self.emit_agg_trade(trade);
+        }
+        self.event_producer.fire(EventType::AggTradeGap, EventPayload::AggTradeGap(gap));
+        if let Some(next_gap) = next_gap {
+            self.request_backfill(next_gap);
+        }
+    }
+
+    /// 按流类型把 data 解析为具体事件，未知流按 "e" 字段兜底。
+    /// 全市场标记价格流的 data 为数组，其余流每条消息只有一个事件
+    fn parse_events(kind: StreamKind, data: &str, is_array: bool) -> Result<Vec<BinanceEvent>, serde_json::Error> {
+        let event = match kind {
+            StreamKind::MarkPrice if is_array => {
+                let events: Vec<event::MarkPriceEvent> = serde_json::from_str(data)?;
+                return Ok(events.into_iter().map(BinanceEvent::MarkPrice).collect());
+            }
+            StreamKind::AggTrade => BinanceEvent::AggTrade(serde_json::from_str(data)?),
+            StreamKind::Depth => BinanceEvent::Depth(serde_json::from_str(data)?),
+            StreamKind::Kline => BinanceEvent::Kline(serde_json::from_str(data)?),
+            StreamKind::Trade => BinanceEvent::Trade(serde_json::from_str(data)?),
+            StreamKind::BookTicker => BinanceEvent::BookTicker(serde_json::from_str(data)?),
+            StreamKind::MarkPrice => BinanceEvent::MarkPrice(serde_json::from_str(data)?),
+            StreamKind::Liquidation => BinanceEvent::Liquidation(serde_json::from_str(data)?),
+            StreamKind::Unknown => serde_json::from_str(data)?,
+        };
+        Ok(vec![event])
+    }
+
+    /// 补全接收时间与流名称后分发到对应的回调，返回事件时间。
+    /// 原始流格式的消息没有流名称，取推导的名称；全市场标记价格数组按各自的交易对命名
+    fn emit_event(&mut self, event: BinanceEvent, stream: Option<&str>, derived_stream: &str, received_timestamp: u128) -> u64 {
+        macro_rules! emit {
+            ($data:expr, $stream:expr, $handler:ident) => {{
+                let mut data = $data;
+                data.received_timestamp = received_timestamp;
+                data.stream = $stream;
+                let event_time = data.event_time;
+                self.$handler(data);
+                event_time
+            }};
+        }
+        let named = stream.unwrap_or(derived_stream).to_string();
+        match event {
+            BinanceEvent::AggTrade(data) => emit!(data, named, on_trade),
+            BinanceEvent::Depth(data) => emit!(data, named, on_depth),
+            BinanceEvent::Kline(data) => emit!(data, named, on_kline),
+            BinanceEvent::Trade(data) => emit!(data, named, on_raw_trade),
+            BinanceEvent::BookTicker(data) => emit!(data, named, on_book_ticker),
+            BinanceEvent::MarkPrice(data) => {
+                let named = match stream {
+                    Some(stream) => stream.to_string(),
+                    None => format!("{}@markPrice", data.symbol.to_lowercase()),
+                };
+                emit!(data, named, on_mark_price)
+            }
+            BinanceEvent::Liquidation(data) => emit!(data, named, on_liquidation),
+        }
+    }
+
+    /// 解析原始消息并分发到对应的回调：组合流按流名称路由，原始流按 "e" 字段解析。
+    /// data 直接从原始文本解析为具体事件，事件时间取自解析结果（数组取第一条）
+    fn on_message(&mut self, msg: String, received_timestamp: u128) {
+        let routed = match self.router.route(&msg) {
+            Ok(routed) => routed,
+            Err(e) => {
+                self.stats.record_parse_failure(UNROUTED_STREAM);
+                eprintln!("JSON解析失败: {} - 原始消息: {}", e, msg);
+                return;
+            }
+        };
+        let stream_name = routed.stream_name().to_string();
+        let events = match Self::parse_events(routed.kind, routed.data.get(), routed.is_array()) {
+            Ok(events) => events,
+            Err(e) => {
+                if routed.kind == StreamKind::Unknown {
+                    self.stats.record_unknown_event(&stream_name);
+                    eprintln!("收到未处理的事件类型 [{}]: {} - 原始消息: {}", stream_name, e, msg);
+                } else {
+                    self.stats.record_parse_failure(&stream_name);
+                    eprintln!("{:?} 解析失败: {} - 原始消息: {}", routed.kind, e, msg);
+                }
+                self.stats.record_message(&stream_name, received_timestamp, None);
+                return;
+            }
+        };
+        let mut event_time = None;
+        for event in events {
+            let time = self.emit_event(event, routed.stream.as_deref(), &stream_name, received_timestamp);
+            event_time.get_or_insert(time);
         }
+        self.stats.record_message(&stream_name, received_timestamp, event_time);
     }
 }
@@ -0,0 +1,298 @@
+// market_agent/binance_stream.rs
+
+use std::borrow::Cow;
+use std::collections::HashMap;
+use serde::de::IgnoredAny;
+use serde::Deserialize;
+use serde_json::value::RawValue;
+
+/// 组合流消息包装：{"stream": "<streamName>", "data": <原始payload>}。
+/// data 只记录原始文本的位置，由调用方按流类型一次解析为具体事件
+#[derive(Deserialize, Debug)]
+struct CombinedStreamMessage<'a> {
+    stream: String,
+    #[serde(borrow)]
+    data: &'a RawValue,
+}
+
+/// 原始流格式的消息没有流名称，只读取路由与推导流名称所需的字段，其余字段跳过
+#[derive(Deserialize, Debug, Default)]
+struct RawEventProbe<'a> {
+    #[serde(borrow, default)]
+    e: Option<Cow<'a, str>>,
+    #[serde(borrow, default)]
+    s: Option<Cow<'a, str>>,
+    #[serde(default)]
+    u: Option<IgnoredAny>,
+    #[serde(default)]
+    b: Option<IgnoredAny>,
+    #[serde(rename = "B", default)]
+    bid_qty: Option<IgnoredAny>,
+    #[serde(default)]
+    a: Option<IgnoredAny>,
+    #[serde(rename = "A", default)]
+    ask_qty: Option<IgnoredAny>,
+    /// 强平事件的交易对在订单对象 "o" 中
+    #[serde(borrow, default)]
+    o: Option<SymbolProbe<'a>>,
+    /// K 线周期在 "k" 中
+    #[serde(borrow, default)]
+    k: Option<IntervalProbe<'a>>,
+}
+
+#[derive(Deserialize, Debug)]
+struct SymbolProbe<'a> {
+    #[serde(borrow, default)]
+    s: Option<Cow<'a, str>>,
+}
+
+#[derive(Deserialize, Debug)]
+struct IntervalProbe<'a> {
+    #[serde(borrow, default)]
+    i: Option<Cow<'a, str>>,
+}
+
+impl RawEventProbe<'_> {
+    /// 现货 bookTicker 不带 "e" 字段，按 u/s/b/B/a/A 字段特征识别
+    fn is_spot_book_ticker(&self) -> bool {
+        self.e.is_none()
+            && self.u.is_some()
+            && self.s.is_some()
+            && self.b.is_some()
+            && self.bid_qty.is_some()
+            && self.a.is_some()
+            && self.ask_qty.is_some()
+    }
+
+    /// 按 "<symbol>@<事件类型>" 推断流名称
+    fn stream_name(&self) -> String {
+        let symbol = self
+            .s
+            .as_deref()
+            .or_else(|| self.o.as_ref().and_then(|o| o.s.as_deref()))
+            .unwrap_or("")
+            .to_lowercase();
+        let event = match self.e.as_deref() {
+            Some("depthUpdate") => "depth".to_string(),
+            Some("kline") => {
+                let interval = self.k.as_ref().and_then(|k| k.i.as_deref()).unwrap_or("");
+                format!("kline_{}", interval)
+            }
+            Some(other) => other.to_string(),
+            None if self.is_spot_book_ticker() => "bookTicker".to_string(),
+            None => String::new(),
+        };
+        format!("{}@{}", symbol, event)
+    }
+}
+
+/// 流类型，由流名称决定
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StreamKind {
+    /// <symbol>@aggTrade
+    AggTrade,
+    /// <symbol>@depth、<symbol>@depth@100ms 等增量深度
+    Depth,
+    /// <symbol>@kline_<interval>
+    Kline,
+    /// <symbol>@trade
+    Trade,
+    /// <symbol>@bookTicker 与全市场 !bookTicker
+    BookTicker,
+    /// <symbol>@markPrice[@1s] 与全市场 !markPrice@arr[@1s]（数组）
+    MarkPrice,
+    /// <symbol>@forceOrder 与全市场 !forceOrder@arr（虽然名为 arr，每条消息仍是单个对象）
+    Liquidation,
+    /// 尚未支持的流，按 "e" 字段兜底解析
+    Unknown,
+}
+
+impl StreamKind {
+    /// 根据流名称判断类型（不区分大小写），例如 "btcusdt@depth@100ms" -> Depth
+    pub fn from_stream(stream: &str) -> Self {
+        let lowered = stream.to_lowercase();
+        if lowered == "!bookticker" {
+            return StreamKind::BookTicker;
+        }
+        if lowered.starts_with("!markprice@arr") {
+            return StreamKind::MarkPrice;
+        }
+        if lowered == "!forceorder@arr" {
+            return StreamKind::Liquidation;
+        }
+        let mut parts = lowered.split('@').skip(1);
+        match parts.next() {
+            Some("aggtrade") => StreamKind::AggTrade,
+            // depth5/depth10/depth20 为有限档深度快照，格式不同，不属于增量深度
+            Some("depth") => StreamKind::Depth,
+            Some(name) if name.starts_with("kline_") => StreamKind::Kline,
+            Some("trade") => StreamKind::Trade,
+            Some("bookticker") => StreamKind::BookTicker,
+            Some("markprice") => StreamKind::MarkPrice,
+            Some("forceorder") => StreamKind::Liquidation,
+            _ => StreamKind::Unknown,
+        }
+    }
+}
+
+/// 拆包后的消息，data 借用原始文本，只解析一次
+#[derive(Debug)]
+pub struct StreamMessage<'a> {
+    /// 来源流名称；原始流格式的消息不带流名称，为 None
+    pub stream: Option<String>,
+    pub kind: StreamKind,
+    pub data: &'a RawValue,
+    /// 原始流格式按事件内容推导的流名称，组合流为空
+    derived_stream: String,
+}
+
+impl StreamMessage<'_> {
+    /// 用于统计与日志的流名称：组合流取包装中的名称，原始流取推导的名称
+    pub fn stream_name(&self) -> &str {
+        self.stream.as_deref().unwrap_or(&self.derived_stream)
+    }
+
+    /// 全市场数组流（例如 !markPrice@arr）的 data 为数组
+    pub fn is_array(&self) -> bool {
+        self.data.get().trim_start().starts_with('[')
+    }
+}
+
+/// 按流名称路由：识别组合流包装并拆包，流名称到类型的映射会被缓存
+#[derive(Default)]
+pub struct StreamRouter {
+    routes: HashMap<String, StreamKind>,
+}
+
+impl StreamRouter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 流名称对应的类型
+    pub fn kind_of(&mut self, stream: &str) -> StreamKind {
+        if let Some(kind) = self.routes.get(stream) {
+            return *kind;
+        }
+        let kind = StreamKind::from_stream(stream);
+        self.routes.insert(stream.to_string(), kind);
+        kind
+    }
+
+    /// 解析一条原始文本消息：组合流格式拆出 stream 与 data，原始流格式整体作为 data。
+    /// 组合流的包装总是以 "stream" 字段开头，据此跳过对原始流消息的包装解析
+    pub fn route<'a>(&mut self, text: &'a str) -> Result<StreamMessage<'a>, serde_json::Error> {
+        if text.trim_start().starts_with("{\"stream\"") {
+            let envelope: CombinedStreamMessage<'a> = serde_json::from_str(text)?;
+            let kind = self.kind_of(&envelope.stream);
+            return Ok(StreamMessage {
+                stream: Some(envelope.stream),
+                kind,
+                data: envelope.data,
+                derived_stream: String::new(),
+            });
+        }
+
+        let data: &'a RawValue = serde_json::from_str(text)?;
+        let (kind, probe) = if data.get().trim_start().starts_with('[') {
+            // 全市场数组流（原始格式）无法按 "e" 标签整体解析，按首个元素的事件类型路由
+            let probes: Vec<RawEventProbe> = serde_json::from_str(text)?;
+            let probe = probes.into_iter().next().unwrap_or_default();
+            let kind = match probe.e.as_deref() {
+                Some("markPriceUpdate") => StreamKind::MarkPrice,
+                _ => StreamKind::Unknown,
+            };
+            (kind, probe)
+        } else {
+            let probe: RawEventProbe = serde_json::from_str(text)?;
+            let kind = if probe.is_spot_book_ticker() { StreamKind::BookTicker } else { StreamKind::Unknown };
+            (kind, probe)
+        };
+        Ok(StreamMessage {
+            stream: None,
+            kind,
+            data,
+            derived_stream: probe.stream_name(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn kind_follows_stream_name() {
+        assert_eq!(StreamKind::from_stream("btcusdt@aggTrade"), StreamKind::AggTrade);
+        assert_eq!(StreamKind::from_stream("btcusdt@depth@100ms"), StreamKind::Depth);
+        assert_eq!(StreamKind::from_stream("btcusdt@depth20"), StreamKind::Unknown);
+        assert_eq!(StreamKind::from_stream("btcusdt@kline_1m"), StreamKind::Kline);
+        assert_eq!(StreamKind::from_stream("btcusdt@trade"), StreamKind::Trade);
+        assert_eq!(StreamKind::from_stream("BTCUSDT@bookTicker"), StreamKind::BookTicker);
+        assert_eq!(StreamKind::from_stream("!bookTicker"), StreamKind::BookTicker);
+        assert_eq!(StreamKind::from_stream("btcusdt@markPrice@1s"), StreamKind::MarkPrice);
+        assert_eq!(StreamKind::from_stream("!markPrice@arr@1s"), StreamKind::MarkPrice);
+        assert_eq!(StreamKind::from_stream("!forceOrder@arr"), StreamKind::Liquidation);
+        assert_eq!(StreamKind::from_stream("pqia91ma19a5s61cv6a81va65sdf19v8a65a1"), StreamKind::Unknown);
+    }
+
+    #[test]
+    fn combined_envelope_is_unwrapped() {
+        let mut router = StreamRouter::new();
+        let text = r#"{"stream":"btcusdt@aggTrade","data":{"e":"aggTrade","E":1,"s":"BTCUSDT"}}"#;
+        let routed = router.route(text).unwrap();
+        assert_eq!(routed.stream.as_deref(), Some("btcusdt@aggTrade"));
+        assert_eq!(routed.stream_name(), "btcusdt@aggTrade");
+        assert_eq!(routed.kind, StreamKind::AggTrade);
+        assert_eq!(routed.data.get(), r#"{"e":"aggTrade","E":1,"s":"BTCUSDT"}"#);
+        assert!(!routed.is_array());
+
+        let text = r#"{"stream":"!markPrice@arr","data":[{"e":"markPriceUpdate","s":"BTCUSDT"}]}"#;
+        let routed = router.route(text).unwrap();
+        assert_eq!(routed.kind, StreamKind::MarkPrice);
+        assert!(routed.is_array());
+    }
+
+    #[test]
+    fn raw_messages_derive_stream_from_event() {
+        let mut router = StreamRouter::new();
+        // 原始流格式不带流名称，类型交给调用方按 "e" 字段解析
+        let routed = router.route(r#"{"e":"depthUpdate","E":1,"s":"BTCUSDT","U":1,"u":2,"b":[],"a":[]}"#).unwrap();
+        assert_eq!(routed.stream, None);
+        assert_eq!(routed.kind, StreamKind::Unknown);
+        assert_eq!(routed.stream_name(), "btcusdt@depth");
+
+        let routed = router.route(r#"{"e":"kline","E":1,"s":"ETHUSDT","k":{"i":"5m"}}"#).unwrap();
+        assert_eq!(routed.stream_name(), "ethusdt@kline_5m");
+
+        // 强平事件的交易对在订单对象中
+        let routed = router.route(r#"{"e":"forceOrder","E":1,"o":{"s":"BTCUSDT"}}"#).unwrap();
+        assert_eq!(routed.stream_name(), "btcusdt@forceOrder");
+
+        // 原始格式的全市场数组按首个元素的事件类型路由
+        let routed = router.route(r#"[{"e":"markPriceUpdate","s":"BTCUSDT"},{"e":"markPriceUpdate","s":"ETHUSDT"}]"#).unwrap();
+        assert_eq!(routed.kind, StreamKind::MarkPrice);
+        assert!(routed.is_array());
+    }
+
+    #[test]
+    fn spot_book_ticker_is_detected_by_fields() {
+        let mut router = StreamRouter::new();
+        let text = r#"{"u":400900217,"s":"BNBUSDT","b":"25.35190000","B":"31.21000000","a":"25.36520000","A":"40.66000000"}"#;
+        let routed = router.route(text).unwrap();
+        assert_eq!(routed.kind, StreamKind::BookTicker);
+        assert_eq!(routed.stream_name(), "bnbusdt@bookTicker");
+
+        // 缺少任一字段都不按 bookTicker 处理
+        let routed = router.route(r#"{"u":400900217,"s":"BNBUSDT","b":"25.35190000","B":"31.21000000"}"#).unwrap();
+        assert_eq!(routed.kind, StreamKind::Unknown);
+        assert_eq!(routed.stream_name(), "bnbusdt@");
+    }
+
+    #[test]
+    fn invalid_json_is_rejected() {
+        let mut router = StreamRouter::new();
+        assert!(router.route("not json").is_err());
+        assert!(router.route(r#"{"stream":"btcusdt@trade"}"#).is_err());
+    }
+}
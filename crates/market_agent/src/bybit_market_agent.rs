@@ -0,0 +1,507 @@
+// market_agent/bybit_market_agent.rs
+
+use std::collections::HashMap;
+use std::convert::Infallible;
+use std::error::Error;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use chrono::Local;
+use serde::Deserialize;
+use serde_json::{Map, Value};
+use tokio::sync::mpsc;
+
+use common::instrument::InstrumentRegistry;
+use event_engine::event;
+use event_engine::event::{ContractSize, EventPayload, EventType};
+use event_engine::event_dispatcher::QueueEventDispatcherProducer;
+use event_engine::market_data;
+use feeder::bybit_websocket::BybitWebSocketClient;
+use feeder::command::WsCommand;
+use feeder::lifecycle::LifecycleEvent;
+use feeder::websocket::WebSocket;
+
+use crate::binance_market_agent::connection_event;
+use crate::cache::{BybitTickerCache, MarkPriceCache};
+use crate::feed_stats::{check_stats_interval, FeedStats, DEFAULT_STATS_INTERVAL, UNROUTED_STREAM};
+use crate::market_agent::{
+    publish_stats, run_feed, AgentCommand, CommandChannel, CommandHandle, EventEmission, FeedAgent, FeedConnection, FeedMessage,
+    MarketAgent, StopHandle,
+};
+
+/// Bybit 推送消息：{"topic": "...", "type": "snapshot"|"delta", "ts": ..., "data": ...}
+#[derive(Deserialize, Debug)]
+struct BybitPush {
+    topic: String,
+    #[serde(rename = "type", default)]
+    kind: String,
+    #[serde(default)]
+    ts: u64,
+    #[serde(default)]
+    cts: u64,
+    #[serde(default)]
+    cs: u64,
+    data: Value,
+}
+
+/// publicTrade 频道的单笔成交
+#[derive(Deserialize, Debug)]
+struct BybitTrade {
+    #[serde(rename = "T")]
+    trade_time: u64,
+    #[serde(rename = "s")]
+    symbol: String,
+    #[serde(rename = "S")]
+    side: String,
+    #[serde(rename = "v")]
+    size: String,
+    #[serde(rename = "p")]
+    price: String,
+    #[serde(rename = "i", default)]
+    trade_id: String,
+}
+
+/// orderbook.<depth>.<symbol> 频道
+#[derive(Deserialize, Debug)]
+struct BybitBook {
+    #[serde(rename = "s")]
+    symbol: String,
+    #[serde(rename = "b", default)]
+    bids: Vec<(String, String)>,
+    #[serde(rename = "a", default)]
+    asks: Vec<(String, String)>,
+    #[serde(rename = "u")]
+    update_id: u64,
+}
+
+/// Bybit v5 行情 agent：publicTrade 映射为逐笔成交，orderbook 映射为深度（快照 + 增量），
+/// tickers 合并增量后映射为最优挂单与标记价格。流名称即 topic，例如 "orderbook.50.BTCUSDT"
+pub struct BybitMarketAgent {
+    pub ws: BybitWebSocketClient,
+    pub event_producer: QueueEventDispatcherProducer,
+    stop: StopHandle,
+    commands: CommandChannel,
+    /// 各订单簿 topic 最近一次的 u，用于检查增量连续性
+    book_update_ids: HashMap<String, u64>,
+    /// 增量不连续时请求重新订阅以获取新快照
+    resync: mpsc::UnboundedSender<String>,
+    pub mark_prices: MarkPriceCache,
+    /// 反向合约每张面值，键为大写交易对；正向合约数量单位为币，不在其中
+    pub contract_sizes: HashMap<String, ContractSize>,
+    /// 按流统计的消息速率、解析失败、重连与延迟
+    stats: FeedStats,
+    /// FeedStats 事件的分发间隔，通过 set_stats_interval 设置
+    stats_interval: Duration,
+    /// 分发原始事件、归一化事件或两者
+    pub emission: EventEmission,
+    /// tickers 合并后的完整字段
+    pub tickers: BybitTickerCache,
+}
+
+impl BybitMarketAgent {
+    pub fn new(ws: BybitWebSocketClient, event_producer: QueueEventDispatcherProducer) -> Self {
+        let resync = ws.resync_handle();
+        Self {
+            ws,
+            event_producer,
+            stop: StopHandle::new(),
+            commands: CommandChannel::new(),
+            book_update_ids: HashMap::new(),
+            resync,
+            mark_prices: MarkPriceCache::new(),
+            contract_sizes: HashMap::new(),
+            stats: FeedStats::new("bybit"),
+            stats_interval: DEFAULT_STATS_INTERVAL,
+            emission: EventEmission::default(),
+            tickers: BybitTickerCache::new(),
+        }
+    }
+
+    /// 设置 FeedStats 事件的分发间隔，为 0 时返回错误；需在 start 之前调用
+    pub fn set_stats_interval(&mut self, interval: Duration) -> Result<(), Box<dyn Error>> {
+        self.stats_interval = check_stats_interval(interval)?;
+        Ok(())
+    }
+
+    pub fn stats_interval(&self) -> Duration {
+        self.stats_interval
+    }
+
+    pub fn set_contract_size(&mut self, symbol: &str, contract_size: ContractSize) {
+        self.contract_sizes.insert(symbol.to_uppercase(), contract_size);
+    }
+
+    /// 从品种元数据中读取反向合约面值
+    pub fn apply_instruments(&mut self, registry: &InstrumentRegistry) {
+        for instrument in registry.iter() {
+            if let Some(contract_size) = instrument.contract_size {
+                self.set_contract_size(&instrument.symbol, contract_size);
+            }
+        }
+    }
+
+    /// 交易对的合约面值，正向合约、现货或未加载元数据的交易对返回 None
+    pub fn contract_size_of(&self, symbol: &str) -> Option<ContractSize> {
+        self.contract_sizes.get(&symbol.to_uppercase()).copied()
+    }
+
+    /// tickers 最新值缓存，可在 start 之前取出交给其他模块查询
+    pub fn ticker_cache(&self) -> BybitTickerCache {
+        self.tickers.clone()
+    }
+
+    /// 深度转换为归一化的 BookSnapshot（行情流快照）或 BookDelta。
+    /// 档位无法解析时丢弃整条深度，订单簿会因序号不连续等待下一次快照
+    fn normalize_depth(event: &event::DepthEvent) -> Result<(EventType, EventPayload), Box<dyn Error>> {
+        if event.event == "snapshot" {
+            let snapshot = market_data::BookSnapshot::from_depth("bybit", event)?;
+            Ok((EventType::BookSnapshot, EventPayload::BookSnapshot(snapshot)))
+        } else {
+            let delta = market_data::BookDelta::from_depth("bybit", event)?;
+            Ok((EventType::BookDelta, EventPayload::BookDelta(delta)))
+        }
+    }
+
+    fn on_message(&mut self, msg: String, received_timestamp: u128) {
+        let push: BybitPush = match serde_json::from_str(&msg) {
+            Ok(push) => push,
+            Err(e) => {
+                self.stats.record_parse_failure(UNROUTED_STREAM);
+                eprintln!("Bybit 消息解析失败: {} - 原始消息: {}", e, msg);
+                return;
+            }
+        };
+        self.stats.record_message(&push.topic, received_timestamp, Some(push.ts));
+        let channel = push.topic.split('.').next().unwrap_or("");
+        match channel {
+            "publicTrade" => match serde_json::from_value::<Vec<BybitTrade>>(push.data) {
+                Ok(trades) => {
+                    for trade in trades {
+                        let event = trade_event(trade, push.ts, &push.topic, received_timestamp);
+                        self.on_raw_trade(event);
+                    }
+                }
+                Err(e) => {
+                    self.stats.record_parse_failure(&push.topic);
+                    eprintln!("Bybit 成交解析失败: {} - 原始消息: {}", e, msg);
+                }
+            },
+            "orderbook" => match serde_json::from_value::<BybitBook>(push.data) {
+                Ok(book) => self.on_book(&push.topic, &push.kind, push.ts, push.cts, book, received_timestamp),
+                Err(e) => {
+                    self.stats.record_parse_failure(&push.topic);
+                    eprintln!("Bybit 深度解析失败: {} - 原始消息: {}", e, msg);
+                }
+            },
+            "tickers" => match push.data {
+                Value::Object(fields) => self.on_ticker(&push.topic, push.ts, push.cs, fields, received_timestamp),
+                _ => {
+                    self.stats.record_parse_failure(&push.topic);
+                    eprintln!("Bybit ticker 格式错误 - 原始消息: {}", msg);
+                }
+            },
+            _ => {
+                self.stats.record_unknown_event(&push.topic);
+                eprintln!("收到未处理的 Bybit topic [{}] - 原始消息: {}", push.topic, msg);
+            }
+        }
+    }
+
+    /// 快照重置连续性检查，增量要求 u 等于上一条 u + 1；
+    /// u == 1 表示交易所服务重启后推送的快照
+    fn on_book(&mut self, topic: &str, kind: &str, ts: u64, cts: u64, book: BybitBook, received_timestamp: u128) {
+        let is_snapshot = kind == "snapshot" || book.update_id == 1;
+        let previous_update_id = if is_snapshot {
+            0
+        } else {
+            match self.book_update_ids.get(topic) {
+                Some(last) if book.update_id == last + 1 => *last,
+                Some(last) => {
+                    eprintln!("[Bybit] {} 增量不连续: u={} 上一条 u={}，重新订阅", topic, book.update_id, last);
+                    self.book_update_ids.remove(topic);
+                    let _ = self.resync.send(topic.to_string());
+                    return;
+                }
+                // 等待快照
+                None => return,
+            }
+        };
+        self.book_update_ids.insert(topic.to_string(), book.update_id);
+
+        let event = event::DepthEvent {
+            event: if is_snapshot { "snapshot".to_string() } else { "delta".to_string() },
+            event_time: ts,
+            trade_time: cts,
+            symbol: book.symbol,
+            first_update_id: book.update_id,
+            last_update_id: book.update_id,
+            previous_update_id,
+            bids: book.bids,
+            asks: book.asks,
+            received_timestamp,
+            stream: topic.to_string(),
+            ..Default::default()
+        };
+        self.on_depth(event);
+    }
+
+    /// 合约 tickers 先推送快照、之后只推送变化的字段，合并后再分发
+    fn on_ticker(&mut self, topic: &str, ts: u64, cs: u64, fields: Map<String, Value>, received_timestamp: u128) {
+        let symbol = match fields.get("symbol").and_then(|v| v.as_str()) {
+            Some(symbol) => symbol.to_string(),
+            None => return,
+        };
+        let touches_book = ["bid1Price", "bid1Size", "ask1Price", "ask1Size"].iter().any(|k| fields.contains_key(*k));
+        let touches_mark = ["markPrice", "indexPrice", "fundingRate"].iter().any(|k| fields.contains_key(*k));
+
+        let mut merged = self.tickers.get(&symbol).unwrap_or_default();
+        merged.extend(fields);
+        self.tickers.update(&symbol, merged.clone());
+
+        let field = |key: &str| -> String {
+            match merged.get(key) {
+                Some(Value::String(s)) => s.clone(),
+                Some(Value::Number(n)) => n.to_string(),
+                _ => String::new(),
+            }
+        };
+
+        if touches_book && merged.contains_key("bid1Price") {
+            let mut extra = HashMap::new();
+            extra.insert("lastPrice".to_string(), Value::String(field("lastPrice")));
+            let event = event::BookTickerEvent {
+                event: "tickers".to_string(),
+                update_id: cs,
+                event_time: ts,
+                symbol: symbol.clone(),
+                bid_price: field("bid1Price"),
+                bid_qty: field("bid1Size"),
+                ask_price: field("ask1Price"),
+                ask_qty: field("ask1Size"),
+                received_timestamp,
+                stream: topic.to_string(),
+                extra,
+                ..Default::default()
+            };
+            self.on_book_ticker(event);
+        }
+
+        if touches_mark && merged.contains_key("markPrice") {
+            let event = event::MarkPriceEvent {
+                event: "tickers".to_string(),
+                event_time: ts,
+                symbol,
+                mark_price: field("markPrice"),
+                index_price: field("indexPrice"),
+                estimated_settle_price: field("predictedDeliveryPrice"),
+                funding_rate: field("fundingRate"),
+                next_funding_time: field("nextFundingTime").parse().unwrap_or(0),
+                received_timestamp,
+                stream: topic.to_string(),
+                ..Default::default()
+            };
+            self.on_mark_price(event);
+        }
+    }
+}
+
+fn trade_event(trade: BybitTrade, ts: u64, topic: &str, received_timestamp: u128) -> event::TradeEvent {
+    event::TradeEvent {
+        event: "publicTrade".to_string(),
+        event_time: ts,
+        trade_time: trade.trade_time,
+        symbol: trade.symbol,
+        // 合约的成交 id 为 UUID，无法放入数值型 trade_id，原值保留在 raw_trade_id 中
+        trade_id: trade.trade_id.parse().unwrap_or(0),
+        raw_trade_id: trade.trade_id,
+        price: trade.price,
+        quantity: trade.size,
+        // S 为 taker 方向，taker 卖出即买方为挂单方
+        is_buyer_maker: trade.side == "Sell",
+        received_timestamp,
+        stream: topic.to_string(),
+        ..Default::default()
+    }
+}
+
+#[async_trait]
+impl FeedConnection for BybitWebSocketClient {
+    fn placeholder(&self) -> Self {
+        BybitWebSocketClient::with_endpoint(self.endpoint().clone())
+    }
+
+    fn set_message_callback(&mut self, callback: Box<dyn FnMut(String) + Send + 'static>) {
+        BybitWebSocketClient::set_message_callback(self, callback);
+    }
+
+    fn set_lifecycle_callback(&mut self, callback: Box<dyn FnMut(u64, LifecycleEvent) + Send + 'static>) {
+        BybitWebSocketClient::set_lifecycle_callback(self, callback);
+    }
+
+    fn command_handle(&self) -> mpsc::UnboundedSender<WsCommand> {
+        BybitWebSocketClient::command_handle(self)
+    }
+
+    async fn listen_loop(&mut self) -> Result<(), Box<dyn Error>> {
+        WebSocket::listen_loop(self).await
+    }
+}
+
+impl FeedAgent for BybitMarketAgent {
+    type Connection = BybitWebSocketClient;
+    type Custom = Infallible;
+
+    const NAME: &'static str = "BybitMarketAgent";
+
+    fn connection(&mut self) -> &mut BybitWebSocketClient {
+        &mut self.ws
+    }
+
+    fn command_channel(&mut self) -> &mut CommandChannel {
+        &mut self.commands
+    }
+
+    fn stop(&self) -> &StopHandle {
+        &self.stop
+    }
+
+    fn stats_interval(&self) -> Duration {
+        self.stats_interval
+    }
+
+    fn handle_feed_message(&mut self, msg: FeedMessage<Infallible>) {
+        match msg {
+            FeedMessage::Text(text, received_timestamp) => self.on_message(text, received_timestamp),
+            FeedMessage::Lifecycle(connection_id, lifecycle) => {
+                self.stats.record_lifecycle(&lifecycle);
+                // 重连后交易所会重新推送快照
+                if matches!(lifecycle, LifecycleEvent::Disconnected { .. }) {
+                    self.book_update_ids.clear();
+                }
+                let (event_type, event) = connection_event("bybit", connection_id, lifecycle);
+                self.on_connection(event_type, event);
+            }
+            FeedMessage::Custom(never) => match never {},
+        }
+    }
+
+    /// 取消订阅时先清除本地订单簿序号状态，与启动前的 unsubscribe 一致
+    fn before_command(&mut self, command: &AgentCommand) -> Result<(), String> {
+        if let AgentCommand::Unsubscribe(streams, _) = command {
+            for s in streams {
+                self.book_update_ids.remove(s);
+            }
+        }
+        Ok(())
+    }
+
+    fn publish_stats(&mut self) {
+        publish_stats(&self.stats, &mut self.event_producer);
+    }
+}
+
+#[async_trait]
+impl MarketAgent for BybitMarketAgent {
+    async fn start(&mut self) -> Result<(), Box<dyn std::error::Error>> {
+        let (tx, rx) = mpsc::unbounded_channel();
+        run_feed(self, tx, rx).await
+    }
+
+    fn stop_handle(&self) -> StopHandle {
+        self.stop.clone()
+    }
+
+    fn command_handle(&self) -> CommandHandle {
+        self.commands.handle()
+    }
+
+    fn feed_stats(&self) -> FeedStats {
+        self.stats.clone()
+    }
+
+    fn set_event_emission(&mut self, emission: EventEmission) {
+        self.emission = emission;
+    }
+
+    fn on_depth(&mut self, mut event: event::DepthEvent) {
+        event.contract_size = self.contract_size_of(&event.symbol);
+        // 行情流推送的快照单独作为 BookSnapshot 分发
+        let normalized = self.emission.normalize(&self.stats, &event.stream, || Self::normalize_depth(&event));
+        if self.emission.raw() {
+            self.event_producer.fire(EventType::Depth, EventPayload::Depth(event));
+        }
+        if let Some((event_type, payload)) = normalized {
+            self.event_producer.fire(event_type, payload);
+        }
+    }
+
+    fn on_trade(&mut self, mut event: event::AggTradeEvent) {
+        event.contract_size = self.contract_size_of(&event.symbol);
+        let trade = self.emission.normalize(&self.stats, &event.stream, || market_data::Trade::from_agg_trade("bybit", &event));
+        if self.emission.raw() {
+            self.event_producer.fire(EventType::AggTrade, EventPayload::AggTrade(event));
+        }
+        if let Some(trade) = trade {
+            self.event_producer.fire(EventType::MarketTrade, EventPayload::MarketTrade(trade));
+        }
+    }
+
+    fn on_raw_trade(&mut self, mut event: event::TradeEvent) {
+        event.contract_size = self.contract_size_of(&event.symbol);
+        let trade = self.emission.normalize(&self.stats, &event.stream, || market_data::Trade::from_trade("bybit", &event));
+        if self.emission.raw() {
+            self.event_producer.fire(EventType::Trade, EventPayload::Trade(event));
+        }
+        if let Some(trade) = trade {
+            self.event_producer.fire(EventType::MarketTrade, EventPayload::MarketTrade(trade));
+        }
+    }
+
+    fn on_book_ticker(&mut self, mut event: event::BookTickerEvent) {
+        event.contract_size = self.contract_size_of(&event.symbol);
+        let bbo = self.emission.normalize(&self.stats, &event.stream, || market_data::Bbo::from_book_ticker("bybit", &event));
+        if self.emission.raw() {
+            self.event_producer.fire(EventType::BookTicker, EventPayload::BookTicker(event));
+        }
+        if let Some(bbo) = bbo {
+            self.event_producer.fire(EventType::Bbo, EventPayload::Bbo(bbo));
+        }
+    }
+
+    fn on_mark_price(&mut self, event: event::MarkPriceEvent) {
+        self.mark_prices.update(&event.symbol, event.clone());
+        self.event_producer.fire(EventType::MarkPrice, EventPayload::MarkPrice(event));
+    }
+
+    fn mark_price_cache(&self) -> MarkPriceCache {
+        self.mark_prices.clone()
+    }
+
+    fn on_liquidation(&mut self, event: event::LiquidationEvent) {
+        self.event_producer.fire(EventType::Liquidation, EventPayload::Liquidation(event));
+    }
+
+    fn on_connection(&mut self, event_type: EventType, event: event::ConnectionEvent) {
+        println!("[{}] Bybit 连接 {} {:?} {}", Local::now().format("%H:%M:%S"), event.connection_id, event_type, event.reason);
+        self.event_producer.fire(event_type, EventPayload::Connection(event));
+    }
+
+    fn on_kline(&mut self, event: event::KlineEvent) {
+        self.event_producer.fire(EventType::Kline, EventPayload::Kline(event));
+    }
+
+    async fn subscribe(&mut self, streams: Vec<&str>) -> Result<(), Box<dyn Error>> {
+        self.ws.subscribe(streams).await
+    }
+
+    async fn unsubscribe(&mut self, streams: Vec<&str>) -> Result<(), Box<dyn Error>> {
+        for s in &streams {
+            self.book_update_ids.remove(*s);
+        }
+        self.ws.unsubscribe(streams).await
+    }
+
+    async fn list_subscriptions(&mut self) -> Result<Vec<String>, Box<dyn Error>> {
+        self.ws.list_subscriptions().await
+    }
+}
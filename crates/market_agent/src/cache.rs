@@ -0,0 +1,65 @@
+// market_agent/cache.rs
+
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+
+use event_engine::event::MarkPriceEvent;
+
+/// 按交易对保存最新值的缓存，可克隆后在多个模块间共享，
+/// agent 写入，策略等模块随时读取，不必订阅事件自己维护状态
+#[derive(Debug)]
+pub struct LatestValueCache<T> {
+    inner: Arc<RwLock<HashMap<String, T>>>,
+}
+
+impl<T> Clone for LatestValueCache<T> {
+    fn clone(&self) -> Self {
+        Self { inner: Arc::clone(&self.inner) }
+    }
+}
+
+impl<T> Default for LatestValueCache<T> {
+    fn default() -> Self {
+        Self { inner: Arc::new(RwLock::new(HashMap::new())) }
+    }
+}
+
+impl<T: Clone> LatestValueCache<T> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 写入最新值，交易对统一转为大写
+    pub fn update(&self, symbol: &str, value: T) {
+        self.inner.write().unwrap().insert(symbol.to_uppercase(), value);
+    }
+
+    /// 查询某个交易对的最新值（不区分大小写）
+    pub fn get(&self, symbol: &str) -> Option<T> {
+        self.inner.read().unwrap().get(&symbol.to_uppercase()).cloned()
+    }
+
+    /// 所有交易对的最新值
+    pub fn snapshot(&self) -> HashMap<String, T> {
+        self.inner.read().unwrap().clone()
+    }
+
+    /// 已缓存的交易对
+    pub fn symbols(&self) -> Vec<String> {
+        self.inner.read().unwrap().keys().cloned().collect()
+    }
+
+    pub fn len(&self) -> usize {
+        self.inner.read().unwrap().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+/// 标记价格、指数价格、资金费率的最新值
+pub type MarkPriceCache = LatestValueCache<MarkPriceEvent>;
+
+/// Bybit tickers 频道合并后的完整字段（增量推送只包含变化的字段）
+pub type BybitTickerCache = LatestValueCache<serde_json::Map<String, serde_json::Value>>;
@@ -0,0 +1,253 @@
+// market_agent/feed_stats.rs
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use event_engine::event::{FeedStatsEvent, StreamStats};
+use feeder::lifecycle::LifecycleEvent;
+
+/// 默认的统计分发间隔
+pub const DEFAULT_STATS_INTERVAL: Duration = Duration::from_secs(10);
+
+/// 统计分发间隔的校验：tokio::time::interval 不接受 0，由各 agent 的 set_stats_interval 调用
+pub fn check_stats_interval(interval: Duration) -> Result<Duration, String> {
+    if interval.is_zero() {
+        return Err("FeedStats 分发间隔必须大于 0".to_string());
+    }
+    Ok(interval)
+}
+
+/// 无法确定流名称的消息（整条消息解析失败）记在该名称下
+pub const UNROUTED_STREAM: &str = "unrouted";
+
+/// 每个流每个窗口最多保留的延迟样本数，超出后覆盖最早的样本
+const MAX_LATENCY_SAMPLES: usize = 10_000;
+
+fn get_timestamp_ms() -> u128 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .expect("Time went backwards")
+        .as_millis()
+}
+
+#[derive(Default)]
+struct StreamCounters {
+    messages: u64,
+    window_messages: u64,
+    parse_failures: u64,
+    unknown_events: u64,
+    reconnects: u64,
+    last_message_ms: Option<u128>,
+    /// 窗口内的延迟样本（毫秒）
+    latencies: Vec<f64>,
+    window_latency_samples: u64,
+}
+
+struct FeedStatsState {
+    window_start_ms: u128,
+    disconnects: u64,
+    streams: HashMap<String, StreamCounters>,
+}
+
+impl FeedStatsState {
+    /// 流的计数器；热路径上流名称几乎总是已存在，只在首次出现时分配 key
+    fn counters(&mut self, stream: &str) -> &mut StreamCounters {
+        if !self.streams.contains_key(stream) {
+            self.streams.insert(stream.to_string(), StreamCounters::default());
+        }
+        self.streams.get_mut(stream).unwrap()
+    }
+}
+
+/// 按流统计消息数、解析失败、重连与延迟。可克隆后在多个模块间共享：
+/// agent 在处理消息时写入，外部随时通过 snapshot 读取
+#[derive(Clone)]
+pub struct FeedStats {
+    exchange: String,
+    inner: Arc<Mutex<FeedStatsState>>,
+}
+
+impl FeedStats {
+    pub fn new(exchange: &str) -> Self {
+        Self {
+            exchange: exchange.to_string(),
+            inner: Arc::new(Mutex::new(FeedStatsState {
+                window_start_ms: get_timestamp_ms(),
+                disconnects: 0,
+                streams: HashMap::new(),
+            })),
+        }
+    }
+
+    /// 记录一条消息；received_timestamp 为本地接收时间（微秒），event_time 为交易所事件时间（毫秒）
+    pub fn record_message(&self, stream: &str, received_timestamp: u128, event_time: Option<u64>) {
+        let mut state = self.inner.lock().unwrap();
+        let counters = state.counters(stream);
+        counters.messages += 1;
+        counters.window_messages += 1;
+        counters.last_message_ms = Some(received_timestamp / 1000);
+        if let Some(event_time) = event_time.filter(|t| *t > 0) {
+            let latency = received_timestamp as f64 / 1000.0 - event_time as f64;
+            let slot = counters.window_latency_samples as usize % MAX_LATENCY_SAMPLES;
+            if slot < counters.latencies.len() {
+                counters.latencies[slot] = latency;
+            } else {
+                counters.latencies.push(latency);
+            }
+            counters.window_latency_samples += 1;
+        }
+    }
+
+    pub fn record_parse_failure(&self, stream: &str) {
+        let mut state = self.inner.lock().unwrap();
+        state.counters(stream).parse_failures += 1;
+    }
+
+    pub fn record_unknown_event(&self, stream: &str) {
+        let mut state = self.inner.lock().unwrap();
+        state.counters(stream).unknown_events += 1;
+    }
+
+    /// 断线计入整个代理，重连后恢复的订阅计入对应的流
+    pub fn record_lifecycle(&self, lifecycle: &LifecycleEvent) {
+        let mut state = self.inner.lock().unwrap();
+        match lifecycle {
+            LifecycleEvent::Disconnected { .. } => state.disconnects += 1,
+            LifecycleEvent::Resubscribed { streams } => {
+                for stream in streams {
+                    state.counters(stream).reconnects += 1;
+                }
+            }
+            LifecycleEvent::Connected { .. } | LifecycleEvent::Stale(_) => {}
+        }
+    }
+
+    /// 当前统计快照，不影响窗口
+    pub fn snapshot(&self) -> FeedStatsEvent {
+        let state = self.inner.lock().unwrap();
+        build_snapshot(&self.exchange, &state)
+    }
+
+    /// 取出当前窗口的快照并开始新窗口，由 agent 定期分发时调用
+    pub fn take_window(&self) -> FeedStatsEvent {
+        let mut state = self.inner.lock().unwrap();
+        let snapshot = build_snapshot(&self.exchange, &state);
+        state.window_start_ms = get_timestamp_ms();
+        for counters in state.streams.values_mut() {
+            counters.window_messages = 0;
+            counters.window_latency_samples = 0;
+            counters.latencies.clear();
+        }
+        snapshot
+    }
+}
+
+fn build_snapshot(exchange: &str, state: &FeedStatsState) -> FeedStatsEvent {
+    let now = get_timestamp_ms();
+    let window_ms = now.saturating_sub(state.window_start_ms) as u64;
+    let mut streams: Vec<StreamStats> = state
+        .streams
+        .iter()
+        .map(|(stream, counters)| {
+            let mut latencies = counters.latencies.clone();
+            latencies.sort_by(|a, b| a.total_cmp(b));
+            StreamStats {
+                stream: stream.clone(),
+                messages: counters.messages,
+                message_rate: if window_ms > 0 { counters.window_messages as f64 * 1000.0 / window_ms as f64 } else { 0.0 },
+                parse_failures: counters.parse_failures,
+                unknown_events: counters.unknown_events,
+                reconnects: counters.reconnects,
+                last_message_age_ms: counters.last_message_ms.map(|t| now.saturating_sub(t) as u64),
+                latency_samples: counters.window_latency_samples,
+                latency_p50_ms: percentile(&latencies, 0.50),
+                latency_p90_ms: percentile(&latencies, 0.90),
+                latency_p99_ms: percentile(&latencies, 0.99),
+                latency_max_ms: latencies.last().copied(),
+            }
+        })
+        .collect();
+    streams.sort_by(|a, b| a.stream.cmp(&b.stream));
+    FeedStatsEvent {
+        exchange: exchange.to_string(),
+        timestamp: now,
+        window_ms,
+        disconnects: state.disconnects,
+        streams,
+    }
+}
+
+/// 已排序样本的分位数（最近秩法）
+fn percentile(sorted: &[f64], p: f64) -> Option<f64> {
+    if sorted.is_empty() {
+        return None;
+    }
+    let rank = ((sorted.len() as f64 * p).ceil() as usize).clamp(1, sorted.len());
+    Some(sorted[rank - 1])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn percentile_uses_nearest_rank() {
+        assert_eq!(percentile(&[], 0.5), None);
+        assert_eq!(percentile(&[7.0], 0.99), Some(7.0));
+        assert_eq!(percentile(&[1.0, 2.0, 3.0], 0.5), Some(2.0));
+        let samples: Vec<f64> = (1..=100).map(|v| v as f64).collect();
+        assert_eq!(percentile(&samples, 0.50), Some(50.0));
+        assert_eq!(percentile(&samples, 0.90), Some(90.0));
+        assert_eq!(percentile(&samples, 0.99), Some(99.0));
+        assert_eq!(percentile(&samples, 1.0), Some(100.0));
+        assert_eq!(percentile(&samples, 0.0), Some(1.0));
+    }
+
+    #[test]
+    fn take_window_resets_window_but_keeps_totals() {
+        let stats = FeedStats::new("binance");
+        let now = get_timestamp_ms() as u64;
+        for latency in [5u64, 10, 20] {
+            let received_us = (now + latency) as u128 * 1000;
+            stats.record_message("btcusdt@aggTrade", received_us, Some(now));
+        }
+        // 没有事件时间的消息只计数，不计入延迟样本
+        stats.record_message("btcusdt@aggTrade", now as u128 * 1000, None);
+        stats.record_parse_failure("btcusdt@aggTrade");
+        stats.record_unknown_event(UNROUTED_STREAM);
+
+        let window = stats.take_window();
+        let stream = window.stream("btcusdt@aggTrade").unwrap();
+        assert_eq!(stream.messages, 4);
+        assert_eq!(stream.latency_samples, 3);
+        assert_eq!(stream.latency_p50_ms, Some(10.0));
+        assert_eq!(stream.latency_max_ms, Some(20.0));
+        assert_eq!(window.total_errors(), 2);
+
+        let next = stats.take_window();
+        let stream = next.stream("btcusdt@aggTrade").unwrap();
+        assert_eq!(stream.messages, 4);
+        assert_eq!(stream.message_rate, 0.0);
+        assert_eq!(stream.latency_samples, 0);
+        assert_eq!(stream.latency_p50_ms, None);
+        assert_eq!(next.total_errors(), 2);
+    }
+
+    #[test]
+    fn lifecycle_counts_disconnects_and_resubscribed_streams() {
+        let stats = FeedStats::new("binance");
+        stats.record_lifecycle(&LifecycleEvent::Disconnected { reason: "test".to_string(), streams: Vec::new() });
+        stats.record_lifecycle(&LifecycleEvent::Resubscribed { streams: vec!["a".to_string(), "b".to_string()] });
+        let snapshot = stats.snapshot();
+        assert_eq!(snapshot.disconnects, 1);
+        assert_eq!(snapshot.stream("a").unwrap().reconnects, 1);
+        assert_eq!(snapshot.stream("b").unwrap().reconnects, 1);
+    }
+
+    #[test]
+    fn stats_interval_must_be_positive() {
+        assert!(check_stats_interval(Duration::ZERO).is_err());
+        assert_eq!(check_stats_interval(Duration::from_millis(1)), Ok(Duration::from_millis(1)));
+    }
+}
@@ -1,2 +1,9 @@
 pub mod market_agent;
-pub mod binance_market_agent;
\ No newline at end of file
+pub mod binance_market_agent;
+pub mod binance_stream;
+pub mod cache;
+pub mod okx_market_agent;
+pub mod bybit_market_agent;
+pub mod user_data_agent;
+pub mod agg_trade_gap;
+pub mod feed_stats;
\ No newline at end of file
@@ -2,25 +2,52 @@
 
 use async_trait::async_trait;
 use feeder::websocket::BinanceWebSocketClient;
+use feeder::command::WsCommand;
+use feeder::lifecycle::LifecycleEvent;
 use event_engine::event;
-use event_engine::event::EventType;
+use event_engine::event::{EventPayload, EventType};
 use event_engine::event_dispatcher::QueueEventDispatcherProducer;
 use std::sync::Arc;
-use tokio::sync::Mutex;
+use std::time::Duration;
+use tokio::sync::{mpsc, oneshot, watch, Mutex};
 use std::error::Error;
+use crate::cache::MarkPriceCache;
+use crate::feed_stats::FeedStats;
 
 /// MarketAgent 定义了市场代理所需实现的接口
-#[async_trait(?Send)]
+/// 实现需要是 Send 的，以便放入 Box<dyn MarketAgent + Send> 并在多线程运行时中启动
+#[async_trait]
 pub trait MarketAgent {
-    // 启动市场代理 单生产者单消费者下，不需要声明为Send
+    // 启动市场代理，运行到连接彻底失败或收到停止信号为止；返回后可再次调用以重启
     async fn start(&mut self) -> Result<(), Box<dyn std::error::Error>>;
 
+    // 停止句柄，可在其他任务或线程中让正在运行的 start 返回
+    fn stop_handle(&self) -> StopHandle;
+
     fn on_depth(&mut self, event: event::DepthEvent);
 
     fn on_trade(&mut self, event: event::AggTradeEvent);
 
-    // // 收到 K 线数据时的回调，将原始数据解析后入队事件
-    // async fn on_kline(&self, raw_data: String);
+    // 收到逐笔成交（<symbol>@trade）时的回调，与归集成交 on_trade 区分
+    fn on_raw_trade(&mut self, event: event::TradeEvent);
+
+    // 收到最优挂单（bookTicker）时的回调
+    fn on_book_ticker(&mut self, event: event::BookTickerEvent);
+
+    // 收到标记价格 / 资金费率时的回调，同时更新最新值缓存
+    fn on_mark_price(&mut self, event: event::MarkPriceEvent);
+
+    // 标记价格最新值缓存，可在 start 之前取出交给其他模块查询
+    fn mark_price_cache(&self) -> MarkPriceCache;
+
+    // 收到强平订单时的回调
+    fn on_liquidation(&mut self, event: event::LiquidationEvent);
+
+    // 连接生命周期事件（Connected / Disconnected / Resubscribed / Stale）
+    fn on_connection(&mut self, event_type: EventType, event: event::ConnectionEvent);
+
+    // 收到 K 线数据时的回调（包括未完结的 K 线，可通过 is_closed 区分）
+    fn on_kline(&mut self, event: event::KlineEvent);
 
     // // 收到 ticker 数据时的回调，将原始数据解析后入队事件
     // async fn on_ticker(&self, raw_data: String);
@@ -31,6 +58,314 @@ pub trait MarketAgent {
     // 订阅指定的流
     async fn subscribe(&mut self, streams: Vec<&str>) -> Result<(), Box<dyn Error>>;
 
-    // // 取消订阅指定的流
-    // async fn unsubscribe(&self, streams: Vec<&str>) -> Result<(), Box<dyn Error + Send>>;
+    // 取消订阅指定的流
+    async fn unsubscribe(&mut self, streams: Vec<&str>) -> Result<(), Box<dyn Error>>;
+
+    // 查询交易所侧已确认的订阅列表
+    async fn list_subscriptions(&mut self) -> Result<Vec<String>, Box<dyn Error>>;
+
+    // 命令句柄，start 运行期间可通过它订阅、取消订阅和查询订阅
+    fn command_handle(&self) -> CommandHandle;
+
+    // 行情统计句柄，可在 start 之前取出交给其他模块查询；start 期间按 stats_interval 分发 FeedStats 事件
+    fn feed_stats(&self) -> FeedStats;
+
+    // 设置行情事件的分发形式（原始、归一化或两者），需在 start 之前调用
+    fn set_event_emission(&mut self, emission: EventEmission);
+
+    // 当前行情统计快照：各流的消息速率、解析失败、未识别事件、重连次数、最后消息距今时间与延迟分位数
+    fn stats(&self) -> event::FeedStatsEvent {
+        self.feed_stats().snapshot()
+    }
+}
+
+
+/// 行情事件的分发形式。归一化事件（MarketTrade、BookDelta / BookSnapshot、Bbo）与原始事件
+/// （AggTrade / Trade、Depth、BookTicker）来自同一条消息，两者都分发会让事件队列的压力翻倍，
+/// 因此默认只分发原始事件，订阅归一化事件的处理器（例如订单簿引擎）需要显式开启
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum EventEmission {
+    /// 只分发交易所原始事件
+    #[default]
+    Raw,
+    /// 只分发归一化事件
+    Normalized,
+    /// 两者都分发
+    Both,
+}
+
+impl EventEmission {
+    /// 是否分发原始事件
+    pub fn raw(&self) -> bool {
+        matches!(self, EventEmission::Raw | EventEmission::Both)
+    }
+
+    /// 是否分发归一化事件
+    pub fn normalized(&self) -> bool {
+        matches!(self, EventEmission::Normalized | EventEmission::Both)
+    }
+
+    /// 开启归一化时构造归一化事件。价格或数量无法解析的记为该流的解析失败并返回 None，原始事件照常分发
+    pub(crate) fn normalize<T>(
+        &self,
+        stats: &FeedStats,
+        stream: &str,
+        build: impl FnOnce() -> Result<T, Box<dyn Error>>,
+    ) -> Option<T> {
+        if !self.normalized() {
+            return None;
+        }
+        match build() {
+            Ok(normalized) => Some(normalized),
+            Err(e) => {
+                stats.record_parse_failure(stream);
+                eprintln!("[{}] 归一化事件丢弃: {}", stream, e);
+                None
+            }
+        }
+    }
+}
+
+/// 市场代理的停止句柄，可克隆后交给其他任务
+#[derive(Clone)]
+pub struct StopHandle {
+    sender: Arc<watch::Sender<bool>>,
+}
+
+impl StopHandle {
+    pub fn new() -> Self {
+        let (sender, _) = watch::channel(false);
+        Self { sender: Arc::new(sender) }
+    }
+
+    /// 请求停止
+    pub fn stop(&self) {
+        self.sender.send_replace(true);
+    }
+
+    /// 是否已请求停止
+    pub fn is_stopped(&self) -> bool {
+        *self.sender.borrow()
+    }
+
+    /// 取得接收端，由 start 在开始时调用；start 之前已请求的停止会让 changed() 立即返回
+    pub fn subscribe(&self) -> watch::Receiver<bool> {
+        let mut receiver = self.sender.subscribe();
+        if *receiver.borrow() {
+            receiver.mark_changed();
+        }
+        receiver
+    }
+
+    /// 重置为运行状态，由 start 在返回前调用，之后可以再次 start
+    pub fn reset(&self) {
+        self.sender.send_replace(false);
+    }
+}
+
+impl Default for StopHandle {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+
+/// 发给运行中市场代理的命令，执行结果通过 oneshot 返回。
+/// 代理在 start 期间把命令转交给连接的监听循环执行，监听循环不会因命令而中断
+pub type AgentCommand = WsCommand;
+
+/// 市场代理的命令句柄，可克隆后交给其他任务
+#[derive(Clone)]
+pub struct CommandHandle {
+    sender: mpsc::UnboundedSender<AgentCommand>,
+}
+
+impl CommandHandle {
+    /// 订阅指定的流，交易所确认后返回
+    pub async fn subscribe(&self, streams: Vec<&str>) -> Result<(), Box<dyn Error>> {
+        let (reply, response) = oneshot::channel();
+        let streams = streams.iter().map(|s| s.to_string()).collect();
+        self.send(AgentCommand::Subscribe(streams, reply))?;
+        let result = response.await.map_err(|_| "市场代理已退出，命令未执行")?;
+        result.map_err(|e| e.into())
+    }
+
+    /// 取消订阅指定的流
+    pub async fn unsubscribe(&self, streams: Vec<&str>) -> Result<(), Box<dyn Error>> {
+        let (reply, response) = oneshot::channel();
+        let streams = streams.iter().map(|s| s.to_string()).collect();
+        self.send(AgentCommand::Unsubscribe(streams, reply))?;
+        let result = response.await.map_err(|_| "市场代理已退出，命令未执行")?;
+        result.map_err(|e| e.into())
+    }
+
+    /// 查询交易所侧已确认的订阅列表
+    pub async fn list_subscriptions(&self) -> Result<Vec<String>, Box<dyn Error>> {
+        let (reply, response) = oneshot::channel();
+        self.send(AgentCommand::ListSubscriptions(reply))?;
+        let result = response.await.map_err(|_| "市场代理已退出，命令未执行")?;
+        result.map_err(|e| e.into())
+    }
+
+    fn send(&self, command: AgentCommand) -> Result<(), Box<dyn Error>> {
+        self.sender
+            .send(command)
+            .map_err(|_| "市场代理已被释放".into())
+    }
+}
+
+/// 代理持有的命令通道。命令只在 start 运行期间处理（转交给连接的监听循环）：
+/// 未启动时发出的命令会排队，到下次 start 时再执行
+pub struct CommandChannel {
+    sender: mpsc::UnboundedSender<AgentCommand>,
+    receiver: Option<mpsc::UnboundedReceiver<AgentCommand>>,
+}
+
+impl CommandChannel {
+    pub fn new() -> Self {
+        let (sender, receiver) = mpsc::unbounded_channel();
+        Self { sender, receiver: Some(receiver) }
+    }
+
+    pub fn handle(&self) -> CommandHandle {
+        CommandHandle { sender: self.sender.clone() }
+    }
+
+    /// start 开始时取出接收端，结束时通过 restore 放回
+    pub fn take_receiver(&mut self) -> Result<mpsc::UnboundedReceiver<AgentCommand>, Box<dyn Error>> {
+        self.receiver.take().ok_or_else(|| "市场代理已在运行".into())
+    }
+
+    pub fn restore(&mut self, receiver: mpsc::UnboundedReceiver<AgentCommand>) {
+        self.receiver = Some(receiver);
+    }
+}
+
+impl Default for CommandChannel {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+
+/// 接收时间戳（微秒）
+pub(crate) fn get_timestamp_us() -> u128 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap()
+        .as_micros()
+}
+
+/// 分发当前窗口的行情统计并开始新窗口
+pub(crate) fn publish_stats(stats: &FeedStats, event_producer: &mut QueueEventDispatcherProducer) {
+    let stats = stats.take_window();
+    event_producer.fire(EventType::FeedStats, EventPayload::FeedStats(stats));
+}
+
+/// WebSocket 回调转发给 agent 的消息；Custom 为代理自己投递的消息（例如币安的补录结果）
+pub(crate) enum FeedMessage<E> {
+    /// 原始文本消息与接收时间戳（微秒）
+    Text(String, u128),
+    /// 连接编号与生命周期事件
+    Lifecycle(u64, LifecycleEvent),
+    Custom(E),
+}
+
+/// 代理运行期间独占的连接：币安连接池、OKX / Bybit 客户端
+#[async_trait]
+pub(crate) trait FeedConnection: Send + Sized {
+    /// start 期间留在代理中的占位连接
+    fn placeholder(&self) -> Self;
+
+    fn set_message_callback(&mut self, callback: Box<dyn FnMut(String) + Send + 'static>);
+
+    fn set_lifecycle_callback(&mut self, callback: Box<dyn FnMut(u64, LifecycleEvent) + Send + 'static>);
+
+    /// 命令交给监听循环在两次读取之间执行，监听循环不会被打断
+    fn command_handle(&self) -> mpsc::UnboundedSender<WsCommand>;
+
+    async fn listen_loop(&mut self) -> Result<(), Box<dyn Error>>;
+}
+
+/// 各交易所代理接入 run_feed 所需的状态与回调
+pub(crate) trait FeedAgent: Send {
+    type Connection: FeedConnection;
+    /// 代理自己投递的消息类型，没有时使用 Infallible
+    type Custom: Send + 'static;
+
+    /// 日志前缀
+    const NAME: &'static str;
+
+    fn connection(&mut self) -> &mut Self::Connection;
+
+    fn command_channel(&mut self) -> &mut CommandChannel;
+
+    fn stop(&self) -> &StopHandle;
+
+    fn stats_interval(&self) -> Duration;
+
+    fn handle_feed_message(&mut self, msg: FeedMessage<Self::Custom>);
+
+    /// 运行期间的命令转交给监听循环之前的检查与本地状态清理，返回 Err 时拒绝该命令
+    fn before_command(&mut self, _command: &AgentCommand) -> Result<(), String> {
+        Ok(())
+    }
+
+    fn publish_stats(&mut self);
+}
+
+/// 各交易所代理共用的运行循环，直到连接彻底失败或收到停止信号为止。
+/// WebSocket 回调只负责把消息转发到通道，解析与事件分发在本任务中进行，回调无需持有代理；
+/// 监听循环独占连接，事件处理需要 &mut agent，因此运行期间把连接移出，结束后放回。
+/// tx 与 rx 由调用方创建，代理可以保留 tx 投递自己的消息
+pub(crate) async fn run_feed<A: FeedAgent>(
+    agent: &mut A,
+    tx: mpsc::UnboundedSender<FeedMessage<A::Custom>>,
+    mut rx: mpsc::UnboundedReceiver<FeedMessage<A::Custom>>,
+) -> Result<(), Box<dyn Error>> {
+    let text_tx = tx.clone();
+    agent.connection().set_message_callback(Box::new(move |msg: String| {
+        let _ = text_tx.send(FeedMessage::Text(msg, get_timestamp_us()));
+    }));
+    agent.connection().set_lifecycle_callback(Box::new(move |connection_id: u64, lifecycle: LifecycleEvent| {
+        let _ = tx.send(FeedMessage::Lifecycle(connection_id, lifecycle));
+    }));
+    let mut command_rx = agent.command_channel().take_receiver()?;
+    let mut stop_rx = agent.stop().subscribe();
+
+    let placeholder = agent.connection().placeholder();
+    let mut ws = std::mem::replace(agent.connection(), placeholder);
+    // 第一次 tick 立即触发，跳过
+    let mut stats_timer = tokio::time::interval(agent.stats_interval());
+    stats_timer.tick().await;
+    let ws_commands = ws.command_handle();
+    let result = {
+        let listen = ws.listen_loop();
+        tokio::pin!(listen);
+        loop {
+            tokio::select! {
+                res = &mut listen => break res.map_err(|e| e.to_string()),
+                Some(msg) = rx.recv() => agent.handle_feed_message(msg),
+                Some(command) = command_rx.recv() => {
+                    if let Err(e) = agent.before_command(&command) {
+                        command.reject(e);
+                    } else if let Err(mpsc::error::SendError(command)) = ws_commands.send(command) {
+                        command.reject("连接已释放，命令未执行".to_string());
+                    }
+                }
+                _ = stats_timer.tick() => agent.publish_stats(),
+                _ = stop_rx.changed() => {
+                    println!("[{}] 收到停止信号", A::NAME);
+                    break Ok(());
+                }
+            }
+        }
+    };
+    while let Ok(msg) = rx.try_recv() {
+        agent.handle_feed_message(msg);
+    }
+    *agent.connection() = ws;
+    agent.command_channel().restore(command_rx);
+    agent.stop().reset();
+    result.map_err(|e| e.into())
 }
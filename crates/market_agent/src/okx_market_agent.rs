@@ -0,0 +1,625 @@
+// market_agent/okx_market_agent.rs
+
+use std::collections::{BTreeMap, HashMap};
+use std::convert::Infallible;
+use std::error::Error;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use chrono::Local;
+use ordered_float::OrderedFloat;
+use serde::Deserialize;
+use serde_json::Value;
+use tokio::sync::mpsc;
+
+use common::instrument::InstrumentRegistry;
+use event_engine::event;
+use event_engine::event::{ContractSize, EventPayload, EventType};
+use event_engine::event_dispatcher::QueueEventDispatcherProducer;
+use event_engine::market_data;
+use feeder::command::WsCommand;
+use feeder::lifecycle::LifecycleEvent;
+use feeder::okx_websocket::{stream_of, OkxWebSocketClient};
+use feeder::websocket::WebSocket;
+
+use crate::binance_market_agent::connection_event;
+use crate::cache::MarkPriceCache;
+use crate::feed_stats::{check_stats_interval, FeedStats, DEFAULT_STATS_INTERVAL, UNROUTED_STREAM};
+use crate::market_agent::{
+    publish_stats, run_feed, AgentCommand, CommandChannel, CommandHandle, EventEmission, FeedAgent, FeedConnection, FeedMessage,
+    MarketAgent, StopHandle,
+};
+
+/// 参与校验和计算的档位数
+const CHECKSUM_DEPTH: usize = 25;
+
+/// OKX 推送消息：{"arg": {"channel": ..., "instId": ...}, "action": "snapshot"|"update", "data": [...]}
+#[derive(Deserialize, Debug)]
+struct OkxPush {
+    arg: Value,
+    #[serde(default)]
+    action: Option<String>,
+    data: Vec<Value>,
+}
+
+/// trades 频道：同一 taker 订单的多笔成交会合并推送，对应归集成交
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+struct OkxTrade {
+    inst_id: String,
+    trade_id: String,
+    px: String,
+    sz: String,
+    side: String,
+    ts: String,
+}
+
+/// books 系列频道，档位格式为 [价格, 数量, 已废弃字段, 订单数]
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+struct OkxBookData {
+    #[serde(default)]
+    asks: Vec<Vec<String>>,
+    #[serde(default)]
+    bids: Vec<Vec<String>>,
+    ts: String,
+    #[serde(default)]
+    checksum: Option<i64>,
+    #[serde(default)]
+    prev_seq_id: Option<i64>,
+    #[serde(default)]
+    seq_id: Option<i64>,
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+struct OkxTicker {
+    inst_id: String,
+    #[serde(default)]
+    last: String,
+    #[serde(default)]
+    last_sz: String,
+    #[serde(default)]
+    ask_px: String,
+    #[serde(default)]
+    ask_sz: String,
+    #[serde(default)]
+    bid_px: String,
+    #[serde(default)]
+    bid_sz: String,
+    ts: String,
+}
+
+/// 本地维护的 OKX 订单簿，仅用于序号与校验和验证，保留原始字符串以便拼接校验串
+#[derive(Default)]
+struct OkxBook {
+    bids: BTreeMap<OrderedFloat<f64>, (String, String)>,
+    asks: BTreeMap<OrderedFloat<f64>, (String, String)>,
+    seq_id: i64,
+}
+
+impl OkxBook {
+    fn apply_levels(side: &mut BTreeMap<OrderedFloat<f64>, (String, String)>, levels: &[(String, String)]) {
+        for (px, sz) in levels {
+            let price = OrderedFloat(px.parse::<f64>().unwrap_or(0.0));
+            if sz.parse::<f64>().unwrap_or(0.0) == 0.0 {
+                side.remove(&price);
+            } else {
+                side.insert(price, (px.clone(), sz.clone()));
+            }
+        }
+    }
+
+    fn apply(&mut self, bids: &[(String, String)], asks: &[(String, String)]) {
+        Self::apply_levels(&mut self.bids, bids);
+        Self::apply_levels(&mut self.asks, asks);
+    }
+
+    /// 买卖前 25 档交替拼接为 "买价:买量:卖价:卖量:..."，一侧不足时只拼另一侧，
+    /// 取 CRC32 并按有符号 32 位整数比较
+    fn checksum(&self) -> i32 {
+        let bids: Vec<&(String, String)> = self.bids.values().rev().take(CHECKSUM_DEPTH).collect();
+        let asks: Vec<&(String, String)> = self.asks.values().take(CHECKSUM_DEPTH).collect();
+        let mut parts: Vec<&str> = Vec::with_capacity(CHECKSUM_DEPTH * 4);
+        for i in 0..CHECKSUM_DEPTH {
+            if let Some((px, sz)) = bids.get(i) {
+                parts.push(px);
+                parts.push(sz);
+            }
+            if let Some((px, sz)) = asks.get(i) {
+                parts.push(px);
+                parts.push(sz);
+            }
+        }
+        crc32fast::hash(parts.join(":").as_bytes()) as i32
+    }
+}
+
+/// OKX 行情 agent：公共频道 trades / books / tickers 映射为现有的事件载荷。
+/// 流名称为 "<instId>@<channel>"，例如 "BTC-USDT-SWAP@trades"；
+/// 合约（SWAP / FUTURES）的数量单位为张，事件中的数量保持交易所原值，
+/// 并按 apply_instruments 加载的 ctVal 设置 contract_size，归一化事件据此折算为币
+pub struct OkxMarketAgent {
+    pub ws: OkxWebSocketClient,
+    pub event_producer: QueueEventDispatcherProducer,
+    stop: StopHandle,
+    commands: CommandChannel,
+    /// 按流名称维护的订单簿，用于增量序号与校验和验证
+    books: HashMap<String, OkxBook>,
+    /// 校验失败时请求重新订阅以获取新快照
+    resync: mpsc::UnboundedSender<String>,
+    pub mark_prices: MarkPriceCache,
+    /// 合约每张的大小（来自 public/instruments 的 ctVal），键为 instId；未配置的品种视为以币计
+    pub contract_sizes: HashMap<String, ContractSize>,
+    /// 按流统计的消息速率、解析失败、重连与延迟
+    stats: FeedStats,
+    /// FeedStats 事件的分发间隔，通过 set_stats_interval 设置
+    stats_interval: Duration,
+    /// 分发原始事件、归一化事件或两者
+    pub emission: EventEmission,
+}
+
+impl OkxMarketAgent {
+    pub fn new(ws: OkxWebSocketClient, event_producer: QueueEventDispatcherProducer) -> Self {
+        let resync = ws.resync_handle();
+        Self {
+            ws,
+            event_producer,
+            stop: StopHandle::new(),
+            commands: CommandChannel::new(),
+            books: HashMap::new(),
+            resync,
+            mark_prices: MarkPriceCache::new(),
+            contract_sizes: HashMap::new(),
+            stats: FeedStats::new("okx"),
+            stats_interval: DEFAULT_STATS_INTERVAL,
+            emission: EventEmission::default(),
+        }
+    }
+
+    /// 设置 FeedStats 事件的分发间隔，为 0 时返回错误；需在 start 之前调用
+    pub fn set_stats_interval(&mut self, interval: Duration) -> Result<(), Box<dyn Error>> {
+        self.stats_interval = check_stats_interval(interval)?;
+        Ok(())
+    }
+
+    pub fn stats_interval(&self) -> Duration {
+        self.stats_interval
+    }
+
+    pub fn set_contract_size(&mut self, inst_id: &str, contract_size: ContractSize) {
+        self.contract_sizes.insert(inst_id.to_uppercase(), contract_size);
+    }
+
+    /// 从品种元数据中读取合约面值（ctVal）
+    pub fn apply_instruments(&mut self, registry: &InstrumentRegistry) {
+        for instrument in registry.iter() {
+            if let Some(contract_size) = instrument.contract_size {
+                self.set_contract_size(&instrument.symbol, contract_size);
+            }
+        }
+    }
+
+    /// 品种的合约面值，现货或未加载元数据的品种返回 None
+    pub fn contract_size_of(&self, inst_id: &str) -> Option<ContractSize> {
+        self.contract_sizes.get(&inst_id.to_uppercase()).copied()
+    }
+
+    /// 深度转换为归一化的 BookSnapshot（行情流快照）或 BookDelta。
+    /// 档位无法解析时丢弃整条深度，订单簿会因序号不连续等待下一次快照
+    fn normalize_depth(event: &event::DepthEvent) -> Result<(EventType, EventPayload), Box<dyn Error>> {
+        if event.event == "snapshot" {
+            let snapshot = market_data::BookSnapshot::from_depth("okx", event)?;
+            Ok((EventType::BookSnapshot, EventPayload::BookSnapshot(snapshot)))
+        } else {
+            let delta = market_data::BookDelta::from_depth("okx", event)?;
+            Ok((EventType::BookDelta, EventPayload::BookDelta(delta)))
+        }
+    }
+
+    fn on_message(&mut self, msg: String, received_timestamp: u128) {
+        let push: OkxPush = match serde_json::from_str(&msg) {
+            Ok(push) => push,
+            Err(e) => {
+                self.stats.record_parse_failure(UNROUTED_STREAM);
+                eprintln!("OKX 消息解析失败: {} - 原始消息: {}", e, msg);
+                return;
+            }
+        };
+        let stream = stream_of(&push.arg).unwrap_or_default();
+        let channel = push.arg.get("channel").and_then(|c| c.as_str()).unwrap_or("");
+        let event_time = push
+            .data
+            .first()
+            .and_then(|d| d.get("ts"))
+            .and_then(|ts| ts.as_str())
+            .and_then(|ts| ts.parse::<u64>().ok());
+        self.stats.record_message(&stream, received_timestamp, event_time);
+        match channel {
+            "trades" => {
+                for item in push.data {
+                    match serde_json::from_value::<OkxTrade>(item) {
+                        Ok(trade) => {
+                            let event = trade_event(trade, &stream, received_timestamp);
+                            self.on_trade(event);
+                        }
+                        Err(e) => {
+                            self.stats.record_parse_failure(&stream);
+                            eprintln!("OKX 成交解析失败: {} - 原始消息: {}", e, msg);
+                        }
+                    }
+                }
+            }
+            "tickers" => {
+                for item in push.data {
+                    match serde_json::from_value::<OkxTicker>(item) {
+                        Ok(ticker) => {
+                            let event = book_ticker_event(ticker, &stream, received_timestamp);
+                            self.on_book_ticker(event);
+                        }
+                        Err(e) => {
+                            self.stats.record_parse_failure(&stream);
+                            eprintln!("OKX ticker 解析失败: {} - 原始消息: {}", e, msg);
+                        }
+                    }
+                }
+            }
+            c if c.starts_with("books") || c == "bbo-tbt" => {
+                for item in push.data {
+                    match serde_json::from_value::<OkxBookData>(item) {
+                        Ok(book) => self.on_book(&stream, push.action.as_deref(), book, received_timestamp),
+                        Err(e) => {
+                            self.stats.record_parse_failure(&stream);
+                            eprintln!("OKX 深度解析失败: {} - 原始消息: {}", e, msg);
+                        }
+                    }
+                }
+            }
+            _ => {
+                self.stats.record_unknown_event(&stream);
+                eprintln!("收到未处理的 OKX 频道 [{}] - 原始消息: {}", stream, msg);
+            }
+        }
+    }
+
+    /// 处理深度推送：带 action 的频道为快照 + 增量，先在本地订单簿上验证序号与校验和再分发；
+    /// 不带 action 的频道（books5、bbo-tbt）每次都是完整快照，直接分发
+    fn on_book(&mut self, stream: &str, action: Option<&str>, book: OkxBookData, received_timestamp: u128) {
+        let bids = levels(&book.bids);
+        let asks = levels(&book.asks);
+        let seq_id = book.seq_id.unwrap_or(0);
+        let prev_seq_id = book.prev_seq_id.unwrap_or(-1);
+        let action = action.unwrap_or("snapshot");
+
+        if action == "snapshot" {
+            let mut local = OkxBook::default();
+            local.apply(&bids, &asks);
+            local.seq_id = seq_id;
+            self.books.insert(stream.to_string(), local);
+        } else {
+            let local = match self.books.get_mut(stream) {
+                Some(local) => local,
+                // 校验失败后等待新的快照，期间的增量没有意义
+                None => return,
+            };
+            if prev_seq_id != local.seq_id {
+                eprintln!("[OKX] {} 增量序号不连续: prevSeqId={} 本地 seqId={}，重新订阅", stream, prev_seq_id, local.seq_id);
+                self.request_resync(stream);
+                return;
+            }
+            local.apply(&bids, &asks);
+            local.seq_id = seq_id;
+        }
+
+        if let (Some(expected), Some(local)) = (book.checksum, self.books.get(stream)) {
+            let actual = local.checksum();
+            if actual as i64 != expected {
+                eprintln!("[OKX] {} 校验和不一致: 本地 {} 交易所 {}，重新订阅", stream, actual, expected);
+                self.request_resync(stream);
+                return;
+            }
+        }
+
+        let ts = book.ts.parse::<u64>().unwrap_or(0);
+        let event = event::DepthEvent {
+            event: action.to_string(),
+            event_time: ts,
+            trade_time: ts,
+            symbol: stream.split('@').next().unwrap_or("").to_string(),
+            first_update_id: (prev_seq_id + 1).max(0) as u64,
+            last_update_id: seq_id.max(0) as u64,
+            previous_update_id: prev_seq_id.max(0) as u64,
+            bids,
+            asks,
+            received_timestamp,
+            stream: stream.to_string(),
+            ..Default::default()
+        };
+        self.on_depth(event);
+    }
+
+    fn request_resync(&mut self, stream: &str) {
+        self.books.remove(stream);
+        let _ = self.resync.send(stream.to_string());
+    }
+}
+
+/// 档位只保留价格与数量
+fn levels(raw: &[Vec<String>]) -> Vec<(String, String)> {
+    raw.iter()
+        .filter(|level| level.len() >= 2)
+        .map(|level| (level[0].clone(), level[1].clone()))
+        .collect()
+}
+
+fn trade_event(trade: OkxTrade, stream: &str, received_timestamp: u128) -> event::AggTradeEvent {
+    let ts = trade.ts.parse::<u64>().unwrap_or(0);
+    event::AggTradeEvent {
+        event: "trades".to_string(),
+        event_time: ts,
+        agg_trade_id: trade.trade_id.parse().unwrap_or(0),
+        symbol: trade.inst_id,
+        price: trade.px,
+        quantity: trade.sz,
+        trade_time: ts,
+        // side 为 taker 方向，taker 卖出即买方为挂单方
+        is_buyer_maker: trade.side == "sell",
+        received_timestamp,
+        stream: stream.to_string(),
+        ..Default::default()
+    }
+}
+
+fn book_ticker_event(ticker: OkxTicker, stream: &str, received_timestamp: u128) -> event::BookTickerEvent {
+    let ts = ticker.ts.parse::<u64>().unwrap_or(0);
+    let mut extra = HashMap::new();
+    extra.insert("last".to_string(), Value::String(ticker.last));
+    extra.insert("lastSz".to_string(), Value::String(ticker.last_sz));
+    event::BookTickerEvent {
+        event: "tickers".to_string(),
+        update_id: ts,
+        event_time: ts,
+        transaction_time: ts,
+        symbol: ticker.inst_id,
+        bid_price: ticker.bid_px,
+        bid_qty: ticker.bid_sz,
+        ask_price: ticker.ask_px,
+        ask_qty: ticker.ask_sz,
+        received_timestamp,
+        stream: stream.to_string(),
+        extra,
+        contract_size: None,
+    }
+}
+
+#[async_trait]
+impl FeedConnection for OkxWebSocketClient {
+    fn placeholder(&self) -> Self {
+        OkxWebSocketClient::with_endpoint(self.endpoint().clone())
+    }
+
+    fn set_message_callback(&mut self, callback: Box<dyn FnMut(String) + Send + 'static>) {
+        OkxWebSocketClient::set_message_callback(self, callback);
+    }
+
+    fn set_lifecycle_callback(&mut self, callback: Box<dyn FnMut(u64, LifecycleEvent) + Send + 'static>) {
+        OkxWebSocketClient::set_lifecycle_callback(self, callback);
+    }
+
+    fn command_handle(&self) -> mpsc::UnboundedSender<WsCommand> {
+        OkxWebSocketClient::command_handle(self)
+    }
+
+    async fn listen_loop(&mut self) -> Result<(), Box<dyn Error>> {
+        WebSocket::listen_loop(self).await
+    }
+}
+
+impl FeedAgent for OkxMarketAgent {
+    type Connection = OkxWebSocketClient;
+    type Custom = Infallible;
+
+    const NAME: &'static str = "OkxMarketAgent";
+
+    fn connection(&mut self) -> &mut OkxWebSocketClient {
+        &mut self.ws
+    }
+
+    fn command_channel(&mut self) -> &mut CommandChannel {
+        &mut self.commands
+    }
+
+    fn stop(&self) -> &StopHandle {
+        &self.stop
+    }
+
+    fn stats_interval(&self) -> Duration {
+        self.stats_interval
+    }
+
+    fn handle_feed_message(&mut self, msg: FeedMessage<Infallible>) {
+        match msg {
+            FeedMessage::Text(text, received_timestamp) => self.on_message(text, received_timestamp),
+            FeedMessage::Lifecycle(connection_id, lifecycle) => {
+                self.stats.record_lifecycle(&lifecycle);
+                // 重连后交易所会重新推送快照，旧的本地订单簿作废
+                if matches!(lifecycle, LifecycleEvent::Disconnected { .. }) {
+                    self.books.clear();
+                }
+                let (event_type, event) = connection_event("okx", connection_id, lifecycle);
+                self.on_connection(event_type, event);
+            }
+            FeedMessage::Custom(never) => match never {},
+        }
+    }
+
+    /// 取消订阅时先清除本地订单簿状态，与启动前的 unsubscribe 一致
+    fn before_command(&mut self, command: &AgentCommand) -> Result<(), String> {
+        if let AgentCommand::Unsubscribe(streams, _) = command {
+            for s in streams {
+                self.books.remove(s);
+            }
+        }
+        Ok(())
+    }
+
+    fn publish_stats(&mut self) {
+        publish_stats(&self.stats, &mut self.event_producer);
+    }
+}
+
+#[async_trait]
+impl MarketAgent for OkxMarketAgent {
+    async fn start(&mut self) -> Result<(), Box<dyn std::error::Error>> {
+        let (tx, rx) = mpsc::unbounded_channel();
+        run_feed(self, tx, rx).await
+    }
+
+    fn stop_handle(&self) -> StopHandle {
+        self.stop.clone()
+    }
+
+    fn command_handle(&self) -> CommandHandle {
+        self.commands.handle()
+    }
+
+    fn feed_stats(&self) -> FeedStats {
+        self.stats.clone()
+    }
+
+    fn set_event_emission(&mut self, emission: EventEmission) {
+        self.emission = emission;
+    }
+
+    fn on_depth(&mut self, mut event: event::DepthEvent) {
+        event.contract_size = self.contract_size_of(&event.symbol);
+        // 行情流推送的快照单独作为 BookSnapshot 分发
+        let normalized = self.emission.normalize(&self.stats, &event.stream, || Self::normalize_depth(&event));
+        if self.emission.raw() {
+            self.event_producer.fire(EventType::Depth, EventPayload::Depth(event));
+        }
+        if let Some((event_type, payload)) = normalized {
+            self.event_producer.fire(event_type, payload);
+        }
+    }
+
+    fn on_trade(&mut self, mut event: event::AggTradeEvent) {
+        event.contract_size = self.contract_size_of(&event.symbol);
+        let trade = self.emission.normalize(&self.stats, &event.stream, || market_data::Trade::from_agg_trade("okx", &event));
+        if self.emission.raw() {
+            self.event_producer.fire(EventType::AggTrade, EventPayload::AggTrade(event));
+        }
+        if let Some(trade) = trade {
+            self.event_producer.fire(EventType::MarketTrade, EventPayload::MarketTrade(trade));
+        }
+    }
+
+    fn on_raw_trade(&mut self, event: event::TradeEvent) {
+        let trade = self.emission.normalize(&self.stats, &event.stream, || market_data::Trade::from_trade("okx", &event));
+        if self.emission.raw() {
+            self.event_producer.fire(EventType::Trade, EventPayload::Trade(event));
+        }
+        if let Some(trade) = trade {
+            self.event_producer.fire(EventType::MarketTrade, EventPayload::MarketTrade(trade));
+        }
+    }
+
+    fn on_book_ticker(&mut self, mut event: event::BookTickerEvent) {
+        event.contract_size = self.contract_size_of(&event.symbol);
+        let bbo = self.emission.normalize(&self.stats, &event.stream, || market_data::Bbo::from_book_ticker("okx", &event));
+        if self.emission.raw() {
+            self.event_producer.fire(EventType::BookTicker, EventPayload::BookTicker(event));
+        }
+        if let Some(bbo) = bbo {
+            self.event_producer.fire(EventType::Bbo, EventPayload::Bbo(bbo));
+        }
+    }
+
+    fn on_mark_price(&mut self, event: event::MarkPriceEvent) {
+        self.mark_prices.update(&event.symbol, event.clone());
+        self.event_producer.fire(EventType::MarkPrice, EventPayload::MarkPrice(event));
+    }
+
+    fn mark_price_cache(&self) -> MarkPriceCache {
+        self.mark_prices.clone()
+    }
+
+    fn on_liquidation(&mut self, event: event::LiquidationEvent) {
+        self.event_producer.fire(EventType::Liquidation, EventPayload::Liquidation(event));
+    }
+
+    fn on_connection(&mut self, event_type: EventType, event: event::ConnectionEvent) {
+        println!("[{}] OKX 连接 {} {:?} {}", Local::now().format("%H:%M:%S"), event.connection_id, event_type, event.reason);
+        self.event_producer.fire(event_type, EventPayload::Connection(event));
+    }
+
+    fn on_kline(&mut self, event: event::KlineEvent) {
+        self.event_producer.fire(EventType::Kline, EventPayload::Kline(event));
+    }
+
+    async fn subscribe(&mut self, streams: Vec<&str>) -> Result<(), Box<dyn Error>> {
+        self.ws.subscribe(streams).await
+    }
+
+    async fn unsubscribe(&mut self, streams: Vec<&str>) -> Result<(), Box<dyn Error>> {
+        for s in &streams {
+            self.books.remove(*s);
+        }
+        self.ws.unsubscribe(streams).await
+    }
+
+    async fn list_subscriptions(&mut self) -> Result<Vec<String>, Box<dyn Error>> {
+        self.ws.list_subscriptions().await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn levels(raw: &[(&str, &str)]) -> Vec<(String, String)> {
+        raw.iter().map(|(px, sz)| (px.to_string(), sz.to_string())).collect()
+    }
+
+    // OKX 文档中的校验串示例，期望值为对应校验串 CRC32 的有符号结果
+    #[test]
+    fn checksum_matches_documented_example() {
+        let mut book = OkxBook::default();
+        book.apply(
+            &levels(&[("3366.1", "7"), ("3366", "6")]),
+            &levels(&[("3366.8", "9"), ("3368", "8")]),
+        );
+        // "3366.1:7:3366.8:9:3366:6:3368:8"
+        assert_eq!(book.checksum(), -1881014294);
+    }
+
+    #[test]
+    fn checksum_with_uneven_sides() {
+        let mut book = OkxBook::default();
+        book.apply(
+            &levels(&[("3366.1", "7")]),
+            &levels(&[("3366.8", "9"), ("3368", "8"), ("3372", "8")]),
+        );
+        // "3366.1:7:3366.8:9:3368:8:3372:8"
+        assert_eq!(book.checksum(), 831078360);
+    }
+
+    #[test]
+    fn zero_size_removes_level_and_only_top_25_count() {
+        let mut book = OkxBook::default();
+        book.apply(
+            &levels(&[("3366.1", "7"), ("3366", "6"), ("3365", "1")]),
+            &levels(&[("3366.8", "9"), ("3368", "8")]),
+        );
+        book.apply(&levels(&[("3365", "0")]), &[]);
+        assert_eq!(book.checksum(), -1881014294);
+
+        // 第 26 档之后的档位不参与校验
+        let deep: Vec<(String, String)> = (0..30).map(|i| (format!("{}", 1000 - i), "1".to_string())).collect();
+        let mut full = OkxBook::default();
+        full.apply(&deep, &[]);
+        let mut top = OkxBook::default();
+        top.apply(&deep[..CHECKSUM_DEPTH], &[]);
+        assert_eq!(full.checksum(), top.checksum());
+    }
+}
@@ -0,0 +1,303 @@
+// market_agent/user_data_agent.rs
+
+use std::error::Error;
+use std::time::Duration;
+
+use event_engine::event::{EventPayload, EventType};
+use event_engine::event_dispatcher::QueueEventDispatcherProducer;
+use event_engine::user_data::UserDataEvent;
+use feeder::endpoint::WsEndpoint;
+use feeder::lifecycle::LifecycleEvent;
+use feeder::listen_key::ListenKeyClient;
+use feeder::watchdog::WatchdogConfig;
+use feeder::websocket::{BinanceWebSocketClient, WebSocket};
+use serde_json::Value;
+use tokio::sync::mpsc;
+
+use crate::market_agent::StopHandle;
+
+fn get_timestamp_us() -> u128 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .expect("Time went backwards")
+        .as_micros()
+}
+
+/// 用户数据流配置：REST 地址用于管理 listenKey，WebSocket 端点用于接收推送。
+/// 两者都可以指向本地 mock 服务
+#[derive(Debug, Clone)]
+pub struct UserDataConfig {
+    pub api_key: String,
+    /// REST 基础地址，例如 "https://fapi.binance.com"
+    pub rest_base_url: String,
+    /// listenKey 接口路径
+    pub listen_key_path: String,
+    pub endpoint: WsEndpoint,
+    /// keepalive 间隔，listenKey 有效期为 60 分钟
+    pub keepalive_interval: Duration,
+    /// 单次 keepalive 请求的超时时间
+    pub keepalive_timeout: Duration,
+    /// keepalive 失败后的重试间隔
+    pub keepalive_retry_interval: Duration,
+    /// 连续失败达到该次数时重新创建 listenKey 并重连
+    pub max_keepalive_failures: u32,
+}
+
+impl UserDataConfig {
+    /// U 本位合约正式环境
+    pub fn binance_futures(api_key: &str) -> Self {
+        Self {
+            api_key: api_key.to_string(),
+            rest_base_url: "https://fapi.binance.com".to_string(),
+            listen_key_path: "/fapi/v1/listenKey".to_string(),
+            endpoint: WsEndpoint::binance_futures(),
+            keepalive_interval: Duration::from_secs(30 * 60),
+            keepalive_timeout: Duration::from_secs(10),
+            keepalive_retry_interval: Duration::from_secs(60),
+            max_keepalive_failures: 3,
+        }
+    }
+
+    /// U 本位合约测试网
+    pub fn binance_futures_testnet(api_key: &str) -> Self {
+        Self {
+            rest_base_url: "https://testnet.binancefuture.com".to_string(),
+            endpoint: WsEndpoint::binance_futures_testnet(),
+            ..Self::binance_futures(api_key)
+        }
+    }
+
+    /// 本地 mock：REST 与 WebSocket 均不启用 TLS，例如 ("127.0.0.1:9002", "127.0.0.1:9001")
+    pub fn local(api_key: &str, rest_addr: &str, ws_addr: &str) -> Self {
+        Self {
+            rest_base_url: format!("http://{}", rest_addr),
+            endpoint: WsEndpoint::local(ws_addr),
+            ..Self::binance_futures(api_key)
+        }
+    }
+}
+
+/// feeder 回调转发到代理任务的消息
+enum FeedMessage {
+    Text(String, u128),
+    Lifecycle(u64, LifecycleEvent),
+    /// 后台 keepalive 请求的结果
+    Keepalive(Result<(), String>),
+}
+
+/// 本轮连接结束的原因
+enum SessionEnd {
+    /// 收到停止信号
+    Stopped,
+    /// listenKey 过期，需要重新创建并重连
+    Expired,
+    /// keepalive 连续失败，listenKey 可能即将失效，重新创建并重连
+    KeepaliveFailed(String),
+    /// 连接彻底失败
+    Failed(String),
+}
+
+/// 用户数据流代理：创建并定期延长 listenKey，连接用户数据流，
+/// 将订单、账户、追加保证金推送解析后通过事件引擎分发
+pub struct UserDataAgent {
+    pub config: UserDataConfig,
+    pub event_producer: QueueEventDispatcherProducer,
+    stop: StopHandle,
+    rest: ListenKeyClient,
+    /// 当前使用的 listenKey
+    listen_key: Option<String>,
+}
+
+impl UserDataAgent {
+    pub fn new(config: UserDataConfig, event_producer: QueueEventDispatcherProducer) -> Self {
+        let rest = ListenKeyClient::new(&config.rest_base_url, &config.listen_key_path, &config.api_key);
+        Self {
+            config,
+            event_producer,
+            stop: StopHandle::new(),
+            rest,
+            listen_key: None,
+        }
+    }
+
+    /// 停止句柄，可在其他任务中让 start 返回
+    pub fn stop_handle(&self) -> StopHandle {
+        self.stop.clone()
+    }
+
+    pub fn listen_key(&self) -> Option<&str> {
+        self.listen_key.as_deref()
+    }
+
+    /// 运行到收到停止信号或连接彻底失败为止；listenKey 过期时自动重新创建并重连
+    pub async fn start(&mut self) -> Result<(), Box<dyn Error>> {
+        let result = self.run().await;
+        // 返回前才重置，start 之前请求的停止不会丢失
+        self.stop.reset();
+        result
+    }
+
+    async fn run(&mut self) -> Result<(), Box<dyn Error>> {
+        let mut stop_rx = self.stop.subscribe();
+        if self.stop.is_stopped() {
+            println!("[UserDataAgent] 启动前已请求停止");
+            return Ok(());
+        }
+        loop {
+            let listen_key = self.rest.create().await?;
+            println!("[UserDataAgent] 已创建 listenKey");
+            self.listen_key = Some(listen_key.clone());
+
+            let (tx, mut rx) = mpsc::unbounded_channel::<FeedMessage>();
+            let text_tx = tx.clone();
+            let keepalive_tx = tx.clone();
+            let mut ws = BinanceWebSocketClient::with_endpoint(self.config.endpoint.clone());
+            // 没有订单与账户变动时用户数据流可以长时间无推送，不做静默检测
+            ws.set_watchdog_config(WatchdogConfig::disabled());
+            ws.set_message_callback(move |msg: String| {
+                let _ = text_tx.send(FeedMessage::Text(msg, get_timestamp_us()));
+            });
+            ws.set_lifecycle_callback(move |connection_id: u64, lifecycle: LifecycleEvent| {
+                let _ = tx.send(FeedMessage::Lifecycle(connection_id, lifecycle));
+            });
+            ws.connect(vec![listen_key.as_str()]).await?;
+
+            // 第一次 tick 立即触发，跳过
+            let mut keepalive = tokio::time::interval(self.config.keepalive_interval);
+            keepalive.tick().await;
+            let mut keepalive_in_flight = false;
+            let mut keepalive_failures = 0;
+
+            let end = {
+                // 错误先转为字符串，select 的输出不能包含 Box<dyn Error>
+                let listen = async { ws.listen_loop().await.map_err(|e| e.to_string()) };
+                tokio::pin!(listen);
+                loop {
+                    tokio::select! {
+                        res = &mut listen => {
+                            break match res {
+                                Ok(()) => SessionEnd::Stopped,
+                                Err(e) => SessionEnd::Failed(e),
+                            };
+                        }
+                        Some(msg) = rx.recv() => {
+                            if let FeedMessage::Keepalive(result) = msg {
+                                keepalive_in_flight = false;
+                                match result {
+                                    Ok(()) => keepalive_failures = 0,
+                                    Err(e) => {
+                                        keepalive_failures += 1;
+                                        eprintln!("[UserDataAgent] listenKey 延期失败（连续 {} 次）: {}", keepalive_failures, e);
+                                        if keepalive_failures >= self.config.max_keepalive_failures {
+                                            break SessionEnd::KeepaliveFailed(e);
+                                        }
+                                        keepalive.reset_after(self.config.keepalive_retry_interval);
+                                    }
+                                }
+                            } else if self.handle_feed_message(msg) {
+                                break SessionEnd::Expired;
+                            }
+                        }
+                        _ = keepalive.tick() => {
+                            // 在后台发送，请求挂起时不影响推送的处理；上一次尚未返回时跳过
+                            if !keepalive_in_flight {
+                                keepalive_in_flight = true;
+                                let rest = self.rest.clone();
+                                let listen_key = listen_key.clone();
+                                let timeout = self.config.keepalive_timeout;
+                                let tx = keepalive_tx.clone();
+                                tokio::spawn(async move {
+                                    let result = match tokio::time::timeout(timeout, rest.keepalive(&listen_key)).await {
+                                        Ok(result) => result.map_err(|e| e.to_string()),
+                                        Err(_) => Err(format!("请求超时（{} 秒）", timeout.as_secs())),
+                                    };
+                                    let _ = tx.send(FeedMessage::Keepalive(result));
+                                });
+                            }
+                        }
+                        _ = stop_rx.changed() => {
+                            println!("[UserDataAgent] 收到停止信号");
+                            break SessionEnd::Stopped;
+                        }
+                    }
+                }
+            };
+            while let Ok(msg) = rx.try_recv() {
+                self.handle_feed_message(msg);
+            }
+
+            match end {
+                SessionEnd::Expired => {
+                    println!("[UserDataAgent] listenKey 已过期，重新创建并重连");
+                    continue;
+                }
+                SessionEnd::KeepaliveFailed(e) => {
+                    eprintln!("[UserDataAgent] listenKey 连续延期失败: {}，重新创建并重连", e);
+                    continue;
+                }
+                SessionEnd::Stopped => {
+                    let result = self.rest.close(&listen_key).await.map_err(|e| e.to_string());
+                    if let Err(e) = result {
+                        eprintln!("[UserDataAgent] 关闭 listenKey 失败: {}", e);
+                    }
+                    self.listen_key = None;
+                    return Ok(());
+                }
+                SessionEnd::Failed(e) => return Err(e.into()),
+            }
+        }
+    }
+
+    /// 处理一条转发消息，返回 listenKey 是否已过期
+    fn handle_feed_message(&mut self, msg: FeedMessage) -> bool {
+        match msg {
+            FeedMessage::Text(text, received_timestamp) => self.on_message(&text, received_timestamp),
+            FeedMessage::Lifecycle(connection_id, lifecycle) => {
+                // 用户数据流断线期间的推送不会补发，断线后应通过 REST 核对订单与持仓
+                println!("[UserDataAgent] 连接 {} {:?}", connection_id, lifecycle);
+                false
+            }
+            // 本轮连接结束后才返回的 keepalive 结果不再需要
+            FeedMessage::Keepalive(_) => false,
+        }
+    }
+
+    fn on_message(&mut self, text: &str, received_timestamp: u128) -> bool {
+        let value: Value = match serde_json::from_str(text) {
+            Ok(value) => value,
+            Err(e) => {
+                eprintln!("JSON解析失败: {} - 原始消息: {}", e, text);
+                return false;
+            }
+        };
+        // 组合流格式带 {"stream","data"} 包装
+        let data = match value.get("data") {
+            Some(data) if value.get("stream").is_some() => data.clone(),
+            _ => value,
+        };
+        let event = match serde_json::from_value::<UserDataEvent>(data) {
+            Ok(event) => event,
+            Err(e) => {
+                eprintln!("用户数据解析失败: {} - 原始消息: {}", e, text);
+                return false;
+            }
+        };
+        match event {
+            UserDataEvent::OrderUpdate(mut event) => {
+                event.received_timestamp = received_timestamp;
+                self.event_producer.fire(EventType::OrderUpdate, EventPayload::OrderUpdate(event));
+            }
+            UserDataEvent::AccountUpdate(mut event) => {
+                event.received_timestamp = received_timestamp;
+                self.event_producer.fire(EventType::AccountUpdate, EventPayload::AccountUpdate(event));
+            }
+            UserDataEvent::MarginCall(mut event) => {
+                event.received_timestamp = received_timestamp;
+                self.event_producer.fire(EventType::MarginCall, EventPayload::MarginCall(event));
+            }
+            UserDataEvent::ListenKeyExpired(_) => return true,
+            UserDataEvent::Unknown => {}
+        }
+        false
+    }
+}
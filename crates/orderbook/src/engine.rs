@@ -1,10 +1,120 @@
-use crate::models::{OrderBook, OrderSide, DepthSnapshot, parse_order_entry};
+use crate::models::{OrderBook, OrderSide, DepthSnapshot};
 use std::error::Error;
-use reqwest::Client;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
 use event_engine::event::EventType;
 use event_engine::event::EventPayload;
-use event_engine::event::DepthEvent;
+use event_engine::event::ConnectionEvent;
 use event_engine::event_dispatcher::EventData;
+use event_engine::market_data::{BookDelta, BookSnapshot};
+use common::exchange::Exchange;
+use common::instrument::Instrument;
+use rest_client::{BinanceRestClient, RestMarket};
+
+/// 订单簿失效后检查是否需要重新获取快照的间隔，快照请求失败时也按此间隔重试
+pub const RESYNC_INTERVAL: Duration = Duration::from_secs(1);
+
+/// 深度同步规则，合约与现货的快照接口和增量连续性校验不同
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SyncMode {
+    /// U 本位合约：首个事件满足 U <= lastUpdateId <= u，之后要求 pu == 上一条的 u
+    Futures,
+    /// 币本位合约：连续性规则与 U 本位相同，快照来自 dapi，数量单位为张
+    CoinFutures,
+    /// 现货：首个事件满足 U <= lastUpdateId+1 <= u，之后要求 U == 上一条的 u + 1（无 pu 字段）
+    Spot,
+    /// OKX：快照由 books 频道推送（event 为 "snapshot"），之后要求 prevSeqId == 上一条的 seqId
+    Okx,
+    /// Bybit：快照由 orderbook 频道推送（event 为 "snapshot"），之后要求 u == 上一条的 u + 1
+    Bybit,
+}
+
+impl SyncMode {
+    pub fn for_exchange(exchange: Exchange) -> Self {
+        match exchange {
+            Exchange::Binance => SyncMode::Futures,
+            Exchange::BinanceSpot => SyncMode::Spot,
+            Exchange::BinanceCoinFutures => SyncMode::CoinFutures,
+            Exchange::Okx => SyncMode::Okx,
+            Exchange::Bybit => SyncMode::Bybit,
+        }
+    }
+
+    /// 对应的交易所标识，与 Exchange::as_str 一致
+    pub fn exchange_name(&self) -> &'static str {
+        match self {
+            SyncMode::Futures => "binance",
+            SyncMode::CoinFutures => "binance_coin_futures",
+            SyncMode::Spot => "binance_spot",
+            SyncMode::Okx => "okx",
+            SyncMode::Bybit => "bybit",
+        }
+    }
+
+    /// 快照是否由行情流推送（而不是通过 REST 获取）
+    pub fn streams_snapshot(&self) -> bool {
+        matches!(self, SyncMode::Okx | SyncMode::Bybit)
+    }
+
+    /// REST 深度快照所在的市场；OKX、Bybit 的快照随行情流推送，返回 None
+    pub fn rest_market(&self) -> Option<RestMarket> {
+        match self {
+            SyncMode::Futures => Some(RestMarket::UsdFutures),
+            SyncMode::CoinFutures => Some(RestMarket::CoinFutures),
+            SyncMode::Spot => Some(RestMarket::Spot),
+            SyncMode::Okx | SyncMode::Bybit => None,
+        }
+    }
+
+    /// 深度流对应的交易对，其他流返回 None。
+    /// 币安为 "<symbol>@depth..."，OKX 为 "<instId>@books..."，Bybit 为 "orderbook.<depth>.<symbol>"
+    pub fn book_stream_symbol<'a>(&self, stream: &'a str) -> Option<&'a str> {
+        match self {
+            SyncMode::Futures | SyncMode::CoinFutures | SyncMode::Spot => {
+                let (symbol, rest) = stream.split_once('@')?;
+                rest.starts_with("depth").then_some(symbol)
+            }
+            SyncMode::Okx => {
+                let (inst_id, channel) = stream.split_once('@')?;
+                channel.starts_with("books").then_some(inst_id)
+            }
+            SyncMode::Bybit => {
+                let mut parts = stream.split('.');
+                if parts.next()? != "orderbook" {
+                    return None;
+                }
+                parts.nth(1)
+            }
+        }
+    }
+
+    /// 快照之前的事件，应当丢弃
+    fn is_outdated(&self, update: &BookDelta, last_update_id: u64) -> bool {
+        match self {
+            SyncMode::Futures | SyncMode::CoinFutures => update.last_update_id < last_update_id,
+            SyncMode::Spot | SyncMode::Okx | SyncMode::Bybit => update.last_update_id <= last_update_id,
+        }
+    }
+
+    /// 是否可以作为快照之后的第一个事件
+    fn is_first_update(&self, update: &BookDelta, last_update_id: u64) -> bool {
+        let target = match self {
+            SyncMode::Futures | SyncMode::CoinFutures => last_update_id,
+            SyncMode::Spot | SyncMode::Bybit => last_update_id + 1,
+            SyncMode::Okx => return update.previous_update_id == last_update_id,
+        };
+        update.first_update_id <= target && target <= update.last_update_id
+    }
+
+    /// 是否与上一条已应用的事件连续
+    fn is_continuous(&self, update: &BookDelta, last_update_id: u64) -> bool {
+        match self {
+            SyncMode::Futures | SyncMode::CoinFutures => update.previous_update_id == last_update_id,
+            SyncMode::Spot | SyncMode::Bybit => update.first_update_id == last_update_id + 1,
+            SyncMode::Okx => update.previous_update_id == last_update_id,
+        }
+    }
+}
 
 
 /// 订单簿维护引擎
@@ -12,17 +122,43 @@ pub struct OrderBookEngine {
     pub order_book: OrderBook,
     pub last_update_id: u64,
     // 用于在初始化前缓存增量事件
-    pub update_buffer: Vec<DepthEvent>,
+    pub update_buffer: Vec<BookDelta>,
     // 回调，当订单簿更新时调用
     pub update_callbacks: Vec<Box<dyn Fn(&OrderBook) + Send + Sync>>,
     // 交易对，例如 "BTCUSDT"
     pub symbol: String,
     // 新增 flag，标识是否已经应用了第一个连续的深度更新事件
     pub continuous_started: bool,
+    // 连接断开或静默后置为 true，表示订单簿已失效，需要重新获取快照
+    pub needs_resync: bool,
+    // 深度同步规则（合约 / 现货）
+    pub sync_mode: SyncMode,
+    // 品种元数据，设置后价格按最小变动取整，避免浮点误差产生重复的价格档位
+    pub instrument: Option<Instrument>,
 }
 
 impl OrderBookEngine {
+    /// U 本位合约订单簿
     pub fn new(symbol: &str) -> Self {
+        Self::with_mode(symbol, SyncMode::Futures)
+    }
+
+    /// 币本位合约订单簿，交易对形如 "BTCUSD_PERP"
+    pub fn coin_futures(symbol: &str) -> Self {
+        Self::with_mode(symbol, SyncMode::CoinFutures)
+    }
+
+    /// 现货订单簿
+    pub fn spot(symbol: &str) -> Self {
+        Self::with_mode(symbol, SyncMode::Spot)
+    }
+
+    /// 按交易所选择同步规则
+    pub fn for_exchange(exchange: Exchange, symbol: &str) -> Self {
+        Self::with_mode(symbol, SyncMode::for_exchange(exchange))
+    }
+
+    pub fn with_mode(symbol: &str, sync_mode: SyncMode) -> Self {
         Self {
             order_book: OrderBook::new(),
             last_update_id: 0,
@@ -30,10 +166,17 @@ impl OrderBookEngine {
             update_callbacks: Vec::new(),
             symbol: symbol.to_string(),
             continuous_started: false,
-
+            needs_resync: false,
+            sync_mode,
+            instrument: None,
         }
     }
 
+    /// 设置品种元数据（来自 InstrumentRegistry）
+    pub fn set_instrument(&mut self, instrument: Instrument) {
+        self.instrument = Some(instrument);
+    }
+
     /// 注册一个新的回调
     pub fn register_callback<F>(&mut self, callback: F)
     where
@@ -51,113 +194,445 @@ impl OrderBookEngine {
 
     /// 通过 REST API 获取深度快照
     pub async fn fetch_depth_snapshot(&self) -> Result<DepthSnapshot, Box<dyn std::error::Error>> {
-        let url = format!("https://fapi.binance.com/fapi/v1/depth?symbol={}&limit=1000", self.symbol);
-        let client = Client::new();
-        let response = client.get(&url).send().await?.json::<DepthSnapshot>().await?;
-        Ok(response)
+        Self::fetch_snapshot_for(self.sync_mode, &self.symbol).await
+    }
+
+    /// 获取指定交易对的深度快照，不需要持有引擎（便于在锁外请求）
+    pub async fn fetch_snapshot_for(sync_mode: SyncMode, symbol: &str) -> Result<DepthSnapshot, Box<dyn std::error::Error>> {
+        let market = sync_mode
+            .rest_market()
+            .ok_or("该交易所的深度快照由行情流推送，无需 REST 快照")?;
+        // 共用进程内的客户端，复用连接并统一计算请求权重
+        let depth = BinanceRestClient::shared(market).depth(symbol, 1000).await?;
+        Ok(depth.into())
     }
 
     /// 初始化订单簿：调用 REST 获取快照，然后应用缓存中增量事件
     pub async fn initialize(&mut self) -> Result<(), Box<dyn std::error::Error>> {
+        if self.sync_mode.streams_snapshot() {
+            // 等待行情流推送的快照
+            return Ok(());
+        }
         let snapshot = self.fetch_depth_snapshot().await?;
+        self.apply_snapshot(snapshot)
+    }
+
+    /// 应用 REST 快照
+    pub fn apply_snapshot(&mut self, snapshot: DepthSnapshot) -> Result<(), Box<dyn std::error::Error>> {
+        let contract_size = self.instrument.as_ref().and_then(|instrument| instrument.contract_size);
+        let snapshot = snapshot.to_book_snapshot(self.sync_mode.exchange_name(), &self.symbol, contract_size)?;
+        self.apply_book_snapshot(snapshot)
+    }
+
+    /// 应用快照（REST 获取或行情流推送），并从缓存的增量事件中找到连续起点继续应用
+    pub fn apply_book_snapshot(&mut self, snapshot: BookSnapshot) -> Result<(), Box<dyn std::error::Error>> {
         println!("修改前的last_update_id: {}", self.last_update_id);
         // println!("last update id的类型：{}", std::any::type_name_of_val(&self.last_update_id));
+        let mut snapshot = snapshot;
+        if let Some(instrument) = &self.instrument {
+            for level in snapshot.bids.iter_mut().chain(snapshot.asks.iter_mut()) {
+                level.price = instrument.round_price(level.price);
+            }
+        }
         self.last_update_id = snapshot.last_update_id;
-        self.order_book = snapshot.to_order_book();
+        self.order_book = OrderBook::from_snapshot(&snapshot);
         println!("此时的last_update_id: {}", self.last_update_id);
         println!("此时buffer长度：{}", self.update_buffer.len());
-        // 丢弃所有 final_update_id < last_update_id 的事件
-        self.update_buffer.retain(|u| u.last_update_id >= self.last_update_id);
+        // 丢弃快照之前的事件（合约：u < lastUpdateId；现货：u <= lastUpdateId）
+        let (sync_mode, last_update_id) = (self.sync_mode, self.last_update_id);
+        self.update_buffer.retain(|u| !sync_mode.is_outdated(u, last_update_id));
         println!("过滤后buffer长度：{}", self.update_buffer.len());
-        // 找到第一个满足 U <= last_update_id <= u 的事件开始应用
-        // 在遍历前克隆 update_buffer
-        let updates = self.update_buffer.clone();
+        // 从第一个满足起点条件的事件开始，依次应用之后缓存的事件
+        self.continuous_started = false;
+        self.needs_resync = false;
+        let updates = std::mem::take(&mut self.update_buffer);
         for update in updates.iter() {
             println!("update.first_update_id: {}, self.last_update_id: {}, update.last_update_id: {}", update.first_update_id, self.last_update_id, update.last_update_id);
-            if update.first_update_id <= self.last_update_id 
-                && self.last_update_id <= update.last_update_id
-            {
-                println!("找到第一个满足 U <= last_update_id <= u 的事件开始应用");
-                self.apply_update(update)?;
-            }
+            // 快照早于缓存的增量或增量不连续时订单簿已失效，等待下一次快照
+            self.apply_update(update)?;
         }
-        self.update_buffer.clear();
 
         self.notify_update();
         Ok(())
     }
 
-    /// 将增量更新缓存起来（如果尚未初始化）或直接应用（如果已经初始化）
-    pub fn push_update(&mut self, event: EventData) -> Result<(), Box<dyn Error>> {
-        // 仅处理深度事件
-        if event.event_type != EventType::Depth {
-            return Ok(());
+    /// 使订单簿失效：清空本地状态，之后的增量事件重新进入缓存，等待下一次快照
+    pub fn invalidate(&mut self) {
+        self.order_book = OrderBook::new();
+        self.last_update_id = 0;
+        self.update_buffer.clear();
+        self.continuous_started = false;
+        self.needs_resync = true;
+    }
+
+    /// 处理连接生命周期事件：断开或静默意味着增量流出现缺口，订单簿必须重建。
+    /// 只处理承载本交易对深度流的连接，其他交易所或其他连接（连接池中的其他分片）的事件忽略
+    pub fn on_connection_event(&mut self, event: &EventData) {
+        let EventPayload::Connection(connection) = &event.data else {
+            return;
+        };
+        if !self.affected_by(connection) {
+            return;
         }
-        // 从 event.data 中提取 DepthEvent
-        let depth_event = match event.data {
-            EventPayload::Depth(de) => {
-                de
-            },
-            other => return Err("Expected Depth event payload".into()),
+        match event.event_type {
+            EventType::Disconnected | EventType::Stale if !self.needs_resync => {
+                println!("[{}] 行情连接中断，订单簿失效，等待重新同步", self.symbol);
+                self.invalidate();
+            }
+            EventType::Resubscribed => {
+                // 缺少 Disconnected 事件时（例如只收到重连结果）同样需要重建
+                if !self.needs_resync {
+                    self.invalidate();
+                }
+                println!("[{}] 行情已恢复订阅，开始缓存增量事件", self.symbol);
+            }
+            _ => {}
+        }
+    }
+
+    /// 连接事件是否影响本订单簿：交易所相同，且连接上有本交易对的深度流；
+    /// 事件不带流列表时无法判断，按受影响处理
+    fn affected_by(&self, connection: &ConnectionEvent) -> bool {
+        if !connection.exchange.is_empty() && connection.exchange != self.sync_mode.exchange_name() {
+            return false;
+        }
+        connection.streams.is_empty()
+            || connection.streams.iter().any(|stream| {
+                self.sync_mode
+                    .book_stream_symbol(stream)
+                    .is_some_and(|symbol| symbol.eq_ignore_ascii_case(&self.symbol))
+            })
+    }
+
+    /// 是否需要通过 REST 重新获取快照：订单簿已失效、快照不由行情流推送，且增量已恢复推送。
+    /// 在增量恢复之前获取的快照可能早于重连后的第一条增量，无法衔接
+    pub fn wants_snapshot(&self) -> bool {
+        self.needs_resync && !self.sync_mode.streams_snapshot() && !self.update_buffer.is_empty()
+    }
+
+    /// 需要时在锁外获取 REST 快照并应用，返回是否应用了快照
+    pub async fn resync(engine: &Arc<Mutex<OrderBookEngine>>) -> Result<bool, Box<dyn Error>> {
+        let (sync_mode, symbol) = {
+            let engine = engine.lock().unwrap();
+            if !engine.wants_snapshot() {
+                return Ok(false);
+            }
+            (engine.sync_mode, engine.symbol.clone())
         };
+        let snapshot = Self::fetch_snapshot_for(sync_mode, &symbol).await?;
+        let mut engine = engine.lock().unwrap();
+        engine.apply_snapshot(snapshot)?;
+        Ok(true)
+    }
 
+    /// 启动后台重新同步任务：订单簿因断线、静默或增量不连续失效后，自动获取 REST 快照并应用，
+    /// 失败时按 RESYNC_INTERVAL 重试。OKX、Bybit 的快照随重新订阅由行情流推送，任务不会请求 REST
+    pub fn spawn_resync_task(engine: Arc<Mutex<OrderBookEngine>>) -> tokio::task::JoinHandle<()> {
+        tokio::spawn(async move {
+            loop {
+                tokio::time::sleep(RESYNC_INTERVAL).await;
+                // 错误先转为字符串，Box<dyn Error> 不能跨 await 持有
+                let result = Self::resync(&engine).await.map_err(|e| e.to_string());
+                match result {
+                    Ok(true) => println!("订单簿重新同步完成"),
+                    Ok(false) => {}
+                    Err(e) => eprintln!("订单簿重新同步失败: {}", e),
+                }
+            }
+        })
+    }
+
+    /// 将增量更新缓存起来（如果尚未初始化）或直接应用（如果已经初始化）。
+    /// 接受归一化的 BookDelta / BookSnapshot 事件，以及原始 Depth 事件（EventEmission::Raw 时），后者按同样规则转换；
+    /// 同一条深度只应以其中一种事件交给引擎，否则会被应用两次。其他交易对的事件忽略，其他类型的事件返回错误
+    pub fn push_update(&mut self, event: EventData) -> Result<(), Box<dyn Error>> {
+        let exchange = self.sync_mode.exchange_name();
+        let delta = match event.data {
+            EventPayload::BookDelta(delta) => delta,
+            EventPayload::BookSnapshot(snapshot) => return self.push_snapshot(snapshot),
+            EventPayload::Depth(depth) if !depth.symbol.eq_ignore_ascii_case(&self.symbol) => return Ok(()),
+            // OKX、Bybit 的行情流快照以 event 为 "snapshot" 的深度事件推送
+            EventPayload::Depth(depth) if self.sync_mode.streams_snapshot() && depth.event == "snapshot" => {
+                return self.push_snapshot(BookSnapshot::from_depth(exchange, &depth)?);
+            }
+            EventPayload::Depth(depth) => BookDelta::from_depth(exchange, &depth)?,
+            _ => return Err(format!("订单簿不处理 {:?} 事件", event.event_type).into()),
+        };
+        if !delta.instrument.eq_ignore_ascii_case(&self.symbol) {
+            return Ok(());
+        }
 
         if self.last_update_id == 0 {
             // 未初始化时，缓存深度事件
             // println!("尚未初始化，缓存深度事件");
-            self.update_buffer.push(depth_event);
+            self.update_buffer.push(delta);
             Ok(())
         } else {
             // 已初始化时，直接应用更新
             // println!("已初始化，直接应用更新");
-            self.apply_update(&depth_event)
+            self.apply_update(&delta)
         }
     }
 
-    /// 应用单个深度事件更新订单簿（这里将 DepthEvent 用作参数）
-    fn apply_update(&mut self, update: &DepthEvent) -> Result<(), Box<dyn Error>> {
+    /// 应用行情流推送的快照，其他交易对的快照忽略
+    fn push_snapshot(&mut self, snapshot: BookSnapshot) -> Result<(), Box<dyn Error>> {
+        if !snapshot.instrument.eq_ignore_ascii_case(&self.symbol) {
+            return Ok(());
+        }
+        self.apply_book_snapshot(snapshot)
+    }
+
+    /// 应用单个深度增量更新订单簿
+    fn apply_update(&mut self, update: &BookDelta) -> Result<(), Box<dyn Error>> {
         if !self.continuous_started {
             // 还没有找到连续更新的起点，检查是否满足条件
-            if update.first_update_id <= self.last_update_id && self.last_update_id <= update.last_update_id {
+            if self.sync_mode.is_first_update(update, self.last_update_id) {
                 println!("找到第一个满足连续条件的深度更新，作为连续更新起点");
-                // 应用更新，不检查 previous_update_id
-                for b in &update.bids {
-                    let (price, qty) = parse_order_entry(b);
-                    self.order_book.update_side(OrderSide::Buy, price, qty);
-                }
-                for a in &update.asks {
-                    let (price, qty) = parse_order_entry(a);
-                    self.order_book.update_side(OrderSide::Sell, price, qty);
-                }
+                // 应用更新，不检查连续性
+                self.apply_levels(update);
                 self.last_update_id = update.last_update_id;
                 self.order_book.event_time = Some(update.event_time);
                 self.continuous_started = true;
                 self.notify_update();
                 Ok(())
-            } else {
-                // 还没达到连续更新的条件，忽略该更新
-                println!("尚未找到连续更新起点，忽略当前更新");
+            } else if self.sync_mode.is_outdated(update, self.last_update_id) {
+                // 快照之前的事件，忽略
                 Ok(())
+            } else {
+                // 增量已越过快照，快照过旧，无法再找到起点
+                self.invalidate();
+                Err("快照早于增量，订单簿失效，需要重新获取快照".into())
             }
         } else {
             // 已经找到连续更新的起点，正常检查连续性
-            if update.previous_update_id != self.last_update_id {
-                return Err("更新连续性验证失败，需要重新初始化".into());
-            }
-            for b in &update.bids {
-                let (price, qty) = parse_order_entry(b);
-                self.order_book.update_side(OrderSide::Buy, price, qty);
-            }
-            for a in &update.asks {
-                let (price, qty) = parse_order_entry(a);
-                self.order_book.update_side(OrderSide::Sell, price, qty);
+            if !self.sync_mode.is_continuous(update, self.last_update_id) {
+                self.invalidate();
+                return Err("更新连续性验证失败，订单簿失效，需要重新获取快照".into());
             }
+            self.apply_levels(update);
             self.last_update_id = update.last_update_id;
             self.order_book.event_time = Some(update.event_time);
             self.notify_update();
             Ok(())
         }
     }
-    
 
+    /// 将增量中的价格档位写入订单簿
+    fn apply_levels(&mut self, update: &BookDelta) {
+        let round = |price: f64| match &self.instrument {
+            Some(instrument) => instrument.round_price(price),
+            None => price,
+        };
+        for b in &update.bids {
+            let price = round(b.price);
+            self.order_book.update_side(OrderSide::Buy, price, b.quantity);
+        }
+        for a in &update.asks {
+            let price = round(a.price);
+            self.order_book.update_side(OrderSide::Sell, price, a.quantity);
+        }
+    }
+
+
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn delta(first: u64, last: u64, previous: u64) -> BookDelta {
+        BookDelta {
+            exchange: String::new(),
+            instrument: "BTCUSDT".to_string(),
+            first_update_id: first,
+            last_update_id: last,
+            previous_update_id: previous,
+            bids: Vec::new(),
+            asks: Vec::new(),
+            event_time: 0,
+            received_timestamp: 0,
+            contract_size: None,
+        }
+    }
+
+    #[test]
+    fn futures_first_update_straddles_snapshot_id() {
+        let mode = SyncMode::Futures;
+        // U <= lastUpdateId <= u
+        assert!(mode.is_first_update(&delta(95, 105, 94), 100));
+        assert!(mode.is_first_update(&delta(100, 105, 99), 100));
+        assert!(mode.is_first_update(&delta(95, 100, 94), 100));
+        assert!(!mode.is_first_update(&delta(101, 105, 100), 100));
+        assert!(!mode.is_first_update(&delta(90, 99, 89), 100));
+    }
+
+    #[test]
+    fn futures_continuity_uses_previous_id() {
+        let mode = SyncMode::Futures;
+        assert!(mode.is_continuous(&delta(110, 120, 105), 105));
+        // U 不必等于上一条的 u + 1，只看 pu
+        assert!(!mode.is_continuous(&delta(106, 120, 104), 105));
+        assert!(mode.is_outdated(&delta(90, 99, 89), 100));
+        assert!(!mode.is_outdated(&delta(95, 100, 94), 100));
+    }
+
+    #[test]
+    fn spot_first_update_covers_next_id() {
+        let mode = SyncMode::Spot;
+        // U <= lastUpdateId + 1 <= u
+        assert!(mode.is_first_update(&delta(95, 105, 0), 100));
+        assert!(mode.is_first_update(&delta(101, 105, 0), 100));
+        assert!(!mode.is_first_update(&delta(95, 100, 0), 100));
+        assert!(!mode.is_first_update(&delta(102, 105, 0), 100));
+    }
+
+    #[test]
+    fn spot_continuity_requires_next_first_id() {
+        let mode = SyncMode::Spot;
+        assert!(mode.is_continuous(&delta(106, 110, 0), 105));
+        assert!(!mode.is_continuous(&delta(107, 110, 0), 105));
+        assert!(!mode.is_continuous(&delta(105, 110, 0), 105));
+        // 现货与快照 id 相同的事件已包含在快照中
+        assert!(mode.is_outdated(&delta(95, 100, 0), 100));
+    }
+
+    #[test]
+    fn coin_futures_follow_futures_rules() {
+        let mode = SyncMode::CoinFutures;
+        assert!(mode.is_first_update(&delta(95, 105, 94), 100));
+        assert!(!mode.is_first_update(&delta(101, 105, 100), 100));
+        assert!(mode.is_continuous(&delta(110, 120, 105), 105));
+        assert!(!mode.is_continuous(&delta(106, 120, 104), 105));
+        assert!(!mode.is_outdated(&delta(95, 100, 94), 100));
+        assert_eq!(mode.rest_market(), Some(RestMarket::CoinFutures));
+    }
+
+    fn depth_event(symbol: &str, first: u64, last: u64, previous: u64) -> EventData {
+        EventData {
+            event_type: EventType::Depth,
+            data: EventPayload::Depth(event_engine::event::DepthEvent {
+                symbol: symbol.to_string(),
+                first_update_id: first,
+                last_update_id: last,
+                previous_update_id: previous,
+                bids: vec![("100".to_string(), "1".to_string())],
+                ..Default::default()
+            }),
+        }
+    }
+
+    #[test]
+    fn push_update_accepts_raw_depth() {
+        let mut engine = OrderBookEngine::new("BTCUSDT");
+        engine.push_update(depth_event("BTCUSDT", 95, 105, 94)).unwrap();
+        // 其他交易对的深度忽略
+        engine.push_update(depth_event("ETHUSDT", 95, 105, 94)).unwrap();
+        assert_eq!(engine.update_buffer.len(), 1);
+        assert_eq!(engine.update_buffer[0].bids[0].price, 100.0);
+    }
+
+    #[test]
+    fn push_update_rejects_unrelated_payloads() {
+        let mut engine = OrderBookEngine::new("BTCUSDT");
+        let event = EventData {
+            event_type: EventType::Trade,
+            data: EventPayload::Trade(Default::default()),
+        };
+        assert!(engine.push_update(event).is_err());
+    }
+
+    fn connection_event(event_type: EventType, exchange: &str, streams: &[&str]) -> EventData {
+        EventData {
+            event_type,
+            data: EventPayload::Connection(ConnectionEvent {
+                exchange: exchange.to_string(),
+                connection_id: 1,
+                streams: streams.iter().map(|s| s.to_string()).collect(),
+                reason: String::new(),
+                silent_ms: 0,
+                timestamp: 0,
+            }),
+        }
+    }
+
+    /// 以 lastUpdateId = 100 的空快照初始化的订单簿
+    fn synced_engine() -> OrderBookEngine {
+        let mut engine = OrderBookEngine::new("BTCUSDT");
+        engine.apply_book_snapshot(BookSnapshot {
+            exchange: "binance".to_string(),
+            instrument: "BTCUSDT".to_string(),
+            last_update_id: 100,
+            bids: Vec::new(),
+            asks: Vec::new(),
+            event_time: 0,
+            received_timestamp: 0,
+            contract_size: None,
+        }).unwrap();
+        engine
+    }
+
+    #[test]
+    fn book_stream_symbol_per_exchange() {
+        assert_eq!(SyncMode::Futures.book_stream_symbol("btcusdt@depth@100ms"), Some("btcusdt"));
+        assert_eq!(SyncMode::Spot.book_stream_symbol("btcusdt@depth"), Some("btcusdt"));
+        assert_eq!(SyncMode::Futures.book_stream_symbol("btcusdt@aggTrade"), None);
+        assert_eq!(SyncMode::Futures.book_stream_symbol("!forceOrder@arr"), None);
+        assert_eq!(SyncMode::Okx.book_stream_symbol("BTC-USDT-SWAP@books"), Some("BTC-USDT-SWAP"));
+        assert_eq!(SyncMode::Okx.book_stream_symbol("BTC-USDT-SWAP@trades"), None);
+        assert_eq!(SyncMode::Bybit.book_stream_symbol("orderbook.50.BTCUSDT"), Some("BTCUSDT"));
+        assert_eq!(SyncMode::Bybit.book_stream_symbol("publicTrade.BTCUSDT"), None);
+    }
+
+    #[test]
+    fn connection_events_only_invalidate_affected_books() {
+        let mut engine = synced_engine();
+        // 其他交易所、其他交易对的深度流、本交易对的非深度流都不影响订单簿
+        engine.on_connection_event(&connection_event(EventType::Disconnected, "okx", &["BTCUSDT@books"]));
+        engine.on_connection_event(&connection_event(EventType::Disconnected, "binance", &["ethusdt@depth@100ms"]));
+        engine.on_connection_event(&connection_event(EventType::Stale, "binance", &["btcusdt@aggTrade"]));
+        engine.on_connection_event(&connection_event(EventType::Resubscribed, "binance", &["ethusdt@depth"]));
+        assert!(!engine.needs_resync);
+        assert_eq!(engine.last_update_id, 100);
+
+        engine.on_connection_event(&connection_event(EventType::Disconnected, "binance", &["btcusdt@aggTrade", "btcusdt@depth@100ms"]));
+        assert!(engine.needs_resync);
+        assert_eq!(engine.last_update_id, 0);
+    }
+
+    #[test]
+    fn connection_event_without_streams_invalidates() {
+        let mut engine = synced_engine();
+        engine.on_connection_event(&connection_event(EventType::Resubscribed, "binance", &[]));
+        assert!(engine.needs_resync);
+    }
+
+    #[test]
+    fn snapshot_applies_all_buffered_updates() {
+        let mut engine = OrderBookEngine::new("BTCUSDT");
+        engine.update_buffer = vec![delta(90, 99, 89), delta(95, 105, 94), delta(106, 110, 105), delta(111, 115, 110)];
+        engine.apply_snapshot(DepthSnapshot { last_update_id: 100, event_time: None, match_time: None, bids: Vec::new(), asks: Vec::new() }).unwrap();
+        assert!(engine.continuous_started);
+        assert_eq!(engine.last_update_id, 115);
+        assert!(engine.update_buffer.is_empty());
+    }
+
+    #[test]
+    fn stale_snapshot_or_gap_invalidates_and_waits_for_resync() {
+        // 快照早于缓存的第一条增量
+        let mut engine = OrderBookEngine::new("BTCUSDT");
+        engine.update_buffer = vec![delta(120, 130, 119)];
+        assert!(engine.apply_snapshot(DepthSnapshot { last_update_id: 100, event_time: None, match_time: None, bids: Vec::new(), asks: Vec::new() }).is_err());
+        assert!(engine.needs_resync);
+        assert_eq!(engine.last_update_id, 0);
+
+        // 增量不连续
+        let mut engine = synced_engine();
+        engine.push_update(EventData { event_type: EventType::BookDelta, data: EventPayload::BookDelta(delta(95, 105, 94)) }).unwrap();
+        assert!(engine.push_update(EventData { event_type: EventType::BookDelta, data: EventPayload::BookDelta(delta(110, 120, 108)) }).is_err());
+        assert!(engine.needs_resync);
+        // 增量恢复后才请求快照
+        assert!(!engine.wants_snapshot());
+        engine.push_update(EventData { event_type: EventType::BookDelta, data: EventPayload::BookDelta(delta(121, 130, 120)) }).unwrap();
+        assert!(engine.wants_snapshot());
+    }
 }
@@ -1,6 +1,9 @@
 use std::collections::BTreeMap;
 use serde::{Deserialize, Serialize};
 use ordered_float::OrderedFloat;
+use event_engine::event::ContractSize;
+use event_engine::market_data::{BookSnapshot, PriceLevel};
+use rest_client::types::Depth;
 
 /// 订单方向：买或卖
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -54,6 +57,19 @@ impl OrderBook {
         }
     }
 
+    /// 由归一化快照构建订单簿
+    pub fn from_snapshot(snapshot: &BookSnapshot) -> Self {
+        let mut order_book = OrderBook::new();
+        for level in &snapshot.bids {
+            order_book.update_side(OrderSide::Buy, level.price, level.quantity);
+        }
+        for level in &snapshot.asks {
+            order_book.update_side(OrderSide::Sell, level.price, level.quantity);
+        }
+        order_book.event_time = Some(snapshot.event_time);
+        order_book
+    }
+
     /// 根据 side 更新指定价位的数量
     pub fn update_side(&mut self, side: OrderSide, price: f64, quantity: f64) {
         match side {
@@ -136,6 +152,18 @@ pub struct DepthSnapshot {
     pub asks: Vec<(String, String)>, // Vec<[price, quantity]>
 }
 
+impl From<Depth> for DepthSnapshot {
+    fn from(depth: Depth) -> Self {
+        Self {
+            last_update_id: depth.last_update_id,
+            event_time: depth.event_time,
+            match_time: depth.match_time,
+            bids: depth.bids,
+            asks: depth.asks,
+        }
+    }
+}
+
 /// 币安增量深度更新 (WebSocket 接收)
 #[derive(Debug, Deserialize, Serialize, Clone)]
 pub struct DepthUpdateEvent {
@@ -179,4 +207,24 @@ impl DepthSnapshot {
 
         order_book
     }
+
+    /// 转换为与交易所无关的快照，任一档位无法解析时返回错误。
+    /// REST 快照不带面值信息，contract_size 由调用方按品种信息传入
+    pub fn to_book_snapshot(
+        &self,
+        exchange: &str,
+        instrument: &str,
+        contract_size: Option<ContractSize>,
+    ) -> Result<BookSnapshot, Box<dyn std::error::Error>> {
+        Ok(BookSnapshot {
+            exchange: exchange.to_string(),
+            instrument: instrument.to_string(),
+            last_update_id: self.last_update_id,
+            bids: PriceLevel::parse_levels(&self.bids)?,
+            asks: PriceLevel::parse_levels(&self.asks)?,
+            event_time: self.event_time.unwrap_or(0),
+            received_timestamp: 0,
+            contract_size,
+        })
+    }
 }
\ No newline at end of file
@@ -0,0 +1,380 @@
+// rest_client/client.rs
+
+use std::error::Error;
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::Duration;
+
+use reqwest::header::HeaderMap;
+use reqwest::{Client, StatusCode};
+use serde::de::DeserializeOwned;
+
+/// 单次请求（含读取响应）的超时时间，超时按网络错误重试
+pub const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// 建立连接的超时时间
+pub const CONNECT_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// 418 响应未带 Retry-After 时的封禁时长（交易所最短封禁 2 分钟）
+const DEFAULT_BAN_DURATION: Duration = Duration::from_secs(120);
+
+/// 币安 REST 行情接口所属市场
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RestMarket {
+    /// U 本位合约
+    UsdFutures,
+    /// 币本位合约
+    CoinFutures,
+    /// 现货
+    Spot,
+}
+
+impl RestMarket {
+    /// 正式环境的 REST 基础地址
+    pub fn base_url(&self) -> &'static str {
+        match self {
+            RestMarket::UsdFutures => "https://fapi.binance.com",
+            RestMarket::CoinFutures => "https://dapi.binance.com",
+            RestMarket::Spot => "https://api.binance.com",
+        }
+    }
+
+    /// 行情接口路径前缀
+    pub fn api_prefix(&self) -> &'static str {
+        match self {
+            RestMarket::UsdFutures => "/fapi/v1",
+            RestMarket::CoinFutures => "/dapi/v1",
+            RestMarket::Spot => "/api/v3",
+        }
+    }
+
+    /// 每分钟的请求权重上限（按 IP 计算）
+    pub fn weight_limit(&self) -> u32 {
+        match self {
+            RestMarket::UsdFutures | RestMarket::CoinFutures => 2400,
+            RestMarket::Spot => 6000,
+        }
+    }
+
+    pub fn is_futures(&self) -> bool {
+        !matches!(self, RestMarket::Spot)
+    }
+}
+
+/// 重试与限频配置
+#[derive(Debug, Clone)]
+pub struct RestConfig {
+    /// 网络错误、5xx、429 时的最大重试次数（418 不重试）
+    pub max_retries: u32,
+    /// 第一次重试前的等待时间，之后每次翻倍
+    pub retry_delay: Duration,
+    /// 本地限频阈值：本分钟已用权重加上本次请求超过该值时，等到下一分钟再发
+    pub weight_limit: u32,
+}
+
+impl RestConfig {
+    /// 预留一成权重给同一 IP 上的其他程序
+    pub fn for_market(market: RestMarket) -> Self {
+        Self {
+            max_retries: 3,
+            retry_delay: Duration::from_millis(500),
+            weight_limit: market.weight_limit() * 9 / 10,
+        }
+    }
+}
+
+/// 当前分钟已用的请求权重，以及交易所要求的暂停时间
+#[derive(Debug, Default)]
+struct WeightState {
+    /// 以分钟计的时间戳
+    minute: u64,
+    used: u32,
+    /// 收到 429 后在该时间（毫秒）之前暂停所有请求
+    paused_until_ms: u64,
+    /// 收到 418 后在该时间（毫秒）之前所有请求直接失败
+    banned_until_ms: u64,
+}
+
+fn now_ms() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .expect("Time went backwards")
+        .as_millis() as u64
+}
+
+/// 币安 REST 行情客户端：复用连接，按响应头 X-MBX-USED-WEIGHT-1M 记录已用权重，
+/// 接近上限时等待下一分钟，失败时按配置重试。收到 429 时所有调用方一起等到 Retry-After，
+/// 收到 418（IP 被封禁）时不再重试，封禁期间的请求直接失败。
+/// clone 出的客户端共享连接与权重计数（权重按 IP 计算，同一进程应共用一个）
+#[derive(Clone)]
+pub struct BinanceRestClient {
+    client: Client,
+    pub market: RestMarket,
+    base_url: String,
+    pub config: RestConfig,
+    weight: Arc<Mutex<WeightState>>,
+}
+
+impl BinanceRestClient {
+    pub fn new(market: RestMarket) -> Self {
+        Self::with_base_url(market, market.base_url())
+    }
+
+    /// 指定基础地址，例如测试网或本地 mock "http://127.0.0.1:9002"
+    pub fn with_base_url(market: RestMarket, base_url: &str) -> Self {
+        Self {
+            client: Client::builder()
+                .timeout(REQUEST_TIMEOUT)
+                .connect_timeout(CONNECT_TIMEOUT)
+                .build()
+                .expect("创建 HTTP 客户端失败"),
+            market,
+            base_url: base_url.trim_end_matches('/').to_string(),
+            config: RestConfig::for_market(market),
+            weight: Arc::new(Mutex::new(WeightState::default())),
+        }
+    }
+
+    /// 进程内共享的客户端，每个市场一个
+    pub fn shared(market: RestMarket) -> Self {
+        static USD_FUTURES: OnceLock<BinanceRestClient> = OnceLock::new();
+        static COIN_FUTURES: OnceLock<BinanceRestClient> = OnceLock::new();
+        static SPOT: OnceLock<BinanceRestClient> = OnceLock::new();
+        let cell = match market {
+            RestMarket::UsdFutures => &USD_FUTURES,
+            RestMarket::CoinFutures => &COIN_FUTURES,
+            RestMarket::Spot => &SPOT,
+        };
+        cell.get_or_init(|| Self::new(market)).clone()
+    }
+
+    pub fn set_config(&mut self, config: RestConfig) {
+        self.config = config;
+    }
+
+    pub fn base_url(&self) -> &str {
+        &self.base_url
+    }
+
+    /// 本分钟已用的请求权重（来自最近一次响应头，加上之后本地预占的部分）
+    pub fn used_weight(&self) -> u32 {
+        let state = self.weight.lock().unwrap();
+        if state.minute == now_ms() / 60_000 { state.used } else { 0 }
+    }
+
+    /// 预占本次请求的权重：封禁期间直接失败，429 暂停期间等待，本分钟剩余权重不足时等到下一分钟
+    async fn acquire_weight(&self, weight: u32) -> Result<(), String> {
+        loop {
+            let wait_ms = {
+                let now = now_ms();
+                let mut state = self.weight.lock().unwrap();
+                if now < state.banned_until_ms {
+                    return Err(format!("IP 已被封禁，{} ms 后解除", state.banned_until_ms - now));
+                }
+                if now < state.paused_until_ms {
+                    state.paused_until_ms - now
+                } else {
+                    if state.minute != now / 60_000 {
+                        state.minute = now / 60_000;
+                        state.used = 0;
+                    }
+                    if state.used + weight <= self.config.weight_limit {
+                        state.used += weight;
+                        return Ok(());
+                    }
+                    60_000 - now % 60_000 + 100
+                }
+            };
+            println!("[BinanceRestClient] 本分钟权重已用 {}，等待 {} ms", self.used_weight(), wait_ms);
+            tokio::time::sleep(Duration::from_millis(wait_ms)).await;
+        }
+    }
+
+    /// 429：在 delay 之后才允许任何调用方继续请求
+    fn pause_for(&self, delay: Duration) {
+        let until = now_ms() + delay.as_millis() as u64;
+        let mut state = self.weight.lock().unwrap();
+        state.paused_until_ms = state.paused_until_ms.max(until);
+    }
+
+    /// 418：封禁期间所有请求直接失败
+    fn ban_for(&self, duration: Duration) {
+        let until = now_ms() + duration.as_millis() as u64;
+        let mut state = self.weight.lock().unwrap();
+        state.banned_until_ms = state.banned_until_ms.max(until);
+    }
+
+    /// 以交易所返回的已用权重为准
+    fn record_weight(&self, headers: &HeaderMap) {
+        let used = headers
+            .get("x-mbx-used-weight-1m")
+            .or_else(|| headers.get("x-mbx-used-weight"))
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| v.parse::<u32>().ok());
+        if let Some(used) = used {
+            let mut state = self.weight.lock().unwrap();
+            state.minute = now_ms() / 60_000;
+            state.used = used;
+        }
+    }
+
+    /// 带重试的 GET 请求，path 为完整路径（例如 "/fapi/v1/depth"），weight 为该接口的请求权重
+    pub async fn get<T: DeserializeOwned>(&self, path: &str, query: &[(&str, String)], weight: u32) -> Result<T, Box<dyn Error>> {
+        let url = format!("{}{}", self.base_url, path);
+        let mut last_error = String::new();
+        for attempt in 0..=self.config.max_retries {
+            if let Err(e) = self.acquire_weight(weight).await {
+                return Err(format!("{} 请求失败: {}", path, e).into());
+            }
+            let backoff = self.config.retry_delay * 2u32.pow(attempt);
+            let delay = match self.client.get(&url).query(query).send().await {
+                Ok(response) => {
+                    self.record_weight(response.headers());
+                    let status = response.status();
+                    let retry_after = response
+                        .headers()
+                        .get("retry-after")
+                        .and_then(|v| v.to_str().ok())
+                        .and_then(|v| v.parse::<u64>().ok())
+                        .map(Duration::from_secs);
+                    let body = response.text().await.map_err(|e| e.to_string())?;
+                    if status.is_success() {
+                        return serde_json::from_str(&body)
+                            .map_err(|e| format!("{} 响应解析失败: {} - 原始响应: {}", path, e, body).into());
+                    }
+                    last_error = format!("{} {}", status, body);
+                    match status {
+                        // 429 超出限频：共享同一计数的其他调用方也暂停到 Retry-After
+                        StatusCode::TOO_MANY_REQUESTS => {
+                            let delay = retry_after.unwrap_or(backoff);
+                            self.pause_for(delay);
+                            delay
+                        }
+                        // 418 因持续超限被封禁，继续请求只会延长封禁，直接失败
+                        StatusCode::IM_A_TEAPOT => {
+                            self.ban_for(retry_after.unwrap_or(DEFAULT_BAN_DURATION));
+                            return Err(format!("{} 请求失败（IP 被封禁）: {}", path, last_error).into());
+                        }
+                        s if s.is_server_error() => backoff,
+                        _ => return Err(format!("{} 请求失败: {}", path, last_error).into()),
+                    }
+                }
+                Err(e) => {
+                    last_error = e.to_string();
+                    backoff
+                }
+            };
+            if attempt < self.config.max_retries {
+                eprintln!("[BinanceRestClient] {} 请求失败: {}，{} ms 后重试", path, last_error, delay.as_millis());
+                tokio::time::sleep(delay).await;
+            }
+        }
+        Err(format!("{} 请求失败（已重试 {} 次）: {}", path, self.config.max_retries, last_error).into())
+    }
+
+    /// 行情接口的完整路径，例如 api_path("depth") -> "/fapi/v1/depth"
+    pub(crate) fn api_path(&self, endpoint: &str) -> String {
+        format!("{}/{}", self.market.api_prefix(), endpoint)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock::{MockResponse, MockServer};
+    use serde_json::Value;
+    use std::time::Instant;
+
+    fn client(server: &MockServer, max_retries: u32) -> BinanceRestClient {
+        let mut client = BinanceRestClient::with_base_url(RestMarket::UsdFutures, &server.base_url);
+        client.set_config(RestConfig {
+            max_retries,
+            retry_delay: Duration::from_millis(50),
+            ..RestConfig::for_market(RestMarket::UsdFutures)
+        });
+        client
+    }
+
+    #[tokio::test]
+    async fn used_weight_follows_response_header() {
+        let server = MockServer::start(|_, _| MockResponse::ok("{}").header("X-MBX-USED-WEIGHT-1M", 1234)).await;
+        let client = client(&server, 0);
+        assert_eq!(client.used_weight(), 0);
+        let _: Value = client.get("/fapi/v1/ping", &[], 5).await.unwrap();
+        // 以交易所返回的已用权重为准，而不是本地预占的 5
+        assert_eq!(client.used_weight(), 1234);
+        // clone 出的客户端共享同一计数
+        assert_eq!(client.clone().used_weight(), 1234);
+    }
+
+    #[tokio::test]
+    async fn waits_when_minute_weight_is_exhausted() {
+        let server = MockServer::start(|_, _| MockResponse::ok("{}").header("X-MBX-USED-WEIGHT-1M", 8)).await;
+        let mut client = client(&server, 0);
+        client.config.weight_limit = 10;
+        let _: Value = client.get("/fapi/v1/ping", &[], 1).await.unwrap();
+
+        // 已用 8，再请求权重 5 会超过上限 10，等到下一分钟之前不会发出
+        let pending = tokio::time::timeout(Duration::from_millis(300), client.get::<Value>("/fapi/v1/ping", &[], 5)).await;
+        assert!(pending.is_err());
+        assert_eq!(server.requests().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn too_many_requests_pauses_until_retry_after() {
+        let server = MockServer::start(|_, index| {
+            if index == 0 { MockResponse::status(429).header("Retry-After", 1) } else { MockResponse::ok(r#"{"ok":true}"#) }
+        })
+        .await;
+        let client = client(&server, 3);
+        let started = Instant::now();
+        let value: Value = client.get("/fapi/v1/ping", &[], 1).await.unwrap();
+        assert_eq!(value["ok"], true);
+        assert_eq!(server.requests().len(), 2);
+        // Retry-After 优先于本地退避时间
+        assert!(started.elapsed() >= Duration::from_secs(1));
+    }
+
+    #[tokio::test]
+    async fn teapot_bans_without_retrying() {
+        let server = MockServer::start(|_, _| MockResponse::status(418).header("Retry-After", 60)).await;
+        let client = client(&server, 3);
+        let err = client.get::<Value>("/fapi/v1/ping", &[], 1).await.unwrap_err().to_string();
+        assert!(err.contains("封禁"), "{}", err);
+        assert_eq!(server.requests().len(), 1);
+
+        // 封禁期间的请求直接失败，不会发到交易所
+        let err = client.get::<Value>("/fapi/v1/ping", &[], 1).await.unwrap_err().to_string();
+        assert!(err.contains("IP 已被封禁"), "{}", err);
+        assert_eq!(server.requests().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn server_errors_retry_with_exponential_backoff() {
+        let server = MockServer::start(|_, _| MockResponse::status(503)).await;
+        let client = client(&server, 2);
+        let started = Instant::now();
+        let err = client.get::<Value>("/fapi/v1/ping", &[], 1).await.unwrap_err().to_string();
+        assert!(err.contains("已重试 2 次"), "{}", err);
+        assert_eq!(server.requests().len(), 3);
+        // 两次重试分别等待 50ms 与 100ms
+        assert!(started.elapsed() >= Duration::from_millis(150));
+    }
+
+    #[tokio::test]
+    async fn client_errors_are_not_retried() {
+        let server = MockServer::start(|_, _| MockResponse::status(400)).await;
+        let client = client(&server, 3);
+        let err = client.get::<Value>("/fapi/v1/ping", &[], 1).await.unwrap_err().to_string();
+        assert!(err.contains("400"), "{}", err);
+        assert_eq!(server.requests().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn malformed_body_is_reported() {
+        let server = MockServer::start(|_, _| MockResponse::ok("not json")).await;
+        let client = client(&server, 3);
+        let err = client.get::<Value>("/fapi/v1/ping", &[], 1).await.unwrap_err().to_string();
+        assert!(err.contains("响应解析失败"), "{}", err);
+        assert_eq!(server.requests().len(), 1);
+    }
+}
@@ -0,0 +1,6 @@
+pub mod client;
+pub mod types;
+pub mod market_data;
+pub use client::{BinanceRestClient, RestConfig, RestMarket};
+#[cfg(test)]
+mod mock;
@@ -0,0 +1,376 @@
+// rest_client/market_data.rs
+
+// 行情接口：单页请求与按 id / 时间区间的自动分页。
+// 权重为交易所文档中的请求权重，用于本地限频，实际已用权重以响应头为准
+
+use std::error::Error;
+
+use event_engine::event::{AggTradeEvent, KlineInterval};
+
+use crate::client::{BinanceRestClient, RestMarket};
+use crate::types::{Depth, FundingRate, Kline, OpenInterest, OpenInterestHist};
+
+/// 归集成交、K 线、资金费率单页最多返回的条数
+const PAGE_LIMIT: u32 = 1000;
+
+/// 持仓量历史单页最多返回的条数
+const OI_HIST_PAGE_LIMIT: u32 = 500;
+
+/// 按时间查询归集成交时，startTime 与 endTime 的间隔必须小于 1 小时
+const AGG_TRADES_TIME_WINDOW_MS: u64 = 60 * 60 * 1000;
+
+impl BinanceRestClient {
+    fn agg_trades_weight(&self) -> u32 {
+        if self.market.is_futures() { 20 } else { 4 }
+    }
+
+    fn klines_weight(&self, limit: u32) -> u32 {
+        if !self.market.is_futures() {
+            return 2;
+        }
+        match limit {
+            0..=99 => 1,
+            100..=499 => 2,
+            500..=1000 => 5,
+            _ => 10,
+        }
+    }
+
+    fn depth_weight(&self, limit: u32) -> u32 {
+        if self.market.is_futures() {
+            match limit {
+                0..=50 => 2,
+                51..=100 => 5,
+                101..=500 => 10,
+                _ => 20,
+            }
+        } else {
+            match limit {
+                0..=100 => 5,
+                101..=500 => 25,
+                501..=1000 => 50,
+                _ => 250,
+            }
+        }
+    }
+
+    fn require_futures(&self, endpoint: &str) -> Result<(), Box<dyn Error>> {
+        if self.market.is_futures() {
+            Ok(())
+        } else {
+            Err(format!("现货市场没有 {} 接口", endpoint).into())
+        }
+    }
+
+    /// 深度快照
+    pub async fn depth(&self, symbol: &str, limit: u32) -> Result<Depth, Box<dyn Error>> {
+        let query = [("symbol", symbol.to_uppercase()), ("limit", limit.to_string())];
+        self.get(&self.api_path("depth"), &query, self.depth_weight(limit)).await
+    }
+
+    /// 单页归集成交。REST 响应不带交易对，这里补上
+    pub async fn agg_trades(
+        &self,
+        symbol: &str,
+        from_id: Option<u64>,
+        start_time: Option<u64>,
+        end_time: Option<u64>,
+        limit: u32,
+    ) -> Result<Vec<AggTradeEvent>, Box<dyn Error>> {
+        let symbol = symbol.to_uppercase();
+        let mut query = vec![("symbol", symbol.clone()), ("limit", limit.to_string())];
+        if let Some(from_id) = from_id {
+            query.push(("fromId", from_id.to_string()));
+        }
+        if let Some(start_time) = start_time {
+            query.push(("startTime", start_time.to_string()));
+        }
+        if let Some(end_time) = end_time {
+            query.push(("endTime", end_time.to_string()));
+        }
+        let mut trades: Vec<AggTradeEvent> = self.get(&self.api_path("aggTrades"), &query, self.agg_trades_weight()).await?;
+        for trade in trades.iter_mut() {
+            trade.symbol = symbol.clone();
+        }
+        Ok(trades)
+    }
+
+    /// id 区间 [from_id, to_id] 内的归集成交，分页请求直到覆盖整个区间或交易所不再返回数据
+    pub async fn agg_trades_by_id(&self, symbol: &str, from_id: u64, to_id: u64) -> Result<Vec<AggTradeEvent>, Box<dyn Error>> {
+        let mut trades = Vec::new();
+        let mut next_id = from_id;
+        while next_id <= to_id {
+            let limit = (to_id - next_id + 1).min(PAGE_LIMIT as u64) as u32;
+            let page = self.agg_trades(symbol, Some(next_id), None, None, limit).await?;
+            let Some(last) = page.last() else {
+                break;
+            };
+            if last.agg_trade_id < next_id {
+                break;
+            }
+            next_id = last.agg_trade_id + 1;
+            trades.extend(page.into_iter().filter(|t| t.agg_trade_id <= to_id));
+        }
+        Ok(trades)
+    }
+
+    /// 成交时间在 [start_time, end_time]（毫秒）内的归集成交：
+    /// 先按 1 小时窗口找到区间内的第一笔，之后按 id 翻页
+    pub async fn agg_trades_by_time(&self, symbol: &str, start_time: u64, end_time: u64) -> Result<Vec<AggTradeEvent>, Box<dyn Error>> {
+        let mut window_start = start_time;
+        let mut first_page = Vec::new();
+        while window_start <= end_time && first_page.is_empty() {
+            let window_end = (window_start + AGG_TRADES_TIME_WINDOW_MS - 1).min(end_time);
+            first_page = self.agg_trades(symbol, None, Some(window_start), Some(window_end), PAGE_LIMIT).await?;
+            window_start = window_end + 1;
+        }
+
+        let mut trades = Vec::new();
+        let mut page = first_page;
+        loop {
+            let Some(last) = page.last() else {
+                break;
+            };
+            let next_id = last.agg_trade_id + 1;
+            let reached_end = last.trade_time > end_time || page.len() < PAGE_LIMIT as usize;
+            trades.extend(page.into_iter().filter(|t| t.trade_time <= end_time));
+            if reached_end {
+                break;
+            }
+            page = self.agg_trades(symbol, Some(next_id), None, None, PAGE_LIMIT).await?;
+        }
+        Ok(trades)
+    }
+
+    /// 单页 K 线
+    pub async fn klines(
+        &self,
+        symbol: &str,
+        interval: KlineInterval,
+        start_time: Option<u64>,
+        end_time: Option<u64>,
+        limit: u32,
+    ) -> Result<Vec<Kline>, Box<dyn Error>> {
+        let mut query = vec![
+            ("symbol", symbol.to_uppercase()),
+            ("interval", interval.as_str().to_string()),
+            ("limit", limit.to_string()),
+        ];
+        if let Some(start_time) = start_time {
+            query.push(("startTime", start_time.to_string()));
+        }
+        if let Some(end_time) = end_time {
+            query.push(("endTime", end_time.to_string()));
+        }
+        self.get(&self.api_path("klines"), &query, self.klines_weight(limit)).await
+    }
+
+    /// 开盘时间在 [start_time, end_time] 内的全部 K 线
+    pub async fn klines_range(&self, symbol: &str, interval: KlineInterval, start_time: u64, end_time: u64) -> Result<Vec<Kline>, Box<dyn Error>> {
+        let mut klines = Vec::new();
+        let mut next_start = start_time;
+        while next_start <= end_time {
+            let page = self.klines(symbol, interval, Some(next_start), Some(end_time), PAGE_LIMIT).await?;
+            let Some(last) = page.last() else {
+                break;
+            };
+            next_start = last.open_time + 1;
+            let full = page.len() == PAGE_LIMIT as usize;
+            klines.extend(page);
+            if !full {
+                break;
+            }
+        }
+        Ok(klines)
+    }
+
+    /// 单页历史资金费率（仅合约）
+    pub async fn funding_rates(
+        &self,
+        symbol: &str,
+        start_time: Option<u64>,
+        end_time: Option<u64>,
+        limit: u32,
+    ) -> Result<Vec<FundingRate>, Box<dyn Error>> {
+        self.require_futures("fundingRate")?;
+        let mut query = vec![("symbol", symbol.to_uppercase()), ("limit", limit.to_string())];
+        if let Some(start_time) = start_time {
+            query.push(("startTime", start_time.to_string()));
+        }
+        if let Some(end_time) = end_time {
+            query.push(("endTime", end_time.to_string()));
+        }
+        self.get(&self.api_path("fundingRate"), &query, 1).await
+    }
+
+    /// 结算时间在 [start_time, end_time] 内的全部资金费率（仅合约）
+    pub async fn funding_rates_range(&self, symbol: &str, start_time: u64, end_time: u64) -> Result<Vec<FundingRate>, Box<dyn Error>> {
+        let mut rates = Vec::new();
+        let mut next_start = start_time;
+        while next_start <= end_time {
+            let page = self.funding_rates(symbol, Some(next_start), Some(end_time), PAGE_LIMIT).await?;
+            let Some(last) = page.last() else {
+                break;
+            };
+            next_start = last.funding_time + 1;
+            let full = page.len() == PAGE_LIMIT as usize;
+            rates.extend(page);
+            if !full {
+                break;
+            }
+        }
+        Ok(rates)
+    }
+
+    /// 当前持仓量（仅合约）
+    pub async fn open_interest(&self, symbol: &str) -> Result<OpenInterest, Box<dyn Error>> {
+        self.require_futures("openInterest")?;
+        let query = [("symbol", symbol.to_uppercase())];
+        self.get(&self.api_path("openInterest"), &query, 1).await
+    }
+
+    /// 历史持仓量统计（仅 U 本位合约，交易所只保留最近 30 天）。
+    /// period 支持 5m、15m、30m、1h、2h、4h、6h、12h、1d
+    pub async fn open_interest_hist(
+        &self,
+        symbol: &str,
+        period: KlineInterval,
+        start_time: Option<u64>,
+        end_time: Option<u64>,
+        limit: u32,
+    ) -> Result<Vec<OpenInterestHist>, Box<dyn Error>> {
+        if self.market != RestMarket::UsdFutures {
+            return Err("持仓量历史目前只支持 U 本位合约".into());
+        }
+        let mut query = vec![
+            ("symbol", symbol.to_uppercase()),
+            ("period", period.as_str().to_string()),
+            ("limit", limit.to_string()),
+        ];
+        if let Some(start_time) = start_time {
+            query.push(("startTime", start_time.to_string()));
+        }
+        if let Some(end_time) = end_time {
+            query.push(("endTime", end_time.to_string()));
+        }
+        self.get("/futures/data/openInterestHist", &query, 1).await
+    }
+
+    /// 统计时间在 [start_time, end_time] 内的全部持仓量历史
+    pub async fn open_interest_hist_range(
+        &self,
+        symbol: &str,
+        period: KlineInterval,
+        start_time: u64,
+        end_time: u64,
+    ) -> Result<Vec<OpenInterestHist>, Box<dyn Error>> {
+        let mut records = Vec::new();
+        let mut next_start = start_time;
+        while next_start <= end_time {
+            let page = self.open_interest_hist(symbol, period, Some(next_start), Some(end_time), OI_HIST_PAGE_LIMIT).await?;
+            let Some(last) = page.last() else {
+                break;
+            };
+            next_start = last.timestamp + 1;
+            let full = page.len() == OI_HIST_PAGE_LIMIT as usize;
+            records.extend(page);
+            if !full {
+                break;
+            }
+        }
+        Ok(records)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock::{query_param, MockResponse, MockServer};
+
+    /// 归集成交 mock：从 fromId 开始返回 limit 条，id 不超过 last_id
+    fn agg_trades_page(target: &str, last_id: u64) -> MockResponse {
+        let from_id: u64 = query_param(target, "fromId").unwrap().parse().unwrap();
+        let limit: u64 = query_param(target, "limit").unwrap().parse().unwrap();
+        let trades: Vec<String> = (from_id..(from_id + limit).min(last_id + 1))
+            .map(|id| format!(r#"{{"a":{},"p":"100","q":"1","f":{},"l":{},"T":{},"m":true}}"#, id, id, id, id * 10))
+            .collect();
+        MockResponse::ok(format!("[{}]", trades.join(",")))
+    }
+
+    fn kline_row(open_time: u64) -> String {
+        format!(r#"[{},"1","2","0.5","1.5","10",{},"15",3,"5","7","0"]"#, open_time, open_time + 59_999)
+    }
+
+    #[tokio::test]
+    async fn agg_trades_by_id_pages_through_the_range() {
+        let server = MockServer::start(|target, _| agg_trades_page(target, 1_000_000)).await;
+        let client = BinanceRestClient::with_base_url(RestMarket::UsdFutures, &server.base_url);
+        let trades = client.agg_trades_by_id("btcusdt", 1, 2500).await.unwrap();
+
+        assert_eq!(trades.len(), 2500);
+        assert_eq!(trades.first().unwrap().agg_trade_id, 1);
+        assert_eq!(trades.last().unwrap().agg_trade_id, 2500);
+        assert!(trades.iter().all(|t| t.symbol == "BTCUSDT"));
+
+        let requests = server.requests();
+        let pages: Vec<(String, String)> = requests
+            .iter()
+            .map(|r| (query_param(r, "fromId").unwrap(), query_param(r, "limit").unwrap()))
+            .collect();
+        let expected = [("1", "1000"), ("1001", "1000"), ("2001", "500")];
+        assert_eq!(pages, expected.map(|(a, b)| (a.to_string(), b.to_string())));
+        assert!(requests[0].starts_with("/fapi/v1/aggTrades?"));
+    }
+
+    #[tokio::test]
+    async fn agg_trades_by_id_stops_when_exchange_runs_dry() {
+        // 交易所只有到 1500 的成交，之后返回空页
+        let server = MockServer::start(|target, _| agg_trades_page(target, 1500)).await;
+        let client = BinanceRestClient::with_base_url(RestMarket::UsdFutures, &server.base_url);
+        let trades = client.agg_trades_by_id("BTCUSDT", 1, 3000).await.unwrap();
+        assert_eq!(trades.len(), 1500);
+        assert_eq!(server.requests().len(), 3);
+    }
+
+    #[tokio::test]
+    async fn klines_range_stops_on_partial_page() {
+        let server = MockServer::start(|target, index| {
+            let start: u64 = query_param(target, "startTime").unwrap().parse().unwrap();
+            let count = if index == 0 { PAGE_LIMIT as u64 } else { 3 };
+            let rows: Vec<String> = (0..count).map(|i| kline_row(start + i * 60_000)).collect();
+            MockResponse::ok(format!("[{}]", rows.join(",")))
+        })
+        .await;
+        let client = BinanceRestClient::with_base_url(RestMarket::UsdFutures, &server.base_url);
+        let klines = client.klines_range("BTCUSDT", KlineInterval::Minute1, 0, u64::MAX / 2).await.unwrap();
+
+        assert_eq!(klines.len(), PAGE_LIMIT as usize + 3);
+        let requests = server.requests();
+        assert_eq!(requests.len(), 2);
+        // 下一页从上一页最后一根的开盘时间之后开始
+        let last_of_first = (PAGE_LIMIT as u64 - 1) * 60_000;
+        assert_eq!(query_param(&requests[1], "startTime"), Some((last_of_first + 1).to_string()));
+    }
+
+    #[tokio::test]
+    async fn futures_only_endpoints_reject_spot() {
+        let client = BinanceRestClient::with_base_url(RestMarket::Spot, "http://127.0.0.1:1");
+        assert!(client.funding_rates("BTCUSDT", None, None, 10).await.is_err());
+        assert!(client.open_interest("BTCUSDT").await.is_err());
+        assert!(client.open_interest_hist("BTCUSDT", KlineInterval::Minute5, None, None, 10).await.is_err());
+    }
+
+    #[test]
+    fn request_weights_follow_limits() {
+        let futures = BinanceRestClient::with_base_url(RestMarket::UsdFutures, "http://127.0.0.1:1");
+        let spot = BinanceRestClient::with_base_url(RestMarket::Spot, "http://127.0.0.1:1");
+        assert_eq!(futures.depth_weight(50), 2);
+        assert_eq!(futures.depth_weight(1000), 20);
+        assert_eq!(spot.depth_weight(100), 5);
+        assert_eq!(spot.depth_weight(5000), 250);
+        assert_eq!(futures.klines_weight(1000), 5);
+        assert_eq!(spot.klines_weight(1000), 2);
+        assert_eq!(futures.agg_trades_weight(), 20);
+        assert_eq!(spot.agg_trades_weight(), 4);
+    }
+}
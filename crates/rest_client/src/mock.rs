@@ -0,0 +1,89 @@
+// rest_client/mock.rs
+
+// 测试用的本地 HTTP mock：按请求顺序调用处理函数生成响应，并记录收到的请求行
+
+use std::sync::{Arc, Mutex};
+
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpListener;
+
+/// mock 的一条响应
+pub(crate) struct MockResponse {
+    pub status: u16,
+    pub headers: Vec<(&'static str, String)>,
+    pub body: String,
+}
+
+impl MockResponse {
+    pub fn ok(body: impl Into<String>) -> Self {
+        Self { status: 200, headers: Vec::new(), body: body.into() }
+    }
+
+    pub fn status(status: u16) -> Self {
+        Self { status, headers: Vec::new(), body: r#"{"code":-1,"msg":"mock"}"#.to_string() }
+    }
+
+    pub fn header(mut self, name: &'static str, value: impl ToString) -> Self {
+        self.headers.push((name, value.to_string()));
+        self
+    }
+}
+
+pub(crate) struct MockServer {
+    pub base_url: String,
+    requests: Arc<Mutex<Vec<String>>>,
+}
+
+impl MockServer {
+    /// 启动 mock，handler 的参数为请求目标（路径加查询串）与该请求的序号（从 0 开始）
+    pub async fn start<F>(handler: F) -> Self
+    where
+        F: Fn(&str, usize) -> MockResponse + Send + Sync + 'static,
+    {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let base_url = format!("http://{}", listener.local_addr().unwrap());
+        let requests = Arc::new(Mutex::new(Vec::new()));
+        let recorded = requests.clone();
+        let handler = Arc::new(handler);
+        tokio::spawn(async move {
+            while let Ok((mut socket, _)) = listener.accept().await {
+                let recorded = recorded.clone();
+                let handler = handler.clone();
+                tokio::spawn(async move {
+                    let mut buf = [0u8; 4096];
+                    let n = socket.read(&mut buf).await.unwrap_or(0);
+                    let request = String::from_utf8_lossy(&buf[..n]).to_string();
+                    let target = request.split_whitespace().nth(1).unwrap_or("").to_string();
+                    let index = {
+                        let mut recorded = recorded.lock().unwrap();
+                        recorded.push(target.clone());
+                        recorded.len() - 1
+                    };
+                    let response = handler(&target, index);
+                    let mut head = format!("HTTP/1.1 {} MOCK\r\nContent-Type: application/json\r\n", response.status);
+                    for (name, value) in &response.headers {
+                        head.push_str(&format!("{}: {}\r\n", name, value));
+                    }
+                    let text = format!("{}Content-Length: {}\r\nConnection: close\r\n\r\n{}", head, response.body.len(), response.body);
+                    let _ = socket.write_all(text.as_bytes()).await;
+                });
+            }
+        });
+        Self { base_url, requests }
+    }
+
+    /// 已收到的请求目标
+    pub fn requests(&self) -> Vec<String> {
+        self.requests.lock().unwrap().clone()
+    }
+}
+
+/// 请求目标中某个查询参数的值
+pub(crate) fn query_param(target: &str, name: &str) -> Option<String> {
+    let query = target.split_once('?')?.1;
+    query
+        .split('&')
+        .filter_map(|pair| pair.split_once('='))
+        .find(|(key, _)| *key == name)
+        .map(|(_, value)| value.to_string())
+}
@@ -0,0 +1,112 @@
+// rest_client/types.rs
+
+// REST 行情接口的响应类型；归集成交直接复用 event_engine 的 AggTradeEvent
+
+use serde::de::IgnoredAny;
+use serde::{Deserialize, Deserializer, Serialize};
+use serde_json::Value;
+
+/// 部分接口的时间戳有时为字符串，统一解析为整数
+fn de_u64<'de, D: Deserializer<'de>>(deserializer: D) -> Result<u64, D::Error> {
+    match Value::deserialize(deserializer)? {
+        Value::Number(n) => n.as_u64().ok_or_else(|| serde::de::Error::custom("时间戳不是非负整数")),
+        Value::String(s) => s.parse().map_err(serde::de::Error::custom),
+        other => Err(serde::de::Error::custom(format!("无法解析的时间戳: {}", other))),
+    }
+}
+
+/// 深度快照，对应 GET depth
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct Depth {
+    #[serde(rename = "lastUpdateId")]
+    pub last_update_id: u64,
+
+    #[serde(rename = "E", default)]
+    pub event_time: Option<u64>,     // 消息时间，仅合约返回
+
+    #[serde(rename = "T", default)]
+    pub match_time: Option<u64>,     // 撮合时间，仅合约返回
+
+    pub bids: Vec<(String, String)>, // [价格, 数量]
+    pub asks: Vec<(String, String)>, // [价格, 数量]
+}
+
+/// 交易所返回的原始 K 线数组，最后一个字段已废弃
+type KlineRow = (u64, String, String, String, String, String, u64, String, u64, String, String, IgnoredAny);
+
+/// K 线，对应 GET klines
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(from = "KlineRow")]
+pub struct Kline {
+    pub open_time: u64,              // 开盘时间
+    pub open: String,                // 开盘价
+    pub high: String,                // 最高价
+    pub low: String,                 // 最低价
+    pub close: String,               // 收盘价
+    pub volume: String,              // 成交量（币本位合约为张数）
+    pub close_time: u64,             // 收盘时间
+    pub quote_volume: String,        // 成交额（币本位合约为币数）
+    pub trade_count: u64,            // 成交笔数
+    pub taker_buy_volume: String,    // 主动买入成交量
+    pub taker_buy_quote_volume: String, // 主动买入成交额
+}
+
+impl From<KlineRow> for Kline {
+    fn from(row: KlineRow) -> Self {
+        let (open_time, open, high, low, close, volume, close_time, quote_volume, trade_count, taker_buy_volume, taker_buy_quote_volume, _) = row;
+        Self {
+            open_time,
+            open,
+            high,
+            low,
+            close,
+            volume,
+            close_time,
+            quote_volume,
+            trade_count,
+            taker_buy_volume,
+            taker_buy_quote_volume,
+        }
+    }
+}
+
+/// 历史资金费率，对应 GET fundingRate
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct FundingRate {
+    pub symbol: String,              // 交易对
+
+    #[serde(rename = "fundingRate")]
+    pub funding_rate: String,        // 资金费率
+
+    #[serde(rename = "fundingTime")]
+    pub funding_time: u64,           // 结算时间
+
+    #[serde(rename = "markPrice", default)]
+    pub mark_price: String,          // 结算时的标记价格，较早的记录为空
+}
+
+/// 当前持仓量，对应 GET openInterest
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct OpenInterest {
+    pub symbol: String,              // 交易对
+
+    #[serde(rename = "openInterest")]
+    pub open_interest: String,       // 持仓量（币本位合约为张数）
+
+    pub time: u64,                   // 撮合引擎时间
+}
+
+/// 历史持仓量统计，对应 GET /futures/data/openInterestHist
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct OpenInterestHist {
+    pub symbol: String,              // 交易对
+
+    #[serde(rename = "sumOpenInterest")]
+    pub sum_open_interest: String,   // 持仓总数量
+
+    #[serde(rename = "sumOpenInterestValue")]
+    pub sum_open_interest_value: String, // 持仓总价值
+
+    #[serde(deserialize_with = "de_u64")]
+    pub timestamp: u64,              // 统计时间
+}